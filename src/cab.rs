@@ -0,0 +1,247 @@
+// Reads Microsoft Cabinet archives, which .onepkg notebook packages are. Only single cabinet
+// archives with uncompressed or MSZIP folders are supported. See MS-CAB
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::error::{Error, Result};
+
+const SIGNATURE: u32 = 0x4643534D;
+
+// CFHEADER flags
+const FLAG_PREV_CABINET: u16 = 0x1;
+const FLAG_NEXT_CABINET: u16 = 0x2;
+const FLAG_RESERVE_PRESENT: u16 = 0x4;
+
+const COMPRESS_MASK: u16 = 0xF;
+const COMPRESS_NONE: u16 = 0x0;
+const COMPRESS_MSZIP: u16 = 0x1;
+
+// iFolder values of files that continue from or into another cabinet
+const IFOLD_CONTINUED: u16 = 0xFFFD;
+
+const ATTRIBUTE_NAME_IS_UTF: u16 = 0x80;
+
+// Every MSZIP block starts with "CK" and expands to at most 32KB
+const MSZIP_SIGNATURE: [u8; 2] = *b"CK";
+const MSZIP_BLOCK_SIZE: usize = 0x8000;
+
+// A CFFOLDER: a run of CFDATA blocks compressed as one stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct CabFolder {
+    pub data_offset: u64,
+    pub data_blocks: u16,
+    pub compression: u16
+}
+
+// A CFFILE: a file stored somewhere in a folder's uncompressed stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct CabFile {
+    // Relative, with the archive's backslashes turned into path separators
+    pub path: PathBuf,
+    pub folder: u16,
+    pub folder_offset: u32,
+    pub len: u32,
+    pub attributes: u16
+}
+
+// The directory of a cabinet. File contents are only read by extract
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cabinet {
+    pub folders: Vec<CabFolder>,
+    pub files: Vec<CabFile>,
+    // Size of the reserved area at the start of every CFDATA block
+    data_reserve_size: u8
+}
+
+impl Cabinet {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<Cabinet> {
+        let start = reader.stream_position()?;
+        let signature = reader.read_u32::<LittleEndian>()?;
+        if signature != SIGNATURE {
+            return Err(Error::BadMagic { structure: "CFHEADER", offset: start, found: signature.into() });
+        }
+        reader.read_u32::<LittleEndian>()?;
+        // cbCabinet
+        reader.read_u32::<LittleEndian>()?;
+        reader.read_u32::<LittleEndian>()?;
+        let files_offset = reader.read_u32::<LittleEndian>()?;
+        reader.read_u32::<LittleEndian>()?;
+        let version_minor = reader.read_u8()?;
+        let version_major = reader.read_u8()?;
+        if (version_major, version_minor) != (1, 3) {
+            return Err(Error::Unsupported { structure: "CFHEADER", offset: start, feature: "cabinet version" });
+        }
+        let folder_count = reader.read_u16::<LittleEndian>()?;
+        let file_count = reader.read_u16::<LittleEndian>()?;
+        let flags = reader.read_u16::<LittleEndian>()?;
+        // setID and iCabinet
+        reader.read_u16::<LittleEndian>()?;
+        reader.read_u16::<LittleEndian>()?;
+
+        if flags & (FLAG_PREV_CABINET | FLAG_NEXT_CABINET) != 0 {
+            return Err(Error::Unsupported { structure: "CFHEADER", offset: start, feature: "archives split over several cabinets" });
+        }
+
+        let (mut folder_reserve_size, mut data_reserve_size) = (0, 0);
+        if flags & FLAG_RESERVE_PRESENT != 0 {
+            let header_reserve_size = reader.read_u16::<LittleEndian>()?;
+            folder_reserve_size = reader.read_u8()?;
+            data_reserve_size = reader.read_u8()?;
+            reader.seek(SeekFrom::Current(header_reserve_size.into()))?;
+        }
+
+        let mut folders = Vec::new();
+        for _ in 0..folder_count {
+            let data_offset = start + u64::from(reader.read_u32::<LittleEndian>()?);
+            let data_blocks = reader.read_u16::<LittleEndian>()?;
+            let compression = reader.read_u16::<LittleEndian>()?;
+            reader.seek(SeekFrom::Current(folder_reserve_size.into()))?;
+            folders.push(CabFolder { data_offset, data_blocks, compression });
+        }
+
+        reader.seek(SeekFrom::Start(start + u64::from(files_offset)))?;
+        let mut files = Vec::new();
+        for _ in 0..file_count {
+            let offset = reader.stream_position()?;
+            let len = reader.read_u32::<LittleEndian>()?;
+            let folder_offset = reader.read_u32::<LittleEndian>()?;
+            let folder = reader.read_u16::<LittleEndian>()?;
+            // Date and time
+            reader.read_u16::<LittleEndian>()?;
+            reader.read_u16::<LittleEndian>()?;
+            let attributes = reader.read_u16::<LittleEndian>()?;
+            let name = read_name(reader, attributes & ATTRIBUTE_NAME_IS_UTF != 0)?;
+
+            if folder >= IFOLD_CONTINUED {
+                return Err(Error::Unsupported { structure: "CFFILE", offset, feature: "files split over several cabinets" });
+            }
+            if usize::from(folder) >= folders.len() {
+                return Err(Error::InvalidData { structure: "CFFILE", offset, reason: "File is in a folder that doesn't exist" });
+            }
+            let path = relative_path(&name)
+                .ok_or(Error::InvalidData { structure: "CFFILE", offset, reason: "File name isn't a relative path" })?;
+            files.push(CabFile { path, folder, folder_offset, len, attributes });
+        }
+
+        Ok(Cabinet { folders, files, data_reserve_size })
+    }
+
+    // Reads every file, decompressing each folder once. Returned in the same order as files
+    pub fn extract<T: Read + Seek>(&self, reader: &mut T) -> Result<Vec<Vec<u8>>> {
+        let mut contents = vec![Vec::new(); self.files.len()];
+        for (index, folder) in self.folders.iter().enumerate() {
+            let in_folder: Vec<usize> = (0..self.files.len()).filter(|&file| usize::from(self.files[file].folder) == index).collect();
+            if in_folder.is_empty() {
+                continue;
+            }
+
+            let stream = self.read_folder(folder, reader)?;
+            for file in in_folder {
+                let entry = &self.files[file];
+                let start = entry.folder_offset as usize;
+                contents[file] = stream.get(start..start + entry.len as usize)
+                    .ok_or(Error::InvalidData { structure: "CFFILE", offset: folder.data_offset, reason: "File extends past the end of its folder" })?
+                    .to_vec();
+            }
+        }
+        Ok(contents)
+    }
+
+    // The uncompressed stream of a folder
+    fn read_folder<T: Read + Seek>(&self, folder: &CabFolder, reader: &mut T) -> Result<Vec<u8>> {
+        let compression = folder.compression & COMPRESS_MASK;
+        if compression != COMPRESS_NONE && compression != COMPRESS_MSZIP {
+            return Err(Error::Unsupported { structure: "CFFOLDER", offset: folder.data_offset, feature: "compression type" });
+        }
+
+        reader.seek(SeekFrom::Start(folder.data_offset))?;
+        let mut stream = Vec::new();
+        for _ in 0..folder.data_blocks {
+            let offset = reader.stream_position()?;
+            let checksum = reader.read_u32::<LittleEndian>()?;
+            let mut sizes = [0; 4];
+            reader.read_exact(&mut sizes)?;
+            let compressed_len = u16::from_le_bytes([sizes[0], sizes[1]]);
+            let uncompressed_len = usize::from(u16::from_le_bytes([sizes[2], sizes[3]]));
+            reader.seek(SeekFrom::Current(self.data_reserve_size.into()))?;
+            let mut data = vec![0; compressed_len.into()];
+            reader.read_exact(&mut data)?;
+
+            // A checksum of 0 means none was computed. It covers the data, then cbData and cbUncomp
+            if checksum != 0 {
+                let actual = checksum_of(&sizes, checksum_of(&data, 0));
+                if actual != checksum {
+                    return Err(Error::CrcMismatch { structure: "CFDATA", offset, expected: checksum, actual });
+                }
+            }
+
+            if compression == COMPRESS_NONE {
+                if data.len() != uncompressed_len {
+                    return Err(Error::InvalidData { structure: "CFDATA", offset, reason: "Uncompressed block sizes don't match" });
+                }
+                stream.extend_from_slice(&data);
+            } else {
+                inflate_block(&data, uncompressed_len, &mut stream, offset)?;
+            }
+        }
+        Ok(stream)
+    }
+}
+
+// Each MSZIP block is a separate deflate stream, but may refer back into the blocks before it, so
+// it is inflated straight onto the end of the folder's stream
+fn inflate_block(data: &[u8], uncompressed_len: usize, stream: &mut Vec<u8>, offset: u64) -> Result<()> {
+    if data.len() < 2 || data[..2] != MSZIP_SIGNATURE {
+        return Err(Error::InvalidData { structure: "CFDATA", offset, reason: "MSZIP block doesn't start with CK" });
+    }
+    if uncompressed_len > MSZIP_BLOCK_SIZE {
+        return Err(Error::InvalidData { structure: "CFDATA", offset, reason: "MSZIP block is larger than 32KB" });
+    }
+
+    let start = stream.len();
+    stream.resize(start + uncompressed_len, 0);
+    let mut decompressor = DecompressorOxide::new();
+    let (status, _, written) = decompress(&mut decompressor, &data[2..], stream, start, inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF);
+    if status != TINFLStatus::Done || written != uncompressed_len {
+        return Err(Error::InvalidData { structure: "CFDATA", offset, reason: "MSZIP block doesn't inflate to its uncompressed size" });
+    }
+    Ok(())
+}
+
+// See MS-CAB 2.6. The bytes left over after the last whole word are taken most significant first
+fn checksum_of(bytes: &[u8], seed: u32) -> u32 {
+    let mut checksum = seed;
+    let mut words = bytes.chunks_exact(4);
+    for word in &mut words {
+        checksum ^= u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    let last = words.remainder().iter().fold(0, |value, byte| (value << 8) | u32::from(*byte));
+    checksum ^ last
+}
+
+fn read_name<T: Read>(reader: &mut T, is_utf8: bool) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match reader.read_u8()? {
+            0 => break,
+            byte => bytes.push(byte)
+        }
+    }
+    // Names without the UTF-8 attribute are in the archiver's code page; only ASCII is certain
+    Ok(if is_utf8 {
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        bytes.iter().map(|&byte| char::from(byte)).collect()
+    })
+}
+
+// None for names that could point outside of the directory the archive is read into
+fn relative_path(name: &str) -> Option<PathBuf> {
+    let path: PathBuf = name.split('\\').filter(|part| !part.is_empty()).collect();
+    let is_relative = !name.starts_with('\\') && path.components().all(|component| matches!(component, Component::Normal(_)));
+    (is_relative && path != Path::new("")).then_some(path)
+}
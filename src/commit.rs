@@ -0,0 +1,324 @@
+// Adds revisions to an existing revision store in place, the way OneNote saves. New chunks go into
+// free chunks or past the end of the file, and lists grow by fragments linked from their last one.
+// Readers only see a list's new nodes once the transaction log counts them, and only see the new
+// transaction once the header does, so the file stays valid until the header is rewritten last.
+// See MS-ONESTORE 2.3.3
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::error::{Error, Result};
+use crate::onestore::{OneStore, StoreFormat};
+use crate::structs::exguid::ExGuid;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::filenode::{FileNode, FileType};
+use crate::structs::filenodedata::*;
+use crate::structs::filenodelist::{FileNodeList, FragmentTail};
+use crate::structs::freechunklist::FreeChunkList;
+use crate::structs::guid::Guid;
+use crate::structs::header::{OneNoteFileHeader, OneNoteFileType, HEADER_SIZE};
+use crate::structs::objectpropset::ObjectSpaceObjectPropSet;
+use crate::structs::transactionlog::{self, LogTail};
+
+const STRUCTURE: &str = "RevisionManifestList";
+
+// Chunks start on 8 byte boundaries, as OneNote lays them out
+const CHUNK_ALIGNMENT: u64 = 8;
+
+// List ids below this are reserved. See MS-ONESTORE 2.4.1
+const FIRST_LIST_ID: u32 = 0x10;
+
+// A new transaction log fragment has room for this many more entries, so later commits can use it
+const SPARE_LOG_ENTRIES: usize = 32;
+
+// A revision to add to an object space. See MS-ONESTORE 2.1.9
+#[derive(Debug, Clone)]
+pub struct NewRevision {
+    pub rid: ExGuid,
+    // Nil, or the revision this one inherits objects from
+    pub rid_dependent: ExGuid,
+    // Nil for the default context
+    pub context: ExGuid,
+    pub revision_role: u32,
+    pub odcs_default: u16,
+    // Each is written as its own list, referenced from the start of the revision manifest
+    pub object_groups: Vec<NewObjectGroup>,
+    // The rest of the revision manifest: global id table, root object references and so on. Data
+    // references must point at chunks written with Commit::write_chunk
+    pub file_nodes: Vec<FileNode>
+}
+
+// See MS-ONESTORE 2.1.13
+#[derive(Debug, Clone)]
+pub struct NewObjectGroup {
+    pub id: ExGuid,
+    // The nodes between ObjectGroupStart and ObjectGroupEnd
+    pub file_nodes: Vec<FileNode>
+}
+
+// One transaction against a store that was parsed from file. Nothing is visible until finish.
+pub struct Commit<'a, F> {
+    store: &'a OneStore,
+    file: &'a mut F,
+    free_chunk_list: FreeChunkList,
+    free_chunks_used: bool,
+    end_of_file: u64,
+    log_tail: LogTail,
+    // Node count of every list this transaction changes
+    list_lengths: BTreeMap<u32, u32>,
+    tails: HashMap<u32, FragmentTail>,
+    // Revision manifest list id of each object space added to so far
+    revision_lists: HashMap<ExGuid, u32>,
+    next_list_id: u32,
+    added_revisions: HashSet<(ExGuid, ExGuid)>
+}
+
+impl<'a, F: Read + Write + Seek> Commit<'a, F> {
+    // store must have been parsed from file
+    pub fn new(store: &'a OneStore, file: &'a mut F) -> Result<Commit<'a, F>> {
+        if store.format != StoreFormat::Native {
+            return Err(Error::Unsupported { structure: "OneStore", offset: 0, feature: "committing to packaged stores" });
+        }
+
+        let header = &store.header;
+        let (_, log_tail) = transactionlog::read_with_tail(&header.transaction_log, file, header.transactions_in_log.into())?;
+        // The new transaction is written after the last one read, so every committed one must have
+        // been read. Only a torn transaction at the very end may be overwritten.
+        if log_tail.transactions + 1 < header.transactions_in_log {
+            return Err(Error::InvalidData { structure: "TransactionLogFragment", offset: log_tail.end_of_transactions, reason: "Transaction log holds fewer transactions than the header records" });
+        }
+        let end_of_file = file.seek(SeekFrom::End(0))?;
+        let next_list_id = store.transaction_log.keys().max().map_or(FIRST_LIST_ID, |id| (id + 1).max(FIRST_LIST_ID));
+
+        Ok(Commit {
+            store,
+            file,
+            free_chunk_list: store.free_chunk_list.clone(),
+            free_chunks_used: false,
+            end_of_file,
+            log_tail,
+            list_lengths: BTreeMap::new(),
+            tails: HashMap::new(),
+            revision_lists: HashMap::new(),
+            next_list_id,
+            added_revisions: HashSet::new()
+        })
+    }
+
+    // Writes bytes into the smallest free chunk that holds them, or at the end of the file
+    pub fn write_chunk(&mut self, bytes: &[u8]) -> Result<FileChunkReference> {
+        let len = bytes.len() as u64;
+        let fcr = match self.free_chunk_list.allocate(len.next_multiple_of(CHUNK_ALIGNMENT)) {
+            Some(chunk) => {
+                self.free_chunks_used = true;
+                FileChunkReference { start: chunk.start, len }
+            },
+            None => self.allocate_at_end(len)
+        };
+        self.write_at(fcr.start, bytes)?;
+        Ok(fcr)
+    }
+
+    pub fn write_property_set(&mut self, property_set: &ObjectSpaceObjectPropSet) -> Result<FileChunkReference> {
+        let mut bytes = Vec::new();
+        property_set.to_writer(&mut bytes)?;
+        self.write_chunk(&bytes)
+    }
+
+    // Appends a revision manifest to the revision manifest list of the object space gosid
+    pub fn add_revision(&mut self, gosid: &ExGuid, revision: NewRevision) -> Result<()> {
+        let object_space = self.store.object_space(gosid)
+            .ok_or(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "No object space with this id" })?;
+        let exists = |rid: &ExGuid| object_space.revision(rid).is_some() || self.added_revisions.contains(&(*gosid, *rid));
+        if exists(&revision.rid) {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "Revision id is already in use" });
+        }
+        if !revision.rid_dependent.is_nil() && !exists(&revision.rid_dependent) {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "Revision depends on a revision that doesn't exist" });
+        }
+
+        let list_id = self.revision_list(gosid)?;
+        let mut file_nodes = vec![self.revision_start(&revision)?];
+        for group in revision.object_groups {
+            let start = FileNode::new(FileType::ObjectGroupStart, FileChunkReference::zero(), FileNodeData::ObjectGroupStart(ObjectGroupStartFnd { oid: group.id }));
+            let end = FileNode::new(FileType::ObjectGroupEnd, FileChunkReference::zero(), FileNodeData::ObjectGroupEnd);
+            let group_nodes = std::iter::once(start).chain(group.file_nodes).chain(std::iter::once(end)).collect();
+            let fcr = self.write_list(group_nodes)?;
+
+            let reference = ObjectGroupListReferenceFnd { object_group_id: group.id };
+            file_nodes.push(FileNode::new(FileType::ObjectGroupListReference, fcr, FileNodeData::ObjectGroupListReference(reference)));
+        }
+        file_nodes.extend(revision.file_nodes);
+        file_nodes.push(FileNode::new(FileType::RevisionManifestEnd, FileChunkReference::zero(), FileNodeData::RevisionManifestEnd));
+
+        self.extend_list(list_id, file_nodes)?;
+        self.added_revisions.insert((*gosid, revision.rid));
+        Ok(())
+    }
+
+    // Commits by appending one transaction to the log, then rewriting the header to count it. The
+    // free chunk list is rewritten if chunks were taken from it; its old fragments are not reused.
+    pub fn finish(mut self) -> Result<()> {
+        if self.list_lengths.is_empty() {
+            return Ok(());
+        }
+
+        let entries: Vec<(u32, u32)> = self.list_lengths.iter().map(|(id, len)| (*id, *len)).collect();
+        let mut transaction = Vec::new();
+        transactionlog::write_transaction(&mut transaction, &entries)?;
+        self.write_transaction(&transaction)?;
+
+        let header = &self.store.header;
+        let (free_chunk_list, free_space_in_free_chunk_list) = if !self.free_chunks_used {
+            (header.free_chunk_list, header.free_space_in_free_chunk_list)
+        } else if self.free_chunk_list.free_chunks.is_empty() {
+            (FileChunkReference::nil(), 0)
+        } else {
+            let mut bytes = Vec::new();
+            self.free_chunk_list.to_writer(&mut bytes)?;
+            let fcr = self.allocate_at_end(bytes.len() as u64);
+            self.write_at(fcr.start, &bytes)?;
+            (fcr, self.free_chunk_list.total_len())
+        };
+        self.file.flush()?;
+
+        let header = OneNoteFileHeader {
+            transactions_in_log: self.log_tail.transactions + 1,
+            free_chunk_list,
+            free_space_in_free_chunk_list,
+            expected_file_length: self.end_of_file.max(header.expected_file_length),
+            file_version: Guid::new_v4(),
+            file_version_generation: header.file_version_generation + 1,
+            ..header.clone()
+        };
+        let mut bytes = Vec::with_capacity(HEADER_SIZE as usize);
+        header.to_writer(&mut bytes)?;
+        self.write_at(0, &bytes)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn revision_start(&self, revision: &NewRevision) -> Result<FileNode> {
+        let base = RevisionManifestStart6Fnd {
+            rid: revision.rid,
+            rid_dependent: revision.rid_dependent,
+            revision_role: revision.revision_role,
+            odcs_default: revision.odcs_default
+        };
+        let zero = FileChunkReference::zero();
+
+        // .onetoc2 files use the older start node, which has no context
+        Ok(match (self.store.header.file_type, revision.context.is_nil()) {
+            (OneNoteFileType::OneToc2, true) => {
+                let start = RevisionManifestStart4Fnd { rid: base.rid, rid_dependent: base.rid_dependent, time_creation: 0, revision_role: base.revision_role, odcs_default: base.odcs_default };
+                FileNode::new(FileType::RevisionManifestStart4, zero, FileNodeData::RevisionManifestStart4(start))
+            },
+            (OneNoteFileType::OneToc2, false) => {
+                return Err(Error::Unsupported { structure: STRUCTURE, offset: 0, feature: "revision contexts in .onetoc2 files" });
+            },
+            (OneNoteFileType::One, true) => FileNode::new(FileType::RevisionManifestStart6, zero, FileNodeData::RevisionManifestStart6(base)),
+            (OneNoteFileType::One, false) => {
+                let start = RevisionManifestStart7Fnd { base, gctxid: revision.context };
+                FileNode::new(FileType::RevisionManifestStart7, zero, FileNodeData::RevisionManifestStart7(start))
+            }
+        })
+    }
+
+    // The id of the current revision manifest list of an object space, reading where it ends the
+    // first time. See MS-ONESTORE 2.1.6
+    fn revision_list(&mut self, gosid: &ExGuid) -> Result<u32> {
+        if let Some(list_id) = self.revision_lists.get(gosid) {
+            return Ok(*list_id);
+        }
+
+        let manifest_list_ref = self.store.root_file_node_list.file_nodes.iter()
+            .find(|node| matches!(&node.data, FileNodeData::ObjectSpaceManifestListReference(reference) if reference.gosid == *gosid))
+            .ok_or(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "No object space with this id" })?;
+        let manifest_list = FileNodeList::from_reader(&manifest_list_ref.file_chunk_ref, self.file, &self.store.transaction_log)?;
+        let revision_list_ref = manifest_list.file_nodes.iter()
+            .rev()
+            .find(|node| matches!(node.data, FileNodeData::RevisionManifestListReference))
+            .ok_or(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: manifest_list_ref.file_chunk_ref.start, reason: "No revision manifest list reference" })?;
+        let (list, tail) = FileNodeList::from_reader_with_tail(&revision_list_ref.file_chunk_ref, self.file, &self.store.transaction_log)?;
+
+        self.tails.insert(list.id, tail);
+        self.list_lengths.entry(list.id).or_insert(list.file_nodes.len() as u32);
+        self.revision_lists.insert(*gosid, list.id);
+        Ok(list.id)
+    }
+
+    // Writes a new list in one fragment
+    fn write_list(&mut self, file_nodes: Vec<FileNode>) -> Result<FileChunkReference> {
+        let id = self.next_list_id;
+        self.next_list_id += 1;
+
+        let len = file_nodes.len() as u32;
+        let fcr = self.write_fragment(FileNodeList { id, fragment_sequence_index: 0, file_nodes })?;
+        self.list_lengths.insert(id, len);
+        Ok(fcr)
+    }
+
+    // Writes file_nodes as a new fragment and links it from the list's last one
+    fn extend_list(&mut self, list_id: u32, file_nodes: Vec<FileNode>) -> Result<()> {
+        let tail = self.tails[&list_id];
+        let len = file_nodes.len() as u32;
+        let fcr = self.write_fragment(FileNodeList { id: list_id, fragment_sequence_index: tail.sequence_index + 1, file_nodes })?;
+
+        // Readers look for more nodes in whatever space is left after the last one, so a
+        // ChunkTerminator sends them on to nextFragment
+        let next_fragment_position = tail.next_fragment_position();
+        if next_fragment_position - tail.end_of_file_nodes >= 4 {
+            let mut terminator = Vec::new();
+            FileNode::new(FileType::ChunkTerminator, FileChunkReference::zero(), FileNodeData::ChunkTerminator).to_writer(&mut terminator)?;
+            self.write_at(tail.end_of_file_nodes, &terminator)?;
+        }
+        let mut next_fragment = Vec::new();
+        fcr.to_writer(&mut next_fragment, 64, 32)?;
+        self.write_at(next_fragment_position, &next_fragment)?;
+
+        *self.list_lengths.get_mut(&list_id).unwrap() += len;
+        Ok(())
+    }
+
+    fn write_fragment(&mut self, list: FileNodeList) -> Result<FileChunkReference> {
+        let mut bytes = Vec::new();
+        list.to_writer(&mut bytes, &FileChunkReference::nil())?;
+        let fcr = self.write_chunk(&bytes)?;
+
+        let mut tail = FragmentTail { fragment: fcr, sequence_index: list.fragment_sequence_index, end_of_file_nodes: 0 };
+        tail.end_of_file_nodes = tail.next_fragment_position();
+        self.tails.insert(list.id, tail);
+        Ok(fcr)
+    }
+
+    // Entries that don't fit after the last transaction continue in a new fragment
+    fn write_transaction(&mut self, transaction: &[u8]) -> Result<()> {
+        let tail = self.log_tail;
+        let room = ((tail.next_fragment_position() - tail.end_of_transactions) as usize).min(transaction.len());
+        let room = room - room % 8;
+        let (in_place, rest) = transaction.split_at(room);
+
+        if !rest.is_empty() {
+            let mut fragment = rest.to_vec();
+            fragment.resize(rest.len() + SPARE_LOG_ENTRIES * 8, 0);
+            FileChunkReference::nil().to_writer(&mut fragment, 64, 32)?;
+            let fcr = self.write_chunk(&fragment)?;
+
+            let mut next_fragment = Vec::new();
+            fcr.to_writer(&mut next_fragment, 64, 32)?;
+            self.write_at(tail.next_fragment_position(), &next_fragment)?;
+        }
+        self.write_at(tail.end_of_transactions, in_place)
+    }
+
+    fn allocate_at_end(&mut self, len: u64) -> FileChunkReference {
+        let start = self.end_of_file.next_multiple_of(CHUNK_ALIGNMENT);
+        self.end_of_file = start + len;
+        FileChunkReference { start, len }
+    }
+
+    fn write_at(&mut self, position: u64, bytes: &[u8]) -> Result<()> {
+        self.file.seek(SeekFrom::Start(position))?;
+        self.file.write_all(bytes)?;
+        Ok(())
+    }
+}
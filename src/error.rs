@@ -0,0 +1,81 @@
+use std::fmt;
+use std::io;
+
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::guid::Guid;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Every error carries the name of the structure being parsed and the byte offset it was found at
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    BadMagic { structure: &'static str, offset: u64, found: u64 },
+    BadGuid { structure: &'static str, offset: u64, found: Guid },
+    CrcMismatch { structure: &'static str, offset: u64, expected: u32, actual: u32 },
+    UnknownFileNodeId { offset: u64, id: u16 },
+    OutOfBounds { structure: &'static str, offset: u64, fcr: FileChunkReference },
+    Unsupported { structure: &'static str, offset: u64, feature: &'static str },
+    InvalidData { structure: &'static str, offset: u64, reason: &'static str }
+}
+
+impl Error {
+    // True if the file ended before a structure could be fully read
+    pub fn is_truncated(&self) -> bool {
+        match self {
+            Error::Io(e) => e.kind() == io::ErrorKind::UnexpectedEof,
+            Error::OutOfBounds { .. } => true,
+            _ => false
+        }
+    }
+
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Io(_) => None,
+            Error::BadMagic { offset, .. }
+            | Error::BadGuid { offset, .. }
+            | Error::CrcMismatch { offset, .. }
+            | Error::UnknownFileNodeId { offset, .. }
+            | Error::OutOfBounds { offset, .. }
+            | Error::Unsupported { offset, .. }
+            | Error::InvalidData { offset, .. } => Some(*offset)
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::BadMagic { structure, offset, found } =>
+                write!(f, "{} at offset {:#x}: bad magic {:#x}", structure, offset, found),
+            Error::BadGuid { structure, offset, found } =>
+                write!(f, "{} at offset {:#x}: unexpected GUID {{{}}}", structure, offset, found),
+            Error::CrcMismatch { structure, offset, expected, actual } =>
+                write!(f, "{} at offset {:#x}: CRC mismatch (expected {:#010x}, computed {:#010x})", structure, offset, expected, actual),
+            Error::UnknownFileNodeId { offset, id } =>
+                write!(f, "FileNode at offset {:#x}: unknown file node id {:#05x}", offset, id),
+            Error::OutOfBounds { structure, offset, fcr } =>
+                write!(f, "{} at offset {:#x}: file chunk reference {:#x}+{:#x} is outside of the file", structure, offset, fcr.start, fcr.len),
+            Error::Unsupported { structure, offset, feature } =>
+                write!(f, "{} at offset {:#x}: unsupported {}", structure, offset, feature),
+            Error::InvalidData { structure, offset, reason } =>
+                write!(f, "{} at offset {:#x}: {}", structure, offset, reason)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,365 @@
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::onenote::{Content, EmbeddedFile, Image, Outline, OutlineElement, OutlineItem, Page, PageContent, RichText, Section, Table, TextRun};
+use crate::onestore::OneStore;
+
+use super::{create_directory, is_safe_link, page_file_stem, DataUris, Resources, SidecarFiles, LINE_BREAK};
+
+// Page coordinates are in half-inch increments; browsers use 96 pixels per inch
+const PIXELS_PER_UNIT: f32 = 48.0;
+
+const STYLE: &str = "body { margin: 0; font-family: Calibri, sans-serif; font-size: 11pt; }
+.page { position: relative; margin: 24px; }
+.title { border-bottom: 1px solid #c0c0c0; }
+.outline p, .outline h1, .outline h2, .outline h3, .outline h4, .outline h5, .outline h6 { margin: 0; }
+.outline ul, .outline ol { margin: 0; padding-left: 24px; }
+.children { margin-left: 24px; }
+table { border-collapse: collapse; }
+table.borders td { border: 1px solid #a0a0a0; }
+td { padding: 2px 6px; vertical-align: top; }
+";
+
+// Where images and attachments go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceMode {
+    // Inside the page as data: URIs, so every page is a single file. Attachments are only named,
+    // since a page can't link to a data: URI safely
+    Embedded,
+    // Next to the pages in the export directory
+    Sidecar
+}
+
+// Writes one .html file per page into directory. Returns the paths of the pages in section order.
+pub fn export_section<T: Read + Seek>(section: &Section, store: &OneStore, reader: &mut T, directory: &Path, mode: ResourceMode) -> Result<Vec<PathBuf>> {
+    create_directory(directory)?;
+
+    // Page names are reserved through the sidecar writer even when resources are embedded so they
+    // get the same sanitizing and deduplication
+    let mut sidecar = SidecarFiles::new(store, reader, directory);
+    let mut pages = Vec::new();
+    for (index, page) in section.pages().enumerate() {
+        let path = sidecar.reserve(&format!("{}.html", page_file_stem(page, index)));
+        let html = match mode {
+            ResourceMode::Sidecar => render_page(page, &mut sidecar)?,
+            ResourceMode::Embedded => render_page(page, &mut DataUris::new(store, &mut *sidecar.reader))?
+        };
+        fs::write(&path, html)?;
+        pages.push(path);
+    }
+    Ok(pages)
+}
+
+// A self-contained HTML document that places the page's outlines and images where OneNote shows them
+pub fn render_page(page: &Page, resources: &mut dyn Resources) -> Result<String> {
+    let mut writer = Writer { out: String::new(), resources };
+    let title = page.title_text().unwrap_or_default();
+
+    writer.out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writer.out.push_str(&format!("<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n", escape(&title), STYLE));
+    writer.out.push_str(&format!("<div class=\"page\"{}>\n", style(&[("width", page.width.map(pixels)), ("min-height", page.height.map(pixels))])));
+
+    match &page.title {
+        Some(title_node) => {
+            let position = position(title_node.offset_horizontal, title_node.offset_vertical, None);
+            writer.out.push_str(&format!("<div class=\"title\"{}>\n", style(&position)));
+            for outline in &title_node.contents {
+                writer.write_outline(outline)?;
+            }
+            writer.out.push_str("</div>\n");
+        },
+        None if !title.is_empty() => writer.out.push_str(&format!("<h1 class=\"title\">{}</h1>\n", escape(&title))),
+        None => {}
+    }
+
+    for content in &page.contents {
+        match content {
+            PageContent::Outline(outline) => writer.write_outline(outline)?,
+            PageContent::Image(image) => {
+                let positioned = position(image.offset_horizontal, image.offset_vertical, None);
+                writer.write_image(image, &positioned)?;
+                writer.out.push('\n');
+            },
+            PageContent::EmbeddedFile(file) => {
+                let positioned = position(file.offset_horizontal, file.offset_vertical, None);
+                writer.out.push_str(&format!("<div{}>", style(&positioned)));
+                writer.write_embedded_file(file)?;
+                writer.out.push_str("</div>\n");
+            }
+        }
+    }
+
+    writer.out.push_str("</div>\n</body>\n</html>\n");
+    Ok(writer.out)
+}
+
+struct Writer<'a> {
+    out: String,
+    resources: &'a mut dyn Resources
+}
+
+impl<'a> Writer<'a> {
+    fn write_outline(&mut self, outline: &Outline) -> Result<()> {
+        let position = position(outline.offset_horizontal, outline.offset_vertical, outline.layout_max_width);
+        self.out.push_str(&format!("<div class=\"outline\"{}>\n", style(&position)));
+        self.write_items(&outline.items)?;
+        self.out.push_str("</div>\n");
+        Ok(())
+    }
+
+    // Consecutive list items of the same kind share a list element
+    fn write_items(&mut self, items: &[OutlineItem]) -> Result<()> {
+        let mut open_list: Option<&str> = None;
+
+        for item in items {
+            let element = match item {
+                OutlineItem::Element(element) => element,
+                OutlineItem::Group(group) => {
+                    self.close_list(&mut open_list);
+                    self.out.push_str("<div class=\"children\">\n");
+                    self.write_items(&group.items)?;
+                    self.out.push_str("</div>\n");
+                    continue;
+                }
+            };
+
+            let tag = element.list.as_ref().map(|list| if list.is_numbered() { "ol" } else { "ul" });
+            let restart = element.list.as_ref().and_then(|list| list.restart);
+            if open_list != tag || restart.is_some() {
+                self.close_list(&mut open_list);
+                if let Some(tag) = tag {
+                    match restart {
+                        Some(start) if tag == "ol" => self.out.push_str(&format!("<ol start=\"{}\">\n", start)),
+                        _ => self.out.push_str(&format!("<{}>\n", tag))
+                    }
+                }
+                open_list = tag;
+            }
+
+            if tag.is_some() {
+                self.out.push_str("<li>");
+                self.write_element(element)?;
+                self.out.push_str("</li>\n");
+            } else {
+                self.write_element(element)?;
+            }
+        }
+
+        self.close_list(&mut open_list);
+        Ok(())
+    }
+
+    fn close_list(&mut self, open_list: &mut Option<&str>) {
+        if let Some(tag) = open_list.take() {
+            self.out.push_str(&format!("</{}>\n", tag));
+        }
+    }
+
+    fn write_element(&mut self, element: &OutlineElement) -> Result<()> {
+        for content in &element.contents {
+            match content {
+                Content::RichText(text) => self.write_rich_text(text),
+                Content::Image(image) => {
+                    self.out.push_str("<div>");
+                    self.write_image(image, &[])?;
+                    self.out.push_str("</div>\n");
+                },
+                Content::Table(table) => self.write_table(table)?,
+                Content::EmbeddedFile(file) => {
+                    self.out.push_str("<div>");
+                    self.write_embedded_file(file)?;
+                    self.out.push_str("</div>\n");
+                }
+            }
+        }
+
+        if !element.children.is_empty() {
+            self.out.push_str("<div class=\"children\">\n");
+            self.write_items(&element.children)?;
+            self.out.push_str("</div>\n");
+        }
+        Ok(())
+    }
+
+    fn write_rich_text(&mut self, text: &RichText) {
+        // Built in heading styles keep their meaning
+        let tag = text.paragraph_style.as_ref()
+            .and_then(|style| style.style_id.as_deref())
+            .filter(|id| matches!(*id, "h1" | "h2" | "h3" | "h4" | "h5" | "h6"))
+            .unwrap_or("p");
+
+        self.out.push_str(&format!("<{}>", tag));
+        if text.runs.is_empty() {
+            self.out.push_str(&escape_text(&text.text));
+        }
+
+        let mut runs = text.runs.iter().peekable();
+        while let Some(run) = runs.next() {
+            match &run.hyperlink {
+                Some(url) if is_safe_link(url) => {
+                    self.out.push_str(&format!("<a href=\"{}\">", escape(url)));
+                    self.out.push_str(&run_html(text, run));
+                    while let Some(next) = runs.next_if(|next| next.hyperlink.as_ref() == Some(url)) {
+                        self.out.push_str(&run_html(text, next));
+                    }
+                    self.out.push_str("</a>");
+                },
+                _ => self.out.push_str(&run_html(text, run))
+            }
+        }
+
+        // Keep empty paragraphs from collapsing
+        if text.text.is_empty() {
+            self.out.push_str("<br>");
+        }
+        self.out.push_str(&format!("</{}>\n", tag));
+    }
+
+    fn write_image(&mut self, image: &Image, position: &[(&str, Option<String>)]) -> Result<()> {
+        let link = match &image.data {
+            Some(data) => self.resources.link(data, image.filename.as_deref())?,
+            None => None
+        };
+        let link = match link {
+            Some(link) => link,
+            None => return Ok(())
+        };
+
+        let mut properties = position.to_vec();
+        properties.push(("width", image.width.or(image.layout_max_width).map(pixels)));
+        properties.push(("height", image.height.or(image.layout_max_height).map(pixels)));
+
+        self.out.push_str(&format!("<img class=\"image\" src=\"{}\" alt=\"{}\"{}>",
+            escape(&link), escape(image.alt_text.as_deref().unwrap_or("")), style(&properties)));
+        Ok(())
+    }
+
+    fn write_embedded_file(&mut self, file: &EmbeddedFile) -> Result<()> {
+        let name = file.filename.as_deref().unwrap_or("attachment");
+        let link = match &file.data {
+            Some(data) => self.resources.link(data, file.filename.as_deref())?,
+            None => None
+        };
+
+        match link {
+            Some(link) if is_safe_link(&link) => self.out.push_str(&format!("<a class=\"attachment\" href=\"{}\" download=\"{}\">{}</a>", escape(&link), escape(name), escape(name))),
+            _ => self.out.push_str(&format!("<span class=\"attachment\">{}</span>", escape(name)))
+        }
+        Ok(())
+    }
+
+    fn write_table(&mut self, table: &Table) -> Result<()> {
+        let class = if table.borders_visible { " class=\"borders\"" } else { "" };
+        self.out.push_str(&format!("<table{}>\n", class));
+
+        if !table.column_widths.is_empty() {
+            self.out.push_str("<colgroup>");
+            for width in &table.column_widths {
+                self.out.push_str(&format!("<col{}>", style(&[("width", Some(pixels(*width)))])));
+            }
+            self.out.push_str("</colgroup>\n");
+        }
+
+        for row in &table.rows {
+            self.out.push_str("<tr>\n");
+            for cell in &row.cells {
+                self.out.push_str("<td>\n");
+                let items: Vec<OutlineItem> = cell.contents.iter().cloned().map(OutlineItem::Element).collect();
+                self.write_items(&items)?;
+                self.out.push_str("</td>\n");
+            }
+            self.out.push_str("</tr>\n");
+        }
+
+        self.out.push_str("</table>\n");
+        Ok(())
+    }
+}
+
+fn run_html(text: &RichText, run: &TextRun) -> String {
+    let mut html = escape_text(text.run_text(run));
+    if run.superscript {
+        html = format!("<sup>{}</sup>", html);
+    }
+    if run.subscript {
+        html = format!("<sub>{}</sub>", html);
+    }
+
+    let decoration = match (run.underline, run.strikethrough) {
+        (true, true) => Some("underline line-through".to_string()),
+        (true, false) => Some("underline".to_string()),
+        (false, true) => Some("line-through".to_string()),
+        (false, false) => None
+    };
+    let properties = [
+        ("font-weight", run.bold.then(|| "bold".to_string())),
+        ("font-style", run.italic.then(|| "italic".to_string())),
+        ("text-decoration", decoration),
+        ("font-family", run.font.as_ref().map(|font| format!("'{}'", font.replace('\'', "")))),
+        ("font-size", run.size.map(|size| format!("{}pt", size))),
+        ("color", run.color.map(css_color)),
+        ("background-color", run.highlight.map(css_color))
+    ];
+
+    let style = style(&properties);
+    if style.is_empty() {
+        html
+    } else {
+        format!("<span{}>{}</span>", style, html)
+    }
+}
+
+// Offsets are only known for elements placed directly on the page; everything else flows normally
+fn position(left: Option<f32>, top: Option<f32>, width: Option<f32>) -> Vec<(&'static str, Option<String>)> {
+    let absolute = left.is_some() || top.is_some();
+    vec![
+        ("position", absolute.then(|| "absolute".to_string())),
+        ("left", left.map(pixels)),
+        ("top", top.map(pixels)),
+        ("width", width.map(pixels))
+    ]
+}
+
+// A style attribute with the properties that are set, or nothing if none are
+fn style(properties: &[(&str, Option<String>)]) -> String {
+    let declarations: Vec<String> = properties.iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("{}: {}", name, value)))
+        .collect();
+
+    if declarations.is_empty() {
+        String::new()
+    } else {
+        format!(" style=\"{}\"", escape(&declarations.join("; ")))
+    }
+}
+
+fn pixels(units: f32) -> String {
+    format!("{}px", (units * PIXELS_PER_UNIT).round())
+}
+
+// COLORREF values are stored as 0x00BBGGRR
+fn css_color(color: u32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color & 0xFF, (color >> 8) & 0xFF, (color >> 16) & 0xFF)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+// Text content, with OneNote's line breaks turned into <br>
+fn escape_text(text: &str) -> String {
+    escape(text).replace("\r\n", "\n").replace(['\r', '\n', LINE_BREAK], "<br>")
+}
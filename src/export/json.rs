@@ -0,0 +1,55 @@
+// JSON output of the page model and the raw revision store. The layout is described in
+// docs/json-schema.md; SCHEMA_VERSION is bumped whenever a field is renamed or removed.
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::onenote::Section;
+use crate::onestore::OneStore;
+
+use super::{create_directory, unique_file_name};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SectionDocument<'a> {
+    schema_version: u32,
+    section: &'a Section
+}
+
+#[derive(Serialize)]
+struct StoreDocument<'a> {
+    schema_version: u32,
+    store: &'a OneStore
+}
+
+// Writes the section as a single pretty printed .json file in directory and returns its path.
+// Images and attachments are referenced by location and aren't written.
+pub fn export_section(section: &Section, directory: &Path) -> Result<Vec<PathBuf>> {
+    create_directory(directory)?;
+    let name = format!("{}.json", section.display_name.as_deref().unwrap_or("section"));
+    let path = directory.join(unique_file_name(&name, &mut HashSet::new()));
+
+    let mut writer = BufWriter::new(File::create(&path)?);
+    write_section(section, &mut writer)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(vec![path])
+}
+
+pub fn write_section<W: Write>(section: &Section, writer: W) -> Result<()> {
+    let document = SectionDocument { schema_version: SCHEMA_VERSION, section };
+    serde_json::to_writer_pretty(writer, &document).map_err(io::Error::from)?;
+    Ok(())
+}
+
+// Every parsed structure of the file, including file node lists and the transaction log
+pub fn write_store<W: Write>(store: &OneStore, writer: W) -> Result<()> {
+    let document = StoreDocument { schema_version: SCHEMA_VERSION, store };
+    serde_json::to_writer_pretty(writer, &document).map_err(io::Error::from)?;
+    Ok(())
+}
@@ -0,0 +1,316 @@
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::onenote::{Content, EmbeddedFile, Image, OutlineElement, OutlineItem, Page, PageContent, RichText, Section, Table};
+use crate::onestore::OneStore;
+
+use super::{create_directory, is_safe_link, page_file_stem, Resources, SidecarFiles, LINE_BREAK};
+
+// Writes one .md file per page into directory, with images and attachments next to them.
+// Returns the paths of the pages in section order.
+pub fn export_section<T: Read + Seek>(section: &Section, store: &OneStore, reader: &mut T, directory: &Path) -> Result<Vec<PathBuf>> {
+    create_directory(directory)?;
+    let mut resources = SidecarFiles::new(store, reader, directory);
+
+    let mut pages = Vec::new();
+    for (index, page) in section.pages().enumerate() {
+        let path = resources.reserve(&format!("{}.md", page_file_stem(page, index)));
+        let markdown = render_page(page, &mut resources)?;
+        fs::write(&path, markdown)?;
+        pages.push(path);
+    }
+    Ok(pages)
+}
+
+pub fn render_page(page: &Page, resources: &mut dyn Resources) -> Result<String> {
+    let mut writer = Writer { out: String::new(), last_block: Block::None, resources };
+
+    if let Some(title) = page.title_text() {
+        writer.start_block(Block::Paragraph);
+        writer.out.push_str("# ");
+        writer.out.push_str(&escape(&title.replace(LINE_BREAK, " ")));
+    }
+
+    for content in &page.contents {
+        match content {
+            PageContent::Outline(outline) => writer.write_items(&outline.items, "")?,
+            PageContent::Image(image) => {
+                let image = writer.image(image)?;
+                writer.write_paragraph("", &image);
+            },
+            PageContent::EmbeddedFile(file) => {
+                let file = writer.embedded_file(file)?;
+                writer.write_paragraph("", &file);
+            }
+        }
+    }
+
+    writer.out.push('\n');
+    Ok(writer.out)
+}
+
+#[derive(PartialEq)]
+enum Block {
+    None,
+    Paragraph,
+    ListItem
+}
+
+struct Writer<'a> {
+    out: String,
+    last_block: Block,
+    resources: &'a mut dyn Resources
+}
+
+impl<'a> Writer<'a> {
+    // List items follow each other directly; everything else is separated by a blank line
+    fn start_block(&mut self, block: Block) {
+        match (&self.last_block, &block) {
+            (Block::None, _) => {},
+            (Block::ListItem, Block::ListItem) => self.out.push('\n'),
+            _ => self.out.push_str("\n\n")
+        }
+        self.last_block = block;
+    }
+
+    fn write_paragraph(&mut self, indent: &str, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.start_block(Block::Paragraph);
+        self.out.push_str(indent);
+        self.out.push_str(text);
+    }
+
+    fn write_items(&mut self, items: &[OutlineItem], indent: &str) -> Result<()> {
+        for item in items {
+            match item {
+                OutlineItem::Element(element) => self.write_element(element, indent)?,
+                OutlineItem::Group(group) => self.write_items(&group.items, indent)?
+            }
+        }
+        Ok(())
+    }
+
+    fn write_element(&mut self, element: &OutlineElement, indent: &str) -> Result<()> {
+        let marker = element.list.as_ref().map(|list| match (list.is_numbered(), list.restart) {
+            (true, Some(start)) => format!("{}. ", start),
+            (true, None) => "1. ".to_string(),
+            (false, _) => "- ".to_string()
+        });
+        let child_indent = format!("{}{}", indent, " ".repeat(marker.as_ref().map_or(0, |marker| marker.len())));
+
+        let mut contents = Vec::new();
+        for content in &element.contents {
+            let text = match content {
+                Content::RichText(text) if marker.is_none() => format!("{}{}", heading_prefix(text), escape_line_start(&rich_text(text, &child_indent))),
+                Content::RichText(text) => rich_text(text, &child_indent),
+                Content::Image(image) => self.image(image)?,
+                Content::Table(table) => self.table(table, &child_indent)?,
+                Content::EmbeddedFile(file) => self.embedded_file(file)?
+            };
+            contents.push(text);
+        }
+
+        match marker {
+            Some(marker) => {
+                self.start_block(Block::ListItem);
+                self.out.push_str(indent);
+                self.out.push_str(&marker);
+                let mut contents = contents.into_iter();
+                if let Some(first) = contents.next() {
+                    self.out.push_str(&first);
+                }
+                for content in contents {
+                    self.write_paragraph(&child_indent, &content);
+                }
+            },
+            None => {
+                for content in contents {
+                    self.write_paragraph(indent, &content);
+                }
+            }
+        }
+
+        self.write_items(&element.children, &child_indent)
+    }
+
+    fn image(&mut self, image: &Image) -> Result<String> {
+        let link = match &image.data {
+            Some(data) => self.resources.link(data, image.filename.as_deref())?,
+            None => None
+        };
+
+        Ok(match link {
+            Some(link) if is_safe_link(&link) => format!("![{}]({})", escape(image.alt_text.as_deref().unwrap_or("")), destination(&link)),
+            _ => String::new()
+        })
+    }
+
+    fn embedded_file(&mut self, file: &EmbeddedFile) -> Result<String> {
+        let link = match &file.data {
+            Some(data) => self.resources.link(data, file.filename.as_deref())?,
+            None => None
+        };
+        let name = escape(file.filename.as_deref().unwrap_or("attachment"));
+
+        Ok(match link {
+            Some(link) if is_safe_link(&link) => format!("[{}]({})", name, destination(&link)),
+            _ => name
+        })
+    }
+
+    // GFM tables can't hold block content so every cell is flattened to a single line
+    fn table(&mut self, table: &Table, indent: &str) -> Result<String> {
+        let columns = table.rows.iter().map(|row| row.cells.len()).max().unwrap_or(0).max(1);
+
+        let mut lines = Vec::new();
+        for (index, row) in table.rows.iter().enumerate() {
+            let mut cells = Vec::new();
+            for cell in &row.cells {
+                let mut paragraphs = Vec::new();
+                for element in &cell.contents {
+                    self.cell_paragraphs(element, &mut paragraphs)?;
+                }
+                cells.push(paragraphs.join("<br>"));
+            }
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+
+            // The first row becomes the header since GFM tables require one
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+
+        Ok(lines.join(&format!("\n{}", indent)))
+    }
+
+    fn cell_paragraphs(&mut self, element: &OutlineElement, paragraphs: &mut Vec<String>) -> Result<()> {
+        for content in &element.contents {
+            let text = match content {
+                Content::RichText(text) => rich_text(text, "").replace('\n', "<br>"),
+                Content::Image(image) => self.image(image)?,
+                Content::EmbeddedFile(file) => self.embedded_file(file)?,
+                // Tables can't be nested in a GFM table
+                Content::Table(_) => String::new()
+            };
+            paragraphs.push(text);
+        }
+        for child in &element.children {
+            if let OutlineItem::Element(child) = child {
+                self.cell_paragraphs(child, paragraphs)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Built in heading styles become Markdown headings
+fn heading_prefix(text: &RichText) -> String {
+    let level = text.paragraph_style.as_ref()
+        .and_then(|style| style.style_id.as_deref())
+        .and_then(|id| id.strip_prefix('h'))
+        .and_then(|level| level.parse::<usize>().ok())
+        .filter(|level| (1..=6).contains(level));
+
+    match level {
+        Some(level) => format!("{} ", "#".repeat(level)),
+        None => String::new()
+    }
+}
+
+fn rich_text(text: &RichText, indent: &str) -> String {
+    let mut out = String::new();
+
+    if text.runs.is_empty() {
+        out.push_str(&escape(&text.text));
+    }
+
+    let mut runs = text.runs.iter().peekable();
+    while let Some(run) = runs.next() {
+        let mut inner = emphasize(&escape(text.run_text(run)), run.bold, run.italic, run.strikethrough, run.superscript, run.subscript);
+
+        // Neighbouring runs of the same link become a single link. Links that could run script
+        // are left out and only their text is kept
+        if let Some(url) = run.hyperlink.as_ref().filter(|url| is_safe_link(url)) {
+            while let Some(next) = runs.next_if(|next| next.hyperlink.as_ref() == Some(url)) {
+                inner.push_str(&emphasize(&escape(text.run_text(next)), next.bold, next.italic, next.strikethrough, next.superscript, next.subscript));
+            }
+            out.push_str(&format!("[{}]({})", inner, destination(url)));
+        } else {
+            out.push_str(&inner);
+        }
+    }
+
+    // Hard line breaks, continued at the paragraph's indentation
+    let line_break = format!("  \n{}", indent);
+    out.replace("\r\n", "\n")
+        .replace(['\r', '\n', LINE_BREAK], &line_break)
+}
+
+// Markers go inside surrounding whitespace since "** bold**" isn't bold
+fn emphasize(text: &str, bold: bool, italic: bool, strikethrough: bool, superscript: bool, subscript: bool) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let mut inner = trimmed.to_string();
+    if superscript {
+        inner = format!("<sup>{}</sup>", inner);
+    }
+    if subscript {
+        inner = format!("<sub>{}</sub>", inner);
+    }
+    if strikethrough {
+        inner = format!("~~{}~~", inner);
+    }
+    if italic {
+        inner = format!("*{}*", inner);
+    }
+    if bold {
+        inner = format!("**{}**", inner);
+    }
+    format!("{}{}{}", leading, inner, trailing)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '~' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Text that would otherwise be read as a heading, list item or quote
+fn escape_line_start(text: &str) -> String {
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    let is_ordered_marker = digits > 0 && matches!(text[digits..].chars().next(), Some('.') | Some(')'));
+
+    if text.starts_with(['#', '-', '+', '=']) {
+        format!("\\{}", text)
+    } else if is_ordered_marker {
+        format!("{}\\{}", &text[..digits], &text[digits..])
+    } else {
+        text.to_string()
+    }
+}
+
+// Link destinations with spaces or parentheses have to be wrapped in angle brackets. Pipes are
+// encoded so links can be used in table cells
+fn destination(link: &str) -> String {
+    let link = link.replace('|', "%7C");
+    if link.contains([' ', '(', ')']) {
+        format!("<{}>", link.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        link
+    }
+}
@@ -0,0 +1,163 @@
+// Converters from the MS-ONE page model to other formats
+pub mod markdown;
+pub mod html;
+#[cfg(feature = "serde")]
+pub mod json;
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::error::Result;
+use crate::onenote::{FileDataLocation, FileDataReference, Page};
+use crate::onestore::OneStore;
+
+// OneNote separates lines within a paragraph with a vertical tab
+const LINE_BREAK: char = '\u{000B}';
+
+// Schemes a link taken from a notebook may use in an exported page. Anything else, javascript:
+// and data: in particular, could run script when the link is followed
+const SAFE_SCHEMES: [&str; 4] = ["http", "https", "mailto", "onenote"];
+
+// True if link is relative or uses one of SAFE_SCHEMES. A relative link can't contain a colon at
+// all, so a scheme can't hide behind characters a browser strips or a Markdown renderer decodes.
+pub fn is_safe_link(link: &str) -> bool {
+    match link.split_once(':') {
+        Some((scheme, _)) => SAFE_SCHEMES.iter().any(|safe| scheme.eq_ignore_ascii_case(safe)),
+        None => true
+    }
+}
+
+// Decides how an exported page refers to an image or attached file
+pub trait Resources {
+    // The link to use for the data, or None if the data isn't available. name is the file's
+    // original name if it is known.
+    fn link(&mut self, data: &FileDataReference, name: Option<&str>) -> Result<Option<String>>;
+}
+
+// Writes images and attachments into the export directory and links to them by relative path
+pub struct SidecarFiles<'a, T: Read + Seek> {
+    store: &'a OneStore,
+    reader: &'a mut T,
+    directory: PathBuf,
+    used_names: HashSet<String>
+}
+
+impl<'a, T: Read + Seek> SidecarFiles<'a, T> {
+    pub fn new(store: &'a OneStore, reader: &'a mut T, directory: &Path) -> SidecarFiles<'a, T> {
+        SidecarFiles { store, reader, directory: directory.to_path_buf(), used_names: HashSet::new() }
+    }
+
+    // Reserves a file name in the export directory that no other page or resource uses
+    pub fn reserve(&mut self, name: &str) -> PathBuf {
+        self.directory.join(unique_file_name(name, &mut self.used_names))
+    }
+}
+
+impl<'a, T: Read + Seek> Resources for SidecarFiles<'a, T> {
+    fn link(&mut self, data: &FileDataReference, name: Option<&str>) -> Result<Option<String>> {
+        let object = match data.location() {
+            FileDataLocation::FileDataStore(guid) => match self.store.file_data_store.get(&guid) {
+                Some(object) => object.clone(),
+                None => return Ok(None)
+            },
+            _ => return Ok(None)
+        };
+
+        let default_name = format!("{}{}", object.guid, data.extension);
+        let file_name = unique_file_name(name.unwrap_or(&default_name), &mut self.used_names);
+        let mut file = File::create(self.directory.join(&file_name))?;
+        io::copy(&mut object.open(self.reader)?, &mut file)?;
+        Ok(Some(file_name))
+    }
+}
+
+// Embeds images and attachments in the page itself as data: URIs
+pub struct DataUris<'a, T: Read + Seek> {
+    store: &'a OneStore,
+    reader: &'a mut T
+}
+
+impl<'a, T: Read + Seek> DataUris<'a, T> {
+    pub fn new(store: &'a OneStore, reader: &'a mut T) -> DataUris<'a, T> {
+        DataUris { store, reader }
+    }
+}
+
+impl<'a, T: Read + Seek> Resources for DataUris<'a, T> {
+    fn link(&mut self, data: &FileDataReference, _name: Option<&str>) -> Result<Option<String>> {
+        let object = match data.location() {
+            FileDataLocation::FileDataStore(guid) => match self.store.file_data_store.get(&guid) {
+                Some(object) => object.clone(),
+                None => return Ok(None)
+            },
+            _ => return Ok(None)
+        };
+
+        let mut bytes = Vec::new();
+        object.open(self.reader)?.read_to_end(&mut bytes)?;
+        Ok(Some(format!("data:{};base64,{}", mime_type(&data.extension), BASE64.encode(bytes))))
+    }
+}
+
+// Guessed from the extension OneNote stored with the file data
+pub fn mime_type(extension: &str) -> &'static str {
+    match extension.trim_start_matches('.').to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "svg" => "image/svg+xml",
+        "emf" => "image/emf",
+        "wmf" => "image/wmf",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "wma" => "audio/x-ms-wma",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream"
+    }
+}
+
+// A file name for page in an export directory, without extension
+pub fn page_file_stem(page: &Page, index: usize) -> String {
+    match page.title_text() {
+        Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+        _ => format!("Untitled page {}", index + 1)
+    }
+}
+
+// Replaces anything that could escape a directory or isn't allowed in file names, and appends a
+// counter if the name is already taken
+pub fn unique_file_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let name: String = name.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    let name = match name.trim() {
+        "" | "." | ".." => "_".to_string(),
+        name => name.to_string()
+    };
+
+    let mut candidate = name.clone();
+    let mut counter = 1;
+    // Names are compared case insensitively since the export may land on a case insensitive file system
+    while !used_names.insert(candidate.to_lowercase()) {
+        candidate = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => format!("{} ({}).{}", stem, counter, extension),
+            _ => format!("{} ({})", name, counter)
+        };
+        counter += 1;
+    }
+    candidate
+}
+
+pub(crate) fn create_directory(directory: &Path) -> Result<()> {
+    fs::create_dir_all(directory)?;
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use byteorder::ReadBytesExt;
+
+use crate::error::{Error, Result};
+use crate::structs::exguid::ExGuid;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::guid::*;
+
+use super::streamobject::*;
+use super::types::*;
+
+// Data element types. See MS-FSSHTTPB 2.2.1.12
+const STORAGE_INDEX: u64 = 0x01;
+const STORAGE_MANIFEST: u64 = 0x02;
+const CELL_MANIFEST: u64 = 0x03;
+const REVISION_MANIFEST_ELEMENT: u64 = 0x04;
+const OBJECT_GROUP: u64 = 0x05;
+const DATA_ELEMENT_FRAGMENT: u64 = 0x06;
+const OBJECT_DATA_BLOB_ELEMENT: u64 = 0x0A;
+
+// Every data element of a package, keyed by data element id. See MS-FSSHTTPB 2.2.1.12
+#[derive(Debug, Default)]
+pub struct DataElementPackage {
+    pub storage_indexes: HashMap<ExGuid, StorageIndex>,
+    pub storage_manifests: HashMap<ExGuid, StorageManifest>,
+    // Each cell manifest only names the cell's current revision
+    pub cell_manifests: HashMap<ExGuid, ExGuid>,
+    pub revision_manifests: HashMap<ExGuid, RevisionManifest>,
+    pub object_groups: HashMap<ExGuid, ObjectGroup>,
+    pub object_data_blobs: HashMap<ExGuid, FileChunkReference>
+}
+
+// See MS-FSSHTTPB 2.2.1.12.2
+#[derive(Debug, Default)]
+pub struct StorageIndex {
+    pub manifest_mappings: Vec<ExGuid>,
+    // Cell to the id of its cell manifest data element
+    pub cell_mappings: HashMap<CellId, ExGuid>,
+    // Revision id to the id of its revision manifest data element
+    pub revision_mappings: HashMap<ExGuid, ExGuid>
+}
+
+// See MS-FSSHTTPB 2.2.1.12.3
+#[derive(Debug)]
+pub struct StorageManifest {
+    pub schema: Guid,
+    // Root id to the cell holding that root
+    pub roots: HashMap<ExGuid, CellId>
+}
+
+// See MS-FSSHTTPB 2.2.1.12.5
+#[derive(Debug)]
+pub struct RevisionManifest {
+    pub rid: ExGuid,
+    // Nil if the revision doesn't build on another one
+    pub base_rid: ExGuid,
+    // Root id and the object holding that root
+    pub root_objects: Vec<(ExGuid, ExGuid)>,
+    // Data element ids of the revision's object groups
+    pub object_groups: Vec<ExGuid>
+}
+
+// Declarations and data are parallel: the nth data belongs to the nth declaration. See MS-FSSHTTPB 2.2.1.12.6
+#[derive(Debug, Default)]
+pub struct ObjectGroup {
+    pub declarations: Vec<ObjectDeclaration>,
+    pub data: Vec<ObjectData>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectDeclaration {
+    pub object_id: ExGuid,
+    // Set for objects whose data is in an object data BLOB
+    pub blob_id: Option<ExGuid>,
+    pub partition_id: u64
+}
+
+// Referenced objects and cells are listed in the order the object's data refers to them
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectData {
+    Object { object_ids: Vec<ExGuid>, cell_ids: Vec<CellId>, data: FileChunkReference },
+    // The data exists but wasn't included in this package
+    Excluded { object_ids: Vec<ExGuid>, cell_ids: Vec<CellId>, len: u64 },
+    BlobReference { object_ids: Vec<ExGuid>, cell_ids: Vec<CellId>, blob_id: ExGuid }
+}
+
+impl DataElementPackage {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<DataElementPackage> {
+        let header = StreamObjectHeader::expect(reader, DATA_ELEMENT_PACKAGE)?;
+        if reader.read_u8()? != 0 {
+            return Err(Error::InvalidData { structure: "DataElementPackage", offset: header.offset, reason: "Reserved byte must be 0" });
+        }
+
+        let mut package = DataElementPackage::default();
+        while !read_end(reader, DATA_ELEMENT_PACKAGE)? {
+            package.read_data_element(reader)?;
+        }
+        Ok(package)
+    }
+
+    fn read_data_element<T: Read + Seek>(&mut self, reader: &mut T) -> Result<()> {
+        let header = StreamObjectHeader::expect(reader, DATA_ELEMENT)?;
+        let id = read_exguid(reader)?;
+        SerialNumber::from_reader(reader)?;
+        let element_type = read_compact_u64(reader)?;
+
+        let duplicate = match element_type {
+            STORAGE_INDEX => self.storage_indexes.insert(id, StorageIndex::from_reader(reader)?).is_some(),
+            STORAGE_MANIFEST => self.storage_manifests.insert(id, StorageManifest::from_reader(reader)?).is_some(),
+            CELL_MANIFEST => {
+                StreamObjectHeader::expect(reader, CELL_MANIFEST_CURRENT_REVISION)?;
+                self.cell_manifests.insert(id, read_exguid(reader)?).is_some()
+            },
+            REVISION_MANIFEST_ELEMENT => self.revision_manifests.insert(id, RevisionManifest::from_reader(reader)?).is_some(),
+            OBJECT_GROUP => self.object_groups.insert(id, ObjectGroup::from_reader(reader)?).is_some(),
+            OBJECT_DATA_BLOB_ELEMENT => {
+                StreamObjectHeader::expect(reader, OBJECT_DATA_BLOB)?;
+                self.object_data_blobs.insert(id, read_binary_item(reader, "ObjectDataBlob")?).is_some()
+            },
+            DATA_ELEMENT_FRAGMENT => return Err(Error::Unsupported { structure: "DataElement", offset: header.offset, feature: "data element fragments" }),
+            _ => return Err(Error::InvalidData { structure: "DataElement", offset: header.offset, reason: "Unknown data element type" })
+        };
+        if duplicate {
+            return Err(Error::InvalidData { structure: "DataElement", offset: header.offset, reason: "Data element id is used twice" });
+        }
+
+        expect_end(reader, DATA_ELEMENT)
+    }
+}
+
+impl StorageIndex {
+    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<StorageIndex> {
+        let mut index = StorageIndex::default();
+        while !at_end(reader, DATA_ELEMENT)? {
+            let header = StreamObjectHeader::from_reader(reader)?;
+            match header.object_type {
+                STORAGE_INDEX_MANIFEST_MAPPING => {
+                    index.manifest_mappings.push(read_exguid(reader)?);
+                    SerialNumber::from_reader(reader)?;
+                },
+                STORAGE_INDEX_CELL_MAPPING => {
+                    let cell = CellId::from_reader(reader)?;
+                    index.cell_mappings.insert(cell, read_exguid(reader)?);
+                    SerialNumber::from_reader(reader)?;
+                },
+                STORAGE_INDEX_REVISION_MAPPING => {
+                    let rid = read_exguid(reader)?;
+                    index.revision_mappings.insert(rid, read_exguid(reader)?);
+                    SerialNumber::from_reader(reader)?;
+                },
+                _ => return Err(Error::InvalidData { structure: "StorageIndex", offset: header.offset, reason: "Unexpected stream object in storage index" })
+            }
+        }
+        Ok(index)
+    }
+}
+
+impl StorageManifest {
+    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<StorageManifest> {
+        StreamObjectHeader::expect(reader, STORAGE_MANIFEST_SCHEMA_GUID)?;
+        let schema = Guid::from_reader(reader)?;
+
+        let mut roots = HashMap::new();
+        while !at_end(reader, DATA_ELEMENT)? {
+            StreamObjectHeader::expect(reader, STORAGE_MANIFEST_ROOT_DECLARE)?;
+            let root = read_exguid(reader)?;
+            roots.insert(root, CellId::from_reader(reader)?);
+        }
+        Ok(StorageManifest { schema, roots })
+    }
+}
+
+impl RevisionManifest {
+    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<RevisionManifest> {
+        StreamObjectHeader::expect(reader, REVISION_MANIFEST)?;
+        let rid = read_exguid(reader)?;
+        let base_rid = read_exguid(reader)?;
+
+        let mut manifest = RevisionManifest { rid, base_rid, root_objects: Vec::new(), object_groups: Vec::new() };
+        while !at_end(reader, DATA_ELEMENT)? {
+            let header = StreamObjectHeader::from_reader(reader)?;
+            match header.object_type {
+                REVISION_MANIFEST_ROOT_DECLARE => {
+                    let root = read_exguid(reader)?;
+                    manifest.root_objects.push((root, read_exguid(reader)?));
+                },
+                REVISION_MANIFEST_OBJECT_GROUP_REFERENCES => manifest.object_groups.push(read_exguid(reader)?),
+                _ => return Err(Error::InvalidData { structure: "RevisionManifest", offset: header.offset, reason: "Unexpected stream object in revision manifest" })
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+impl ObjectGroup {
+    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<ObjectGroup> {
+        let mut group = ObjectGroup::default();
+
+        StreamObjectHeader::expect(reader, OBJECT_GROUP_DECLARATIONS)?;
+        while !read_end(reader, OBJECT_GROUP_DECLARATIONS)? {
+            let header = StreamObjectHeader::from_reader(reader)?;
+            let object_id = read_exguid(reader)?;
+            let blob_id = match header.object_type {
+                OBJECT_GROUP_OBJECT_DECLARE => None,
+                OBJECT_GROUP_OBJECT_BLOB_DATA_DECLARATION => Some(read_exguid(reader)?),
+                _ => return Err(Error::InvalidData { structure: "ObjectGroupDeclarations", offset: header.offset, reason: "Unexpected stream object in object group declarations" })
+            };
+            let partition_id = read_compact_u64(reader)?;
+            if blob_id.is_none() {
+                // Data size
+                read_compact_u64(reader)?;
+            }
+            // Object and cell reference counts
+            read_compact_u64(reader)?;
+            read_compact_u64(reader)?;
+            group.declarations.push(ObjectDeclaration { object_id, blob_id, partition_id });
+        }
+
+        // Metadata about how often objects change is optional and not needed for reading
+        let mut header = StreamObjectHeader::from_reader(reader)?;
+        if header.object_type == OBJECT_GROUP_METADATA_DECLARATIONS {
+            while !read_end(reader, OBJECT_GROUP_METADATA_DECLARATIONS)? {
+                StreamObjectHeader::expect(reader, OBJECT_GROUP_METADATA)?;
+                read_compact_u64(reader)?;
+            }
+            header = StreamObjectHeader::from_reader(reader)?;
+        }
+        if header.object_type != OBJECT_GROUP_DATA {
+            return Err(Error::InvalidData { structure: "ObjectGroup", offset: header.offset, reason: "Expected object group data" });
+        }
+
+        while !read_end(reader, OBJECT_GROUP_DATA)? {
+            let header = StreamObjectHeader::from_reader(reader)?;
+            let object_ids = read_exguid_array(reader)?;
+            let cell_ids = read_cell_id_array(reader)?;
+            let data = match header.object_type {
+                OBJECT_GROUP_OBJECT_DATA => ObjectData::Object { object_ids, cell_ids, data: read_binary_item(reader, "ObjectGroupObjectData")? },
+                OBJECT_GROUP_OBJECT_EXCLUDED_DATA => ObjectData::Excluded { object_ids, cell_ids, len: read_compact_u64(reader)? },
+                OBJECT_GROUP_OBJECT_DATA_BLOB_REFERENCE => ObjectData::BlobReference { object_ids, cell_ids, blob_id: read_exguid(reader)? },
+                _ => return Err(Error::InvalidData { structure: "ObjectGroupData", offset: header.offset, reason: "Unexpected stream object in object group data" })
+            };
+            group.data.push(data);
+        }
+
+        if group.data.len() != group.declarations.len() {
+            return Err(Error::InvalidData { structure: "ObjectGroup", offset: header.offset, reason: "Object group has a different number of declarations and data" });
+        }
+        Ok(group)
+    }
+}
@@ -0,0 +1,8 @@
+// The alternative packaging of MS-ONESTORE section 2.8, which stores a revision store as FSSHTTPB
+// data elements (MS-FSSHTTPB) instead of file node lists. OneNote uses it for files synced
+// through SharePoint and OneDrive. packaging maps it onto the same object spaces and revisions
+// as the native format.
+pub mod types;
+pub mod streamobject;
+pub mod dataelement;
+pub mod packaging;
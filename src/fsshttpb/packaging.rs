@@ -0,0 +1,309 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::error::{Error, Result};
+use crate::onestore::{OneStore, StoreFormat};
+use crate::structs::exguid::ExGuid;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::filedatastore::{FileDataStore, FileDataStoreObject};
+use crate::structs::filenodelist::FileNodeList;
+use crate::structs::freechunklist::FreeChunkList;
+use crate::structs::globalidtable::GlobalIdTable;
+use crate::structs::guid::*;
+use crate::structs::hashedchunklist::HashedChunkList;
+use crate::structs::header::{OneNoteFileHeader, OneNoteFileType, FILE_TYPE_ONE, FILE_TYPE_ONETOC2};
+use crate::structs::jcid::Jcid;
+use crate::structs::objectpropset::ObjectSpaceObjectPropSet;
+use crate::structs::objectspace::ObjectSpace;
+use crate::structs::objecttable::{Object, ObjectBody, ObjectTable};
+use crate::structs::revision::{self, Revision, REVISION_ROLE_DEFAULT_CONTENT};
+use crate::structs::transactionlog::TransactionLog;
+
+use super::dataelement::{DataElementPackage, ObjectData, ObjectGroup, StorageIndex};
+use super::streamobject::*;
+use super::types::{read_exguid, CellId};
+
+const STRUCTURE: &str = "OneStorePackaging";
+
+// Offset of guidFileFormat, which is the same in both formats
+const FILE_FORMAT_OFFSET: u64 = 48;
+const FILE_FORMAT_PACKAGING: Guid = guid!("638DE92F-A6D4-4BC1-9A36-B3FC2511A5B7");
+
+// Storage manifest root of the cell holding the store's root object space. See MS-ONESTORE 2.7.1
+const DATA_ROOT: ExGuid = ExGuid { guid: guid!("84DEFAB9-AAA3-4A0D-A3A8-520C77AC7073"), n: 2 };
+// The header cell holds a copy of header fields rather than an object space
+const HEADER_ROOT: ExGuid = ExGuid { guid: guid!("1A5A319C-C26B-41AA-B9C5-9BD8C44E07D4"), n: 1 };
+
+// Every object is split over partitions of its object group. See MS-ONESTORE 2.7.7
+const PARTITION_PROPERTY_SET: u64 = 1;
+const PARTITION_FILE_DATA: u64 = 2;
+const PARTITION_JCID: u64 = 4;
+
+// True if the stream holds the alternative packaging rather than the native format
+pub fn is_packaged<T: Read + Seek>(reader: &mut T) -> Result<bool> {
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    if stream_len < FILE_FORMAT_OFFSET + 16 {
+        return Ok(false);
+    }
+    reader.seek(SeekFrom::Start(FILE_FORMAT_OFFSET))?;
+    Ok(Guid::from_reader(reader)? == FILE_FORMAT_PACKAGING)
+}
+
+// Reads a packaged revision store and maps its cells onto object spaces and its revision manifests
+// onto revisions, so it can be used like a native one. See MS-ONESTORE 2.7 and 2.8
+pub fn parse_store<T: Read + Seek>(reader: &mut T) -> Result<OneStore> {
+    reader.seek(SeekFrom::Start(0))?;
+
+    let file_type_guid = Guid::from_reader(reader)?;
+    let file_type = match file_type_guid {
+        FILE_TYPE_ONE => OneNoteFileType::One,
+        FILE_TYPE_ONETOC2 => OneNoteFileType::OneToc2,
+        _ => return Err(Error::BadGuid { structure: STRUCTURE, offset: 0, found: file_type_guid })
+    };
+    let file_guid = Guid::from_reader(reader)?;
+    let legacy_file_version = Guid::from_reader(reader)?;
+    let file_format = Guid::from_reader(reader)?;
+
+    let offset = reader.stream_position()?;
+    if reader.read_u32::<LittleEndian>()? != 0 {
+        return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Reserved field must be 0" });
+    }
+
+    StreamObjectHeader::expect(reader, ONE_NOTE_PACKAGING)?;
+    let storage_index_id = read_exguid(reader)?;
+    // guidCellSchemaId
+    Guid::from_reader(reader)?;
+    let package = DataElementPackage::from_reader(reader)?;
+    expect_end(reader, ONE_NOTE_PACKAGING)?;
+
+    let file_length = reader.seek(SeekFrom::End(0))?;
+    let mut mapper = Mapper { package: &package, file_data_store: FileDataStore::default(), reader };
+    let (root_object_space_id, object_spaces) = mapper.object_spaces(&storage_index_id)?;
+    let file_data_store = mapper.file_data_store;
+
+    Ok(OneStore {
+        format: StoreFormat::Packaged,
+        header: OneNoteFileHeader::packaged(file_type, file_guid, legacy_file_version, file_format, file_length),
+        file_length,
+        transaction_log: TransactionLog::new(),
+        root_file_node_list: FileNodeList { id: 0, fragment_sequence_index: 0, file_nodes: Vec::new() },
+        root_object_space_id,
+        object_spaces,
+        file_data_store,
+        free_chunk_list: FreeChunkList::default(),
+        hashed_chunk_list: HashedChunkList::default(),
+        name_matches: None
+    })
+}
+
+struct Mapper<'a, T: Read + Seek> {
+    package: &'a DataElementPackage,
+    // File data has no GUID of its own in the packaging, so every blob is given one here
+    file_data_store: FileDataStore,
+    reader: &'a mut T
+}
+
+impl<'a, T: Read + Seek> Mapper<'a, T> {
+    fn object_spaces(&mut self, storage_index_id: &ExGuid) -> Result<(ExGuid, Vec<ObjectSpace>)> {
+        let storage_index = self.package.storage_indexes.get(storage_index_id)
+            .ok_or(missing("StorageIndex", "Storage index is missing"))?;
+        let storage_manifest = storage_index.manifest_mappings.iter()
+            .find_map(|id| self.package.storage_manifests.get(id))
+            .ok_or(missing("StorageManifest", "Storage manifest is missing"))?;
+        let data_root = storage_manifest.roots.get(&DATA_ROOT)
+            .ok_or(missing("StorageManifest", "Storage manifest has no data root"))?;
+        let header_cell = storage_manifest.roots.get(&HEADER_ROOT);
+
+        // Sorted so object spaces come out in the same order every time
+        let mut cells: Vec<(&CellId, &ExGuid)> = storage_index.cell_mappings.iter()
+            .filter(|(cell, manifest)| Some(*cell) != header_cell && !manifest.is_nil())
+            .collect();
+        cells.sort_by_key(|(cell, _)| (cell.object_space, cell.context));
+
+        let mut object_spaces: Vec<ObjectSpace> = Vec::new();
+        for (cell, cell_manifest_id) in cells {
+            if !object_spaces.iter().any(|space| space.gosid == cell.object_space) {
+                object_spaces.push(ObjectSpace { gosid: cell.object_space, revisions: Vec::new(), revision_roles: HashMap::new(), is_encrypted: false });
+            }
+            let object_space = object_spaces.iter_mut().find(|space| space.gosid == cell.object_space).unwrap();
+
+            let current_rid = *self.package.cell_manifests.get(cell_manifest_id)
+                .ok_or(missing("CellManifest", "Cell manifest is missing"))?;
+            self.push_revisions(object_space, storage_index, cell, &current_rid)?;
+            object_space.revision_roles.insert((cell.context, REVISION_ROLE_DEFAULT_CONTENT), current_rid);
+        }
+
+        // Cells are usually in the default context already. If a space has none there, its last
+        // revision stands in for it so the space can be read like a native one.
+        for object_space in &mut object_spaces {
+            let default_role = (ExGuid::nil(), REVISION_ROLE_DEFAULT_CONTENT);
+            if !object_space.revision_roles.contains_key(&default_role) {
+                if let Some(rid) = object_space.revisions.last().map(|revision| revision.rid) {
+                    object_space.revision_roles.insert(default_role, rid);
+                }
+            }
+        }
+
+        Ok((data_root.object_space, object_spaces))
+    }
+
+    // Adds the revision and every revision it builds on that the space doesn't have yet, oldest first
+    fn push_revisions(&mut self, object_space: &mut ObjectSpace, storage_index: &StorageIndex, cell: &CellId, rid: &ExGuid) -> Result<()> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = *rid;
+        while !next.is_nil() && object_space.revision(&next).is_none() {
+            if !visited.insert(next) {
+                return Err(Error::InvalidData { structure: "RevisionManifest", offset: 0, reason: "Revision depends on itself" });
+            }
+            let manifest = storage_index.revision_mappings.get(&next)
+                .and_then(|id| self.package.revision_manifests.get(id))
+                .ok_or(missing("RevisionManifest", "Revision manifest is missing"))?;
+            chain.push(manifest);
+            next = manifest.base_rid;
+        }
+
+        for manifest in chain.into_iter().rev() {
+            let mut objects = ObjectTable::new();
+            let mut object_groups = Vec::new();
+            for group_id in &manifest.object_groups {
+                let group = self.package.object_groups.get(group_id)
+                    .ok_or(missing("ObjectGroup", "Object group is missing"))?;
+                let group_objects = self.objects(group)?;
+                objects.extend(group_objects.iter().map(|(id, object)| (*id, object.clone())));
+                object_groups.push(revision::ObjectGroup {
+                    id: *group_id,
+                    global_id_table: Arc::new(GlobalIdTable::default()),
+                    objects: group_objects,
+                    file_nodes: Vec::new()
+                });
+            }
+
+            object_space.revisions.push(Revision {
+                rid: manifest.rid,
+                rid_dependent: manifest.base_rid,
+                role: REVISION_ROLE_DEFAULT_CONTENT,
+                context: cell.context,
+                odcs_default: 0,
+                // Root ids are {4A3717F8-1C14-49E7-9526-81D942DE1741},role
+                root_objects: manifest.root_objects.iter().map(|(root, object)| (root.n, *object)).collect(),
+                object_groups,
+                global_id_table: None,
+                objects,
+                ref_count_overrides: HashMap::new(),
+                file_nodes: Vec::new()
+            });
+        }
+        Ok(())
+    }
+
+    fn objects(&mut self, group: &ObjectGroup) -> Result<ObjectTable> {
+        // Object ids in the order they are first declared, with the data of each partition
+        let mut ids = Vec::new();
+        let mut partitions: HashMap<ExGuid, HashMap<u64, &ObjectData>> = HashMap::new();
+        for (declaration, data) in group.declarations.iter().zip(&group.data) {
+            let object = partitions.entry(declaration.object_id).or_default();
+            if object.is_empty() {
+                ids.push(declaration.object_id);
+            }
+            object.insert(declaration.partition_id, data);
+        }
+
+        let mut objects = ObjectTable::new();
+        for id in ids {
+            if let Some(object) = self.object(id, &partitions[&id])? {
+                objects.insert(id, object);
+            }
+        }
+        Ok(objects)
+    }
+
+    // None for objects whose data isn't in the package
+    fn object(&mut self, id: ExGuid, partitions: &HashMap<u64, &ObjectData>) -> Result<Option<Object>> {
+        let jcid = match partitions.get(&PARTITION_JCID).map(|data| self.data(data)).transpose()?.flatten() {
+            Some(fcr) if fcr.len == 4 => {
+                self.reader.seek(SeekFrom::Start(fcr.start))?;
+                Jcid::from_reader(self.reader)?
+            },
+            Some(fcr) => return Err(Error::InvalidData { structure: "ObjectGroupObjectData", offset: fcr.start, reason: "JCID partition must hold 4 bytes" }),
+            None => return Ok(None)
+        };
+
+        if jcid.is_file_data() {
+            let fcr = match partitions.get(&PARTITION_FILE_DATA).map(|data| self.data(data)).transpose()?.flatten() {
+                Some(fcr) => fcr,
+                None => return Ok(None)
+            };
+            let guid = Guid::from_u128(self.file_data_store.objects.len() as u128 + 1);
+            self.file_data_store.objects.insert(guid, FileDataStoreObject { guid, data_offset: fcr.start, len: fcr.len });
+
+            return Ok(Some(Object {
+                id,
+                jcid,
+                ref_count: 1,
+                body: ObjectBody::FileData { reference: format!("<ifndf>{{{}}}", guid), extension: String::new() },
+                md5_hash: None,
+                has_oid_references: false,
+                has_osid_references: false,
+                global_id_table: Arc::new(GlobalIdTable::default())
+            }));
+        }
+
+        let data = match partitions.get(&PARTITION_PROPERTY_SET) {
+            Some(data) => *data,
+            None => return Ok(None)
+        };
+        let fcr = match self.data(data)? {
+            Some(fcr) => fcr,
+            None => return Ok(None)
+        };
+        let (object_ids, cell_ids) = match data {
+            ObjectData::Object { object_ids, cell_ids, .. } | ObjectData::Excluded { object_ids, cell_ids, .. } | ObjectData::BlobReference { object_ids, cell_ids, .. } => (object_ids, cell_ids)
+        };
+
+        // The property set's CompactIds stand for the listed objects and cells, in order: object
+        // ids first, then object spaces followed by contexts
+        let prop_set = ObjectSpaceObjectPropSet::from_reader(&fcr, self.reader)?;
+        if prop_set.oids.len() != object_ids.len() || prop_set.osids.len() + prop_set.context_ids.len() != cell_ids.len() {
+            return Err(Error::InvalidData { structure: "ObjectGroupObjectData", offset: fcr.start, reason: "Object references don't match the property set" });
+        }
+        let (space_cells, context_cells) = cell_ids.split_at(prop_set.osids.len());
+        let references = prop_set.oids.iter().copied().zip(object_ids.iter().copied())
+            .chain(prop_set.osids.iter().copied().zip(space_cells.iter().map(|cell| cell.object_space)))
+            .chain(prop_set.context_ids.iter().copied().zip(context_cells.iter().map(|cell| cell.context)));
+        let global_id_table = GlobalIdTable::from_references(references)
+            .ok_or(Error::InvalidData { structure: "ObjectGroupObjectData", offset: fcr.start, reason: "CompactId refers to two different objects" })?;
+
+        Ok(Some(Object {
+            id,
+            jcid,
+            // The packaging doesn't count references
+            ref_count: 1,
+            body: ObjectBody::PropertySet(fcr),
+            md5_hash: None,
+            has_oid_references: !prop_set.oids.is_empty(),
+            has_osid_references: !prop_set.osids.is_empty(),
+            global_id_table: Arc::new(global_id_table)
+        }))
+    }
+
+    // Where an object's data is, or None if it was excluded from the package
+    fn data(&self, data: &ObjectData) -> Result<Option<FileChunkReference>> {
+        match data {
+            ObjectData::Object { data, .. } => Ok(Some(*data)),
+            ObjectData::Excluded { .. } => Ok(None),
+            ObjectData::BlobReference { blob_id, .. } => self.package.object_data_blobs.get(blob_id)
+                .copied()
+                .map(Some)
+                .ok_or(missing("ObjectDataBlob", "Object data BLOB is missing"))
+        }
+    }
+}
+
+// Data elements are found by id rather than position, so there is no useful offset to report
+fn missing(structure: &'static str, reason: &'static str) -> Error {
+    Error::InvalidData { structure, offset: 0, reason }
+}
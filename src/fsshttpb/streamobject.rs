@@ -0,0 +1,137 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::types::read_compact_u64;
+
+// Stream object types. See MS-FSSHTTPB 2.2.1.5.1
+pub const DATA_ELEMENT: u16 = 0x01;
+pub const OBJECT_DATA_BLOB: u16 = 0x02;
+pub const OBJECT_GROUP_OBJECT_EXCLUDED_DATA: u16 = 0x03;
+pub const OBJECT_GROUP_OBJECT_BLOB_DATA_DECLARATION: u16 = 0x05;
+pub const STORAGE_MANIFEST_ROOT_DECLARE: u16 = 0x07;
+pub const REVISION_MANIFEST_ROOT_DECLARE: u16 = 0x0A;
+pub const CELL_MANIFEST_CURRENT_REVISION: u16 = 0x0B;
+pub const STORAGE_MANIFEST_SCHEMA_GUID: u16 = 0x0C;
+pub const STORAGE_INDEX_REVISION_MAPPING: u16 = 0x0D;
+pub const STORAGE_INDEX_CELL_MAPPING: u16 = 0x0E;
+pub const STORAGE_INDEX_MANIFEST_MAPPING: u16 = 0x11;
+pub const DATA_ELEMENT_PACKAGE: u16 = 0x15;
+pub const OBJECT_GROUP_OBJECT_DATA: u16 = 0x16;
+pub const OBJECT_GROUP_OBJECT_DECLARE: u16 = 0x18;
+pub const REVISION_MANIFEST_OBJECT_GROUP_REFERENCES: u16 = 0x19;
+pub const REVISION_MANIFEST: u16 = 0x1A;
+pub const OBJECT_GROUP_OBJECT_DATA_BLOB_REFERENCE: u16 = 0x1C;
+pub const OBJECT_GROUP_DECLARATIONS: u16 = 0x1D;
+pub const OBJECT_GROUP_DATA: u16 = 0x1E;
+pub const OBJECT_GROUP_METADATA: u16 = 0x78;
+pub const OBJECT_GROUP_METADATA_DECLARATIONS: u16 = 0x79;
+// See MS-ONESTORE 2.8.1
+pub const ONE_NOTE_PACKAGING: u16 = 0x7A;
+
+const STRUCTURE: &str = "StreamObjectHeader";
+
+// The low two bits of every header say which kind it is
+const HEADER_START_16: u8 = 0x0;
+const HEADER_END_8: u8 = 0x1;
+const HEADER_START_32: u8 = 0x2;
+const HEADER_END_16: u8 = 0x3;
+
+// A 32 bit header with this length is followed by the real length
+const LARGE_LENGTH: u64 = 0x7FFF;
+
+// The start of a stream object. See MS-FSSHTTPB 2.2.1.5.2
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamObjectHeader {
+    pub offset: u64,
+    pub object_type: u16,
+    pub compound: bool,
+    pub len: u64
+}
+
+impl StreamObjectHeader {
+    // Reads a 16 or 32 bit start header
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<StreamObjectHeader> {
+        let offset = reader.stream_position()?;
+        let first = reader.read_u8()?;
+        reader.seek(SeekFrom::Start(offset))?;
+
+        match first & 0x3 {
+            HEADER_START_16 => {
+                let value = reader.read_u16::<LittleEndian>()?;
+                Ok(StreamObjectHeader {
+                    offset,
+                    object_type: (value >> 3) & 0x3F,
+                    compound: value & 0x4 != 0,
+                    len: (value >> 9) as u64
+                })
+            },
+            HEADER_START_32 => {
+                let value = reader.read_u32::<LittleEndian>()?;
+                let mut len = (value >> 17) as u64;
+                if len == LARGE_LENGTH {
+                    len = read_compact_u64(reader)?;
+                }
+                Ok(StreamObjectHeader {
+                    offset,
+                    object_type: ((value >> 3) & 0x3FFF) as u16,
+                    compound: value & 0x4 != 0,
+                    len
+                })
+            },
+            _ => Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Expected a stream object start header" })
+        }
+    }
+
+    // Reads a start header that must be of the given type
+    pub fn expect<T: Read + Seek>(reader: &mut T, object_type: u16) -> Result<StreamObjectHeader> {
+        let header = StreamObjectHeader::from_reader(reader)?;
+        if header.object_type != object_type {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: header.offset, reason: "Unexpected stream object type" });
+        }
+        Ok(header)
+    }
+}
+
+// If the next header ends a compound object of the given type, reads it and returns true. Otherwise
+// leaves the reader where it was. See MS-FSSHTTPB 2.2.1.5.3
+pub fn read_end<T: Read + Seek>(reader: &mut T, object_type: u16) -> Result<bool> {
+    let offset = reader.stream_position()?;
+    let first = reader.read_u8()?;
+
+    let end_type = match first & 0x3 {
+        HEADER_END_8 => (first >> 2) as u16,
+        HEADER_END_16 => {
+            reader.seek(SeekFrom::Start(offset))?;
+            reader.read_u16::<LittleEndian>()? >> 2
+        },
+        _ => {
+            reader.seek(SeekFrom::Start(offset))?;
+            return Ok(false);
+        }
+    };
+
+    if end_type != object_type {
+        return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "End header doesn't match the object it ends" });
+    }
+    Ok(true)
+}
+
+// Whether the next header ends a compound object of the given type, without reading it
+pub fn at_end<T: Read + Seek>(reader: &mut T, object_type: u16) -> Result<bool> {
+    let offset = reader.stream_position()?;
+    let is_end = read_end(reader, object_type)?;
+    reader.seek(SeekFrom::Start(offset))?;
+    Ok(is_end)
+}
+
+// Reads an end header that must be there
+pub fn expect_end<T: Read + Seek>(reader: &mut T, object_type: u16) -> Result<()> {
+    let offset = reader.stream_position()?;
+    if !read_end(reader, object_type)? {
+        return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Expected a stream object end header" });
+    }
+    Ok(())
+}
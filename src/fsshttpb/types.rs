@@ -0,0 +1,113 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::error::{Error, Result};
+use crate::structs::check_fcr_bounds;
+use crate::structs::exguid::ExGuid;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::guid::*;
+
+// A pair of ExGuids naming a cell. In a revision store the first is the context and the second the
+// object space. See MS-FSSHTTPB 2.2.1.10
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellId {
+    pub context: ExGuid,
+    pub object_space: ExGuid
+}
+
+impl CellId {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<CellId> {
+        Ok(CellId { context: read_exguid(reader)?, object_space: read_exguid(reader)? })
+    }
+}
+
+// See MS-FSSHTTPB 2.2.1.9. Only read past; nothing in a revision store depends on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SerialNumber {
+    pub guid: Guid,
+    pub value: u64
+}
+
+impl SerialNumber {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<Option<SerialNumber>> {
+        let offset = reader.stream_position()?;
+        match reader.read_u8()? {
+            0 => Ok(None),
+            0x80 => Ok(Some(SerialNumber { guid: Guid::from_reader(reader)?, value: reader.read_u64::<LittleEndian>()? })),
+            _ => Err(Error::InvalidData { structure: "SerialNumber", offset, reason: "Unknown serial number type" })
+        }
+    }
+}
+
+// Variable length integer whose first byte's lowest set bit gives its size. See MS-FSSHTTPB 2.2.1.1
+pub fn read_compact_u64<T: Read + Seek>(reader: &mut T) -> Result<u64> {
+    let offset = reader.stream_position()?;
+    let first = reader.read_u8()?;
+    if first == 0 {
+        return Ok(0);
+    }
+    if first == 0x80 {
+        return Ok(reader.read_u64::<LittleEndian>()?);
+    }
+
+    // One byte for each bit up to and including the lowest set bit
+    let len = first.trailing_zeros() as usize + 1;
+    if len > 7 {
+        return Err(Error::InvalidData { structure: "CompactUnsigned64bitInteger", offset, reason: "Unknown integer size" });
+    }
+    let mut bytes = [0; 8];
+    bytes[0] = first;
+    reader.read_exact(&mut bytes[1..len])?;
+    Ok(u64::from_le_bytes(bytes) >> len)
+}
+
+// See MS-FSSHTTPB 2.2.1.7
+pub fn read_exguid<T: Read + Seek>(reader: &mut T) -> Result<ExGuid> {
+    let offset = reader.stream_position()?;
+    let first = reader.read_u8()?;
+
+    let n = if first == 0 {
+        return Ok(ExGuid::nil());
+    } else if first & 0x07 == 0x04 {
+        (first >> 3) as u32
+    } else if first & 0x3F == 0x20 {
+        (reader.read_u8()? as u32) << 2 | (first >> 6) as u32
+    } else if first & 0x7F == 0x40 {
+        (reader.read_u16::<LittleEndian>()? as u32) << 1 | (first >> 7) as u32
+    } else if first == 0x80 {
+        reader.read_u32::<LittleEndian>()?
+    } else {
+        return Err(Error::InvalidData { structure: "ExtendedGUID", offset, reason: "Unknown extended GUID size" });
+    };
+
+    Ok(ExGuid { guid: Guid::from_reader(reader)?, n })
+}
+
+pub fn read_exguid_array<T: Read + Seek>(reader: &mut T) -> Result<Vec<ExGuid>> {
+    let count = read_count(reader)?;
+    (0..count).map(|_| read_exguid(reader)).collect()
+}
+
+pub fn read_cell_id_array<T: Read + Seek>(reader: &mut T) -> Result<Vec<CellId>> {
+    let count = read_count(reader)?;
+    (0..count).map(|_| CellId::from_reader(reader)).collect()
+}
+
+// A length prefixed byte array. Returns where the bytes are and leaves the reader after them.
+// See MS-FSSHTTPB 2.2.1.3
+pub fn read_binary_item<T: Read + Seek>(reader: &mut T, structure: &'static str) -> Result<FileChunkReference> {
+    let len = read_compact_u64(reader)?;
+    let fcr = FileChunkReference { start: reader.stream_position()?, len };
+    check_fcr_bounds(&fcr, reader, structure)?;
+    reader.seek(SeekFrom::Current(len as i64))?;
+    Ok(fcr)
+}
+
+// Every element takes at least a byte, so a count larger than the rest of the stream can't be right
+fn read_count<T: Read + Seek>(reader: &mut T) -> Result<u64> {
+    let count = read_compact_u64(reader)?;
+    let fcr = FileChunkReference { start: reader.stream_position()?, len: count };
+    check_fcr_bounds(&fcr, reader, "Array")?;
+    Ok(count)
+}
@@ -0,0 +1,14 @@
+pub mod error;
+pub mod structs;
+pub mod onestore;
+pub mod writer;
+pub mod commit;
+pub mod fsshttpb;
+pub mod cab;
+pub mod onenote;
+pub mod export;
+#[cfg(feature = "serde")]
+mod serialize;
+
+pub use error::{Error, Result};
+pub use onestore::{OneStore, StoreFormat};
@@ -1,54 +1,345 @@
-mod structs;
-
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
-
-use crate::structs::header::OneNoteFileHeader;
-use crate::structs::transactionlog::TransactionLog;
-use crate::structs::{FromFileChunk, ListFromFileChunk};
-use crate::structs::filechunkreference::FileChunkReference;
-use crate::structs::filenodelist::FileNodeList;
-
-// TODO: Spin this function off into a separate OneStore::parse() function
-fn main() -> Result<(), std::io::Error> {
-    let path = Path::new("./scratchu23.one");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display, why),
-        Ok(file) => file,
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+use onernote::export::html::{self, ResourceMode};
+#[cfg(feature = "serde")]
+use onernote::export::json;
+use onernote::export::{markdown, unique_file_name};
+use onernote::onenote::{Attachment, NotebookPackage, Section};
+use onernote::structs::filenode::FileNode;
+use onernote::structs::objecttable::ObjectBody;
+use onernote::writer;
+use onernote::OneStore;
+
+// Exit codes; clap exits with 2 for usage errors
+const EXIT_PARSE_FAILED: u8 = 1;
+const EXIT_OUTPUT_FAILED: u8 = 3;
+const EXIT_UNSUPPORTED: u8 = 4;
+
+#[cfg(not(feature = "serde"))]
+const NO_JSON: &str = "JSON output needs onernote to be built with the serde feature";
+
+#[derive(Parser)]
+#[command(name = "onernote", version, about = "Inspect and convert OneNote revision store files")]
+#[command(after_help = "Exit codes: 0 success, 1 the file couldn't be parsed, 2 usage error, 3 output couldn't be written, 4 unsupported request")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Summarize the file header and object spaces
+    Info { file: PathBuf },
+    /// Print raw revision store structures
+    Dump(DumpArgs),
+    /// List the pages of a section
+    Pages { file: PathBuf },
+    /// List the files in a .onepkg notebook package and parse its notebook
+    Package { file: PathBuf },
+    /// Write every embedded image and attached file to a directory
+    Extract {
+        file: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf
+    },
+    /// Write a copy of a .one or .onetoc2 file with every structure laid out anew
+    Rewrite {
+        file: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf
+    },
+    /// Convert a section to another format
+    Export {
+        file: PathBuf,
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+        /// Directory to write the export to
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+        /// Write HTML images and attachments next to the pages instead of embedding them
+        #[arg(long)]
+        sidecar: bool
+    }
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("structures").required(true).multiple(true)))]
+struct DumpArgs {
+    file: PathBuf,
+    /// Every file node, grouped by the list it was read from
+    #[arg(long, group = "structures")]
+    nodes: bool,
+    /// Node counts committed by the transaction log
+    #[arg(long, group = "structures")]
+    txlog: bool,
+    /// Objects of the latest revision of every object space
+    #[arg(long, group = "structures")]
+    objects: bool,
+    /// Every parsed structure as JSON (needs the serde feature)
+    #[arg(long, group = "structures", conflicts_with_all = ["nodes", "txlog", "objects"])]
+    json: bool
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Md,
+    Html,
+    Json
+}
+
+// Why a command failed, mapped to an exit code
+enum Failure {
+    Parse(PathBuf, onernote::Error),
+    Output(PathBuf, io::Error),
+    Unsupported(String)
+}
+
+type CommandResult = Result<(), Failure>;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Info { file } => info(&file),
+        Command::Dump(args) => dump(&args),
+        Command::Pages { file } => pages(&file),
+        Command::Package { file } => package(&file),
+        Command::Extract { file, output } => extract(&file, &output),
+        Command::Rewrite { file, output } => rewrite(&file, &output),
+        Command::Export { file, format, output, sidecar } => export(&file, format, &output, sidecar)
     };
 
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Parse(path, why)) => {
+            eprintln!("couldn't parse {}: {}", path.display(), why);
+            ExitCode::from(EXIT_PARSE_FAILED)
+        },
+        Err(Failure::Output(path, why)) => {
+            eprintln!("couldn't write {}: {}", path.display(), why);
+            ExitCode::from(EXIT_OUTPUT_FAILED)
+        },
+        Err(Failure::Unsupported(why)) => {
+            eprintln!("{}", why);
+            ExitCode::from(EXIT_UNSUPPORTED)
+        }
+    }
+}
+
+// Parses the store and keeps the reader open for commands that read objects lazily
+fn open(path: &Path) -> Result<(OneStore, BufReader<File>), Failure> {
+    let file = File::open(path).map_err(|e| Failure::Parse(path.to_path_buf(), e.into()))?;
     let mut reader = BufReader::new(file);
-    let start_of_file: FileChunkReference = FileChunkReference { start: 0, len: 1024 };
+    let store = OneStore::parse(&mut reader).map_err(|e| Failure::Parse(path.to_path_buf(), e))?;
+    Ok((store, reader))
+}
+
+fn open_section(path: &Path) -> Result<(OneStore, BufReader<File>, Section), Failure> {
+    let (store, mut reader) = open(path)?;
+    let section = Section::from_store(&store, &mut reader).map_err(|e| Failure::Parse(path.to_path_buf(), e))?;
+    Ok((store, reader, section))
+}
+
+fn info(path: &Path) -> CommandResult {
+    let (store, _) = open(path)?;
+    let header = &store.header;
+
+    println!("format:              {:?}", store.format);
+    println!("file type:           {:?}", header.file_type);
+    println!("file guid:           {{{}}}", header.file_guid);
+    println!("file version:        {{{}}} (generation {})", header.file_version, header.file_version_generation);
+    println!("expected length:     {}", header.expected_file_length);
+    println!("actual length:       {}", store.file_length);
+    println!("transactions:        {}", header.transactions_in_log);
+    println!("ancestor guid:       {{{}}}", header.ancestor_guid);
+    println!("format versions:     last {:#x}, oldest {:#x}, newest {:#x}, oldest reader {:#x}", header.last_code_that_wrote_to_this_file,
+        header.oldest_code_that_has_written_to_this_file, header.newest_code_that_has_written_to_this_file, header.oldest_code_that_may_read_this_file);
+    println!("build numbers:       created {}, last written {}, oldest {}, newest {}", header.created_build_number,
+        header.last_written_build_number, header.oldest_written_build_number, header.newest_written_build_number);
+    println!("flags:               needs defrag {}, repaired {}, needs garbage collect {}, no embedded files {}", header.needs_defrag,
+        header.repaired_file, header.needs_garbage_collect, header.has_no_embedded_file_objects);
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    match header.verify_name(&name) {
+        Ok(()) => println!("name crc:            {:#010x} (matches)", header.crc_name),
+        Err(_) => println!("name crc:            {:#010x} (doesn't match the file name)", header.crc_name)
+    }
+    println!("root object space:   {}", store.root_object_space_id);
+    println!("object spaces:       {}", store.object_spaces.len());
+    println!("revisions:           {}", store.object_spaces.iter().map(|space| space.revisions.len()).sum::<usize>());
+    println!("file data objects:   {}", store.file_data_store.objects.len());
+    println!("free chunks:         {} ({} bytes)", store.free_chunk_list.free_chunks.len(), store.free_chunk_list.total_len());
+    println!("hashed chunks:       {}", store.hashed_chunk_list.chunks.len());
+    Ok(())
+}
+
+fn dump(args: &DumpArgs) -> CommandResult {
+    let (store, _) = open(&args.file)?;
+
+    if args.json {
+        return dump_json(&store);
+    }
 
-    // Read header from beginning of document
-    let header: OneNoteFileHeader = OneNoteFileHeader::from_reader(&start_of_file, &mut reader)?;
-    println!("{:#?}", header);
+    if args.txlog {
+        println!("transaction log:");
+        let mut lists: Vec<_> = store.transaction_log.iter().collect();
+        lists.sort();
+        for (list_id, count) in lists {
+            println!("  list {:#x}: {} nodes", list_id, count);
+        }
+    }
 
-    // Read transaction log based on fcr and length given in header
-    let transaction_log_fcr: FileChunkReference = header.transaction_log;
-    let transaction_list_len: u64 = header.transactions_in_log.into();
-    let transaction_log: TransactionLog = TransactionLog::from_reader(&transaction_log_fcr, &mut reader, transaction_list_len)?;
-    println!("{:#?}", transaction_log);
+    if args.nodes {
+        println!("root file node list {:#x}:", store.root_file_node_list.id);
+        print_nodes(&store.root_file_node_list.file_nodes, 1);
+        for space in &store.object_spaces {
+            println!("object space {}:", space.gosid);
+            for revision in &space.revisions {
+                println!("  revision {}:", revision.rid);
+                print_nodes(&revision.file_nodes, 2);
+                for group in &revision.object_groups {
+                    println!("    object group {}:", group.id);
+                    print_nodes(&group.file_nodes, 3);
+                }
+            }
+        }
+    }
 
-    // Number of file nodes in the list is given by the transaction log entry for this list
-    let file_node_list_root_fcr: FileChunkReference = header.file_node_list_root;
-    let file_node_list_root = FileNodeList::from_reader(&file_node_list_root_fcr, &mut reader, &transaction_log).unwrap();
-    println!("{:#?}", file_node_list_root);
+    if args.objects {
+        for space in &store.object_spaces {
+            println!("object space {}:", space.gosid);
+            let revision = match space.latest_revision() {
+                Some(revision) => revision,
+                None => continue
+            };
+            let objects = space.effective_objects(&revision.rid);
+            let mut objects: Vec<_> = objects.values().collect();
+            objects.sort_by_key(|object| (object.id.guid, object.id.n));
+            for object in objects {
+                let body = match &object.body {
+                    ObjectBody::PropertySet(fcr) => format!("property set at {:#x}+{:#x}", fcr.start, fcr.len),
+                    ObjectBody::FileData { reference, extension } => format!("file data {} {}", reference, extension)
+                };
+                println!("  {} jcid={:#010x} refs={} {}", object.id, object.jcid.0, object.ref_count, body);
+            }
+        }
+    }
 
-    // Can we read the root object space?
-    // TODO: Parse the revision manifest lists for an object space.
-    let object_space_1_fcr = &file_node_list_root.file_nodes[0].file_chunk_ref;
-    let object_space_1 = FileNodeList::from_reader(object_space_1_fcr, &mut reader, &transaction_log)?;
-    println!("{:#?}", object_space_1);
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn dump_json(store: &OneStore) -> CommandResult {
+    let stdout = io::stdout().lock();
+    json::write_store(store, stdout).map_err(|e| match e {
+        onernote::Error::Io(e) => Failure::Output(PathBuf::from("-"), e),
+        e => Failure::Unsupported(e.to_string())
+    })?;
+    println!();
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn dump_json(_: &OneStore) -> CommandResult {
+    Err(Failure::Unsupported(NO_JSON.to_string()))
+}
+
+fn print_nodes(nodes: &[FileNode], depth: usize) {
+    for node in nodes {
+        println!("{:indent$}{:#010x} {:?} ({} bytes)", "", node.offset, node.file_type, node.size, indent = depth * 2);
+    }
+}
 
-    // Read latest revision
-    let revision_manifest_fcr = &object_space_1.file_nodes[1].file_chunk_ref;
-    let revision_manifest = FileNodeList::from_reader(revision_manifest_fcr, &mut reader, &transaction_log)?;
-    println!("{:#?}", revision_manifest);
+fn pages(path: &Path) -> CommandResult {
+    let (_, _, section) = open_section(path)?;
 
+    for (index, page) in section.pages().enumerate() {
+        let indent = page.level.saturating_sub(1) as usize * 2;
+        let title = page.title_text().unwrap_or_else(|| "(untitled)".to_string());
+        println!("{:3} {:indent$}{}", index + 1, "", title, indent = indent);
+    }
     Ok(())
+}
+
+fn package(path: &Path) -> CommandResult {
+    let package = NotebookPackage::open(path).map_err(|e| Failure::Parse(path.to_path_buf(), e))?;
+    for member in &package.members {
+        println!("{:10} {}", member.data.len(), member.path.display());
+    }
+
+    let notebook = package.notebook().map_err(|e| Failure::Parse(path.to_path_buf(), e))?;
+    let sections = notebook.sections();
+    println!("notebook {:?}: {} sections, {} pages", notebook.name, sections.len(),
+        sections.iter().map(|entry| entry.section.pages().count()).sum::<usize>());
+    Ok(())
+}
+
+fn extract(path: &Path, output: &Path) -> CommandResult {
+    let (store, mut reader) = open(path)?;
+    let attachments = Attachment::list(&store, &mut reader).map_err(|e| Failure::Parse(path.to_path_buf(), e))?;
+
+    fs::create_dir_all(output).map_err(|e| Failure::Output(output.to_path_buf(), e))?;
+    let mut used_names = HashSet::new();
+    for attachment in &attachments {
+        let mut data = match attachment.open(&store, &mut reader).map_err(|e| Failure::Parse(path.to_path_buf(), e))? {
+            Some(data) => data,
+            None => {
+                eprintln!("skipping {:?}: data is not stored in this file", attachment.location);
+                continue;
+            }
+        };
 
-}
\ No newline at end of file
+        // Names come from the file so they are sanitized before use
+        let name = attachment.filename.clone()
+            .unwrap_or_else(|| format!("{}{}", attachment.object_id.guid, attachment.extension));
+        let destination = output.join(unique_file_name(&name, &mut used_names));
+        write_stream(&mut data, &destination)?;
+        println!("{}", destination.display());
+    }
+    Ok(())
+}
+
+fn rewrite(path: &Path, output: &Path) -> CommandResult {
+    let (store, mut reader) = open(path)?;
+    writer::save(&store, &mut reader, output).map_err(|e| match e {
+        onernote::Error::Io(e) => Failure::Output(output.to_path_buf(), e),
+        e => Failure::Parse(path.to_path_buf(), e)
+    })
+}
+
+fn write_stream<R: Read>(data: &mut R, destination: &Path) -> CommandResult {
+    let mut file = File::create(destination).map_err(|e| Failure::Output(destination.to_path_buf(), e))?;
+    io::copy(data, &mut file).map_err(|e| Failure::Output(destination.to_path_buf(), e))?;
+    Ok(())
+}
+
+fn export(path: &Path, format: ExportFormat, output: &Path, sidecar: bool) -> CommandResult {
+    let (store, mut reader, section) = open_section(path)?;
+
+    let written = match format {
+        ExportFormat::Md => markdown::export_section(&section, &store, &mut reader, output),
+        ExportFormat::Html => {
+            let mode = if sidecar { ResourceMode::Sidecar } else { ResourceMode::Embedded };
+            html::export_section(&section, &store, &mut reader, output, mode)
+        },
+        #[cfg(feature = "serde")]
+        ExportFormat::Json => json::export_section(&section, output),
+        #[cfg(not(feature = "serde"))]
+        ExportFormat::Json => return Err(Failure::Unsupported(NO_JSON.to_string()))
+    };
+
+    // The store has been read by now, so I/O errors almost always come from writing the export
+    let written = written.map_err(|e| match e {
+        onernote::Error::Io(e) => Failure::Output(output.to_path_buf(), e),
+        e => Failure::Parse(path.to_path_buf(), e)
+    })?;
+    for page in written {
+        println!("{}", page.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, Take};
+
+use crate::error::Result;
+use crate::onestore::OneStore;
+use crate::structs::exguid::ExGuid;
+use crate::structs::objecttable::ObjectBody;
+
+use super::embedded::{FileDataLocation, FileDataReference};
+use super::jcid;
+use super::object::ObjectReader;
+use super::property;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum AttachmentKind {
+    Image,
+    // Attached files, including audio and video recordings
+    File,
+    // Any other file data object, identified by its JCID
+    Other(u32)
+}
+
+// A file data object of any object space in the file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Attachment {
+    pub object_id: ExGuid,
+    pub kind: AttachmentKind,
+    pub location: FileDataLocation,
+    // None if the data isn't in this file's file data store
+    pub size: Option<u64>,
+    // Taken from the image or embedded file node that points at the object
+    pub filename: Option<String>,
+    pub extension: String
+}
+
+impl Attachment {
+    // Attachments of the latest revision of every object space, in object space order
+    pub fn list<T: Read + Seek>(store: &OneStore, reader: &mut T) -> Result<Vec<Attachment>> {
+        let mut reader = ObjectReader::new(store, reader);
+        let mut attachments = Vec::new();
+        let mut seen = HashSet::new();
+
+        for object_space in &store.object_spaces {
+            let space = match reader.space(&object_space.gosid) {
+                Some(space) => space,
+                None => continue
+            };
+
+            // Names are stored on the nodes that reference the file data objects
+            let mut filenames = HashMap::new();
+            for object in space.objects.values() {
+                let (container, name) = match object.jcid.0 {
+                    jcid::IMAGE_NODE => (property::PICTURE_CONTAINER, property::IMAGE_FILENAME),
+                    jcid::EMBEDDED_FILE_NODE => (property::EMBEDDED_FILE_CONTAINER, property::EMBEDDED_FILE_NAME),
+                    _ => continue
+                };
+                if let Some(node) = reader.read(&space, &object.id)? {
+                    if let (Some(container), Some(name)) = (node.object_id(container), node.string(name)) {
+                        filenames.insert(container, name);
+                    }
+                }
+            }
+
+            let mut file_data: Vec<_> = space.objects.values()
+                .filter(|object| matches!(object.body, ObjectBody::FileData { .. }))
+                .collect();
+            file_data.sort_by_key(|object| (object.id.guid, object.id.n));
+
+            for object in file_data {
+                if !seen.insert(object.id) {
+                    continue;
+                }
+                let reference = match reader.file_data(&space, &object.id) {
+                    Some(reference) => reference,
+                    None => continue
+                };
+
+                attachments.push(Attachment::new(store, &reference, object.jcid.0, filenames.get(&object.id).cloned()));
+            }
+        }
+
+        Ok(attachments)
+    }
+
+    // Streams the attachment's bytes. None if they aren't stored in this file
+    pub fn open<'a, T: Read + Seek>(&self, store: &OneStore, reader: &'a mut T) -> Result<Option<Take<&'a mut T>>> {
+        match &self.location {
+            FileDataLocation::FileDataStore(guid) => match store.file_data_store.get(guid) {
+                Some(object) => Ok(Some(object.open(reader)?)),
+                None => Ok(None)
+            },
+            _ => Ok(None)
+        }
+    }
+
+    fn new(store: &OneStore, reference: &FileDataReference, jcid: u32, filename: Option<String>) -> Attachment {
+        let location = reference.location();
+        let size = match &location {
+            FileDataLocation::FileDataStore(guid) => store.file_data_store.get(guid).map(|object| object.len),
+            _ => None
+        };
+
+        Attachment {
+            object_id: reference.object_id,
+            kind: match jcid {
+                jcid::PICTURE_CONTAINER_14 => AttachmentKind::Image,
+                jcid::EMBEDDED_FILE_CONTAINER => AttachmentKind::File,
+                jcid => AttachmentKind::Other(jcid)
+            },
+            location,
+            size,
+            filename,
+            extension: reference.extension.clone()
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{Read, Seek};
+
+use crate::error::Result;
+use crate::structs::exguid::ExGuid;
+use crate::structs::guid::Guid;
+
+use super::object::{ObjectReader, PropertyObject, Space};
+use super::property;
+
+// Points at the bytes of an image or attached file. See MS-ONESTORE 2.5.27
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileDataReference {
+    pub object_id: ExGuid,
+    // "<ifndf>{guid}" for data in the file data store, "<file>name" for a file next to the section
+    pub reference: String,
+    pub extension: String
+}
+
+// Where the bytes of a file data object are kept
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FileDataLocation {
+    // A FileDataStoreObject in the same file
+    FileDataStore(Guid),
+    // A file in the onefiles folder next to the section
+    External(String),
+    // The reference is "<invfdo>" or couldn't be parsed
+    Invalid
+}
+
+// A jcidImageNode. Sizes and offsets are in half-inch increments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Image {
+    pub data: Option<FileDataReference>,
+    pub filename: Option<String>,
+    pub alt_text: Option<String>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub offset_horizontal: Option<f32>,
+    pub offset_vertical: Option<f32>,
+    pub layout_max_width: Option<f32>,
+    pub layout_max_height: Option<f32>,
+    pub is_background: bool
+}
+
+// A jcidEmbeddedFileNode
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EmbeddedFile {
+    pub data: Option<FileDataReference>,
+    pub filename: Option<String>,
+    pub source_path: Option<String>,
+    pub offset_horizontal: Option<f32>,
+    pub offset_vertical: Option<f32>,
+    pub layout_max_width: Option<f32>,
+    pub layout_max_height: Option<f32>
+}
+
+impl FileDataReference {
+    pub fn location(&self) -> FileDataLocation {
+        if let Some(guid) = self.reference.strip_prefix("<ifndf>") {
+            match Guid::parse_str(guid.trim_start_matches('{').trim_end_matches('}')) {
+                Ok(guid) => FileDataLocation::FileDataStore(guid),
+                Err(_) => FileDataLocation::Invalid
+            }
+        } else if let Some(name) = self.reference.strip_prefix("<file>") {
+            FileDataLocation::External(name.to_string())
+        } else {
+            FileDataLocation::Invalid
+        }
+    }
+}
+
+impl Image {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>) -> Result<Image> {
+        let data = object.object_id(property::PICTURE_CONTAINER)
+            .and_then(|container| reader.file_data(space, &container));
+
+        Ok(Image {
+            data,
+            filename: object.string(property::IMAGE_FILENAME),
+            alt_text: object.string(property::IMAGE_ALT_TEXT),
+            width: object.f32(property::PICTURE_WIDTH),
+            height: object.f32(property::PICTURE_HEIGHT),
+            offset_horizontal: object.f32(property::OFFSET_FROM_PARENT_HORIZ),
+            offset_vertical: object.f32(property::OFFSET_FROM_PARENT_VERT),
+            layout_max_width: object.f32(property::LAYOUT_MAX_WIDTH),
+            layout_max_height: object.f32(property::LAYOUT_MAX_HEIGHT),
+            is_background: object.bool(property::IS_BACKGROUND).unwrap_or(false)
+        })
+    }
+}
+
+impl EmbeddedFile {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>) -> Result<EmbeddedFile> {
+        let data = object.object_id(property::EMBEDDED_FILE_CONTAINER)
+            .and_then(|container| reader.file_data(space, &container));
+
+        Ok(EmbeddedFile {
+            data,
+            filename: object.string(property::EMBEDDED_FILE_NAME),
+            source_path: object.string(property::SOURCE_FILEPATH),
+            offset_horizontal: object.f32(property::OFFSET_FROM_PARENT_HORIZ),
+            offset_vertical: object.f32(property::OFFSET_FROM_PARENT_VERT),
+            layout_max_width: object.f32(property::LAYOUT_MAX_WIDTH),
+            layout_max_height: object.f32(property::LAYOUT_MAX_HEIGHT)
+        })
+    }
+}
@@ -0,0 +1,23 @@
+// JCID values of the objects that make up a notebook, its sections and their pages. See MS-ONE 2.2
+// Both the root of a .onetoc2 file and each of its entries
+pub const PERSISTABLE_PROPERTY_CONTAINER_FOR_TOC: u32 = 0x00020001;
+pub const SECTION_NODE: u32 = 0x00060007;
+pub const PAGE_SERIES_NODE: u32 = 0x00060008;
+pub const PAGE_NODE: u32 = 0x0006000B;
+pub const OUTLINE_NODE: u32 = 0x0006000C;
+pub const OUTLINE_ELEMENT_NODE: u32 = 0x0006000D;
+pub const RICH_TEXT_OE_NODE: u32 = 0x0006000E;
+pub const IMAGE_NODE: u32 = 0x00060011;
+pub const NUMBER_LIST_NODE: u32 = 0x00060012;
+pub const OUTLINE_GROUP: u32 = 0x00060019;
+pub const TABLE_NODE: u32 = 0x00060022;
+pub const TABLE_ROW_NODE: u32 = 0x00060023;
+pub const TABLE_CELL_NODE: u32 = 0x00060024;
+pub const TITLE_NODE: u32 = 0x0006002C;
+pub const PAGE_META_DATA: u32 = 0x00020030;
+pub const SECTION_META_DATA: u32 = 0x00020031;
+pub const EMBEDDED_FILE_NODE: u32 = 0x00060035;
+pub const EMBEDDED_FILE_CONTAINER: u32 = 0x00080036;
+pub const PAGE_MANIFEST_NODE: u32 = 0x00060037;
+pub const PICTURE_CONTAINER_14: u32 = 0x00080039;
+pub const PARAGRAPH_STYLE_OBJECT: u32 = 0x0012004D;
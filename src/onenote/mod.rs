@@ -0,0 +1,25 @@
+// The MS-ONE data model: notebooks, sections, pages and their contents built from the objects of a revision store
+pub mod jcid;
+pub mod property;
+pub mod notebook;
+pub mod package;
+pub mod toc;
+pub mod section;
+pub mod page;
+pub mod outline;
+pub mod richtext;
+pub mod table;
+pub mod embedded;
+pub mod attachment;
+mod object;
+
+pub use notebook::{Notebook, NotebookEntry, SectionEntry, SectionGroup};
+pub use package::{NotebookPackage, PackageMember};
+pub use toc::{TableOfContents, TocEntry};
+pub use section::{Section, PageSeries};
+pub use page::{Page, PageContent, Title};
+pub use outline::{Outline, OutlineItem, OutlineGroup, OutlineElement, Content, List};
+pub use richtext::{RichText, TextRun, ParagraphStyle};
+pub use table::{Table, TableRow, TableCell};
+pub use embedded::{Image, EmbeddedFile, FileDataReference, FileDataLocation};
+pub use attachment::{Attachment, AttachmentKind};
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+
+use super::package::NotebookPackage;
+use super::section::Section;
+use super::toc::{self, TableOfContents};
+
+// Deleted sections are moved into this folder; OneNote doesn't show it as a section group
+const RECYCLE_BIN: &str = "OneNote_RecycleBin";
+
+// A notebook folder: its .onetoc2 and every section and section group in it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Notebook {
+    // Name of the folder
+    pub name: String,
+    pub color: Option<u32>,
+    pub entries: Vec<NotebookEntry>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum NotebookEntry {
+    Section(SectionEntry),
+    SectionGroup(SectionGroup)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SectionEntry {
+    pub path: PathBuf,
+    pub display_name: String,
+    pub color: Option<u32>,
+    pub section: Section
+}
+
+// A subfolder of the notebook, with its own .onetoc2
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SectionGroup {
+    pub path: PathBuf,
+    pub display_name: String,
+    pub color: Option<u32>,
+    pub entries: Vec<NotebookEntry>
+}
+
+impl Notebook {
+    // Reads the .onetoc2 in directory and every section file and section group folder next to it.
+    // Entries are in the order the table of contents gives them, followed by any files it doesn't
+    // list in name order.
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Notebook> {
+        let directory = directory.as_ref();
+        Notebook::read(file_name(directory), directory, &Disk)
+    }
+
+    // Reads a notebook out of a .onepkg package. Paths in its entries are paths inside the package
+    pub fn from_package(package: &NotebookPackage) -> Result<Notebook> {
+        Notebook::read(package.name.clone(), Path::new(""), package)
+    }
+
+    fn read<F: Files>(name: String, directory: &Path, files: &F) -> Result<Notebook> {
+        let toc_path = find_toc(directory, files)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Notebook folder has no .onetoc2 file"))?;
+        let toc = files.table_of_contents(&toc_path)?;
+
+        Ok(Notebook {
+            name,
+            color: toc.color,
+            entries: read_entries(directory, &toc, files)?
+        })
+    }
+
+    // Every section in the notebook, depth first in display order
+    pub fn sections(&self) -> Vec<&SectionEntry> {
+        let mut sections = Vec::new();
+        collect_sections(&self.entries, &mut sections);
+        sections
+    }
+}
+
+fn collect_sections<'a>(entries: &'a [NotebookEntry], sections: &mut Vec<&'a SectionEntry>) {
+    for entry in entries {
+        match entry {
+            NotebookEntry::Section(section) => sections.push(section),
+            NotebookEntry::SectionGroup(group) => collect_sections(&group.entries, sections)
+        }
+    }
+}
+
+// Where a notebook's files are read from: a folder on disk or a .onepkg package
+trait Files {
+    // Every file and folder directly inside directory
+    fn list(&self, directory: &Path) -> Result<Vec<PathBuf>>;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn is_file(&self, path: &Path) -> bool;
+    fn table_of_contents(&self, path: &Path) -> Result<TableOfContents>;
+    fn section(&self, path: &Path) -> Result<Section>;
+}
+
+struct Disk;
+
+impl Files for Disk {
+    fn list(&self, directory: &Path) -> Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn table_of_contents(&self, path: &Path) -> Result<TableOfContents> {
+        TableOfContents::open(path)
+    }
+
+    fn section(&self, path: &Path) -> Result<Section> {
+        Section::open(path)
+    }
+}
+
+// Packages only list files; a folder is any path that is a prefix of one
+impl Files for NotebookPackage {
+    fn list(&self, directory: &Path) -> Result<Vec<PathBuf>> {
+        let mut children: Vec<PathBuf> = self.paths()
+            .filter_map(|path| path.strip_prefix(directory).ok())
+            .filter_map(|relative| relative.components().next())
+            .map(|child| directory.join(child))
+            .collect();
+        children.dedup();
+        Ok(children)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.paths().any(|member| member != path && member.starts_with(path))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    fn table_of_contents(&self, path: &Path) -> Result<TableOfContents> {
+        NotebookPackage::table_of_contents(self, path)
+    }
+
+    fn section(&self, path: &Path) -> Result<Section> {
+        NotebookPackage::section(self, path)
+    }
+}
+
+fn read_entries<F: Files>(directory: &Path, toc: &TableOfContents, files: &F) -> Result<Vec<NotebookEntry>> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+
+    for toc_entry in &toc.entries {
+        // Windows file names are case insensitive, so the table of contents may not match the folder
+        if !seen.insert(toc_entry.filename.to_lowercase()) {
+            continue;
+        }
+        let path = directory.join(&toc_entry.filename);
+        if let Some(entry) = read_entry(&path, toc_entry.color, files)? {
+            entries.push(entry);
+        }
+    }
+
+    let mut unlisted = files.list(directory)?;
+    unlisted.sort();
+    unlisted.dedup();
+    for path in unlisted {
+        if !seen.insert(file_name(&path).to_lowercase()) {
+            continue;
+        }
+        if files.is_dir(&path) && find_toc(&path, files)?.is_none() {
+            continue;
+        }
+        if let Some(entry) = read_entry(&path, None, files)? {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+// None for paths that are missing or aren't sections or section groups
+fn read_entry<F: Files>(path: &Path, color: Option<u32>, files: &F) -> Result<Option<NotebookEntry>> {
+    let name = file_name(path);
+
+    if files.is_dir(path) {
+        if name == RECYCLE_BIN || name.starts_with('.') {
+            return Ok(None);
+        }
+        // A group whose .onetoc2 is missing still shows its sections, in name order
+        let toc = match find_toc(path, files)? {
+            Some(toc_path) => files.table_of_contents(&toc_path)?,
+            None => TableOfContents::default()
+        };
+        return Ok(Some(NotebookEntry::SectionGroup(SectionGroup {
+            path: path.to_path_buf(),
+            display_name: name,
+            color: color.or(toc.color),
+            entries: read_entries(path, &toc, files)?
+        })));
+    }
+
+    if !files.is_file(path) || !has_extension(path, "one") {
+        return Ok(None);
+    }
+    Ok(Some(NotebookEntry::Section(SectionEntry {
+        path: path.to_path_buf(),
+        display_name: toc::display_name(&name).to_string(),
+        color,
+        section: files.section(path)?
+    })))
+}
+
+// The first .onetoc2 in directory by name; OneNote only ever writes one
+fn find_toc<F: Files>(directory: &Path, files: &F) -> Result<Option<PathBuf>> {
+    let mut tocs = Vec::new();
+    for path in files.list(directory)? {
+        if files.is_file(&path) && has_extension(&path, "onetoc2") {
+            tocs.push(path);
+        }
+    }
+    tocs.sort();
+    Ok(tocs.into_iter().next())
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
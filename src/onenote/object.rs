@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::onestore::OneStore;
+use crate::structs::exguid::ExGuid;
+use crate::structs::globalidtable::GlobalIdTable;
+use crate::structs::objecttable::{ObjectBody, ObjectTable};
+use crate::structs::propertyset::{PropertySet, PropertyValue};
+
+use super::embedded::FileDataReference;
+
+// Outline elements and tables can nest; anything deeper than this is treated as a reference cycle
+pub(crate) const MAX_NESTING_DEPTH: u32 = 64;
+
+// The objects visible in the latest revision of an object space
+pub(crate) struct Space {
+    pub gosid: ExGuid,
+    pub objects: ObjectTable,
+    pub root_objects: HashMap<u32, ExGuid>,
+    // Offset of the revision manifest, for error reporting
+    pub offset: u64
+}
+
+// An object whose property set has been read and whose references can be resolved
+pub(crate) struct PropertyObject {
+    pub jcid: u32,
+    pub offset: u64,
+    properties: PropertySet,
+    global_id_table: Arc<GlobalIdTable>
+}
+
+// Reads objects out of a parsed store on demand
+pub(crate) struct ObjectReader<'a, T: Read + Seek> {
+    pub store: &'a OneStore,
+    pub reader: &'a mut T
+}
+
+impl<'a, T: Read + Seek> ObjectReader<'a, T> {
+    pub fn new(store: &'a OneStore, reader: &'a mut T) -> ObjectReader<'a, T> {
+        ObjectReader { store, reader }
+    }
+
+    // None if the object space doesn't exist or has no default content revision
+    pub fn space(&self, gosid: &ExGuid) -> Option<Space> {
+        let object_space = self.store.object_space(gosid)?;
+        let revision = object_space.latest_revision()?;
+
+        Some(Space {
+            gosid: *gosid,
+            objects: object_space.effective_objects(&revision.rid),
+            root_objects: object_space.effective_root_objects(&revision.rid),
+            offset: revision.file_nodes.first().map_or(0, |node| node.offset)
+        })
+    }
+
+    // None if the object isn't declared in the space or holds file data instead of properties
+    pub fn read(&mut self, space: &Space, id: &ExGuid) -> Result<Option<PropertyObject>> {
+        let object = match space.objects.get(id) {
+            Some(object) => object,
+            None => return Ok(None)
+        };
+        let offset = match &object.body {
+            ObjectBody::PropertySet(fcr) => fcr.start,
+            ObjectBody::FileData { .. } => return Ok(None)
+        };
+
+        let properties = match object.read_property_set(self.reader)? {
+            Some(prop_set) => prop_set.body,
+            None => return Ok(None)
+        };
+
+        Ok(Some(PropertyObject {
+            jcid: object.jcid.0,
+            offset,
+            properties,
+            global_id_table: object.global_id_table.clone()
+        }))
+    }
+
+    // The root object with the given role, which must exist
+    pub fn read_root(&mut self, space: &Space, role: u32) -> Result<PropertyObject> {
+        let root = space.root_objects.get(&role)
+            .ok_or(Error::InvalidData { structure: "RevisionManifest", offset: space.offset, reason: "Revision has no root object" })?;
+
+        self.read(space, root)?
+            .ok_or(Error::InvalidData { structure: "RevisionManifest", offset: space.offset, reason: "Root object is not declared" })
+    }
+
+    // Objects that are missing from the space are skipped
+    pub fn read_all(&mut self, space: &Space, ids: &[ExGuid]) -> Result<Vec<PropertyObject>> {
+        let mut objects = Vec::new();
+        for id in ids {
+            if let Some(object) = self.read(space, id)? {
+                objects.push(object);
+            }
+        }
+        Ok(objects)
+    }
+
+    pub fn file_data(&self, space: &Space, id: &ExGuid) -> Option<FileDataReference> {
+        match &space.objects.get(id)?.body {
+            ObjectBody::FileData { reference, extension } => Some(FileDataReference {
+                object_id: *id,
+                reference: reference.clone(),
+                extension: extension.clone()
+            }),
+            ObjectBody::PropertySet(_) => None
+        }
+    }
+}
+
+impl PropertyObject {
+    pub fn get(&self, prid: u32) -> Option<&PropertyValue> {
+        self.properties.get(prid)
+    }
+
+    // Bool properties store their value in the property id
+    pub fn bool(&self, prid: u32) -> Option<bool> {
+        match self.get(prid)? {
+            PropertyValue::Bool(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn u8(&self, prid: u32) -> Option<u8> {
+        match self.get(prid)? {
+            PropertyValue::OneByte(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn u16(&self, prid: u32) -> Option<u16> {
+        match self.get(prid)? {
+            PropertyValue::TwoBytes(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn u32(&self, prid: u32) -> Option<u32> {
+        match self.get(prid)? {
+            PropertyValue::FourBytes(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn f32(&self, prid: u32) -> Option<f32> {
+        self.u32(prid).map(f32::from_bits)
+    }
+
+    pub fn bytes(&self, prid: u32) -> Option<&[u8]> {
+        match self.get(prid)? {
+            PropertyValue::Bytes(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn string(&self, prid: u32) -> Option<String> {
+        self.bytes(prid).map(decode_utf16)
+    }
+
+    pub fn object_id(&self, prid: u32) -> Option<ExGuid> {
+        match self.get(prid)? {
+            PropertyValue::ObjectId(id) => self.global_id_table.resolve(*id),
+            _ => None
+        }
+    }
+
+    // Ids that don't resolve are dropped
+    pub fn object_ids(&self, prid: u32) -> Vec<ExGuid> {
+        match self.get(prid) {
+            Some(PropertyValue::ObjectIds(ids)) => ids.iter().filter_map(|id| self.global_id_table.resolve(*id)).collect(),
+            Some(PropertyValue::ObjectId(id)) => self.global_id_table.resolve(*id).into_iter().collect(),
+            _ => Vec::new()
+        }
+    }
+
+    pub fn object_space_ids(&self, prid: u32) -> Vec<ExGuid> {
+        match self.get(prid) {
+            Some(PropertyValue::ObjectSpaceIds(ids)) => ids.iter().filter_map(|id| self.global_id_table.resolve(*id)).collect(),
+            Some(PropertyValue::ObjectSpaceId(id)) => self.global_id_table.resolve(*id).into_iter().collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+// Strings are stored as UTF-16LE, usually without a terminator but sometimes with one
+pub(crate) fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+}
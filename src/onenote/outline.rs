@@ -0,0 +1,172 @@
+use std::io::{Read, Seek};
+
+use crate::error::{Error, Result};
+use crate::structs::exguid::ExGuid;
+
+use super::embedded::{EmbeddedFile, Image};
+use super::jcid;
+use super::object::{ObjectReader, PropertyObject, Space, MAX_NESTING_DEPTH};
+use super::property;
+use super::richtext::RichText;
+use super::table::Table;
+
+// A jcidOutlineNode: a block of text positioned on the page. Offsets and sizes are in half-inch increments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Outline {
+    pub offset_horizontal: Option<f32>,
+    pub offset_vertical: Option<f32>,
+    pub layout_max_width: Option<f32>,
+    pub layout_max_height: Option<f32>,
+    pub items: Vec<OutlineItem>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum OutlineItem {
+    Element(OutlineElement),
+    Group(OutlineGroup)
+}
+
+// A jcidOutlineGroup: outline elements indented together without a parent paragraph
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OutlineGroup {
+    pub child_level: u8,
+    pub items: Vec<OutlineItem>
+}
+
+// A jcidOutlineElementNode: a paragraph and the paragraphs indented under it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OutlineElement {
+    pub child_level: u8,
+    pub list: Option<List>,
+    pub contents: Vec<Content>,
+    pub children: Vec<OutlineItem>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Content {
+    RichText(RichText),
+    Image(Image),
+    Table(Table),
+    EmbeddedFile(EmbeddedFile)
+}
+
+// A jcidNumberListNode
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct List {
+    pub format: String,
+    pub font: Option<String>,
+    pub restart: Option<u32>
+}
+
+impl Outline {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>) -> Result<Outline> {
+        let children = object.object_ids(property::ELEMENT_CHILD_NODES);
+
+        Ok(Outline {
+            offset_horizontal: object.f32(property::OFFSET_FROM_PARENT_HORIZ),
+            offset_vertical: object.f32(property::OFFSET_FROM_PARENT_VERT),
+            layout_max_width: object.f32(property::LAYOUT_MAX_WIDTH),
+            layout_max_height: object.f32(property::LAYOUT_MAX_HEIGHT),
+            items: read_items(&children, space, reader, 0)?
+        })
+    }
+
+    // The outline's paragraphs as plain text, one per line. Tables, images and files are left out
+    pub fn text(&self) -> String {
+        let mut lines = Vec::new();
+        collect_lines(&self.items, &mut lines);
+        lines.join("\n")
+    }
+}
+
+impl OutlineElement {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>, depth: u32) -> Result<OutlineElement> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(Error::InvalidData { structure: "OutlineElementNode", offset: object.offset, reason: "Outline elements are nested too deeply" });
+        }
+
+        let list = match object.object_ids(property::LIST_NODES).first() {
+            Some(id) => reader.read(space, id)?.map(|list| List::from_object(&list)),
+            None => None
+        };
+
+        let mut contents = Vec::new();
+        for content in reader.read_all(space, &object.object_ids(property::CONTENT_CHILD_NODES))? {
+            match content.jcid {
+                jcid::RICH_TEXT_OE_NODE => contents.push(Content::RichText(RichText::from_object(&content, space, reader)?)),
+                jcid::IMAGE_NODE => contents.push(Content::Image(Image::from_object(&content, space, reader)?)),
+                jcid::TABLE_NODE => contents.push(Content::Table(Table::from_object(&content, space, reader, depth + 1)?)),
+                jcid::EMBEDDED_FILE_NODE => contents.push(Content::EmbeddedFile(EmbeddedFile::from_object(&content, space, reader)?)),
+                // Ink and other content we don't model yet
+                _ => {}
+            }
+        }
+
+        let children = object.object_ids(property::ELEMENT_CHILD_NODES);
+
+        Ok(OutlineElement {
+            child_level: object.u8(property::OUTLINE_ELEMENT_CHILD_LEVEL).unwrap_or(1),
+            list,
+            contents,
+            children: read_items(&children, space, reader, depth + 1)?
+        })
+    }
+}
+
+impl List {
+    // Numbered lists store a placeholder where the number goes; bulleted lists store the bullet
+    pub fn is_numbered(&self) -> bool {
+        self.format.contains('\u{FFFD}')
+    }
+
+    fn from_object(object: &PropertyObject) -> List {
+        List {
+            format: object.string(property::NUMBER_LIST_FORMAT).unwrap_or_default(),
+            font: object.string(property::LIST_FONT),
+            restart: object.u32(property::LIST_RESTART)
+        }
+    }
+}
+
+fn collect_lines<'a>(items: &'a [OutlineItem], lines: &mut Vec<&'a str>) {
+    for item in items {
+        match item {
+            OutlineItem::Element(element) => {
+                for content in &element.contents {
+                    if let Content::RichText(text) = content {
+                        lines.push(&text.text);
+                    }
+                }
+                collect_lines(&element.children, lines);
+            },
+            OutlineItem::Group(group) => collect_lines(&group.items, lines)
+        }
+    }
+}
+
+fn read_items<T: Read + Seek>(ids: &[ExGuid], space: &Space, reader: &mut ObjectReader<T>, depth: u32) -> Result<Vec<OutlineItem>> {
+    let mut items = Vec::new();
+    for child in reader.read_all(space, ids)? {
+        match child.jcid {
+            jcid::OUTLINE_ELEMENT_NODE => items.push(OutlineItem::Element(OutlineElement::from_object(&child, space, reader, depth)?)),
+            jcid::OUTLINE_GROUP => {
+                if depth > MAX_NESTING_DEPTH {
+                    return Err(Error::InvalidData { structure: "OutlineGroup", offset: child.offset, reason: "Outline elements are nested too deeply" });
+                }
+                let children = child.object_ids(property::ELEMENT_CHILD_NODES);
+                items.push(OutlineItem::Group(OutlineGroup {
+                    child_level: child.u8(property::OUTLINE_ELEMENT_CHILD_LEVEL).unwrap_or(1),
+                    items: read_items(&children, space, reader, depth + 1)?
+                }));
+            },
+            _ => {}
+        }
+    }
+    Ok(items)
+}
@@ -0,0 +1,83 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::cab::Cabinet;
+use crate::error::Result;
+use crate::onestore::OneStore;
+
+use super::notebook::Notebook;
+use super::section::Section;
+use super::toc::TableOfContents;
+
+// A .onepkg file: a cabinet holding a notebook's .onetoc2, its .one files and a folder for each
+// section group, as OneNote's "Export notebook" writes it. Every member is read into memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookPackage {
+    // The package's file name without its extension, which OneNote names the notebook after
+    pub name: String,
+    // In archive order
+    pub members: Vec<PackageMember>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageMember {
+    // Relative to the notebook folder
+    pub path: PathBuf,
+    pub data: Vec<u8>
+}
+
+impl NotebookPackage {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<NotebookPackage> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let name = path.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        NotebookPackage::from_reader(name, &mut reader)
+    }
+
+    pub fn from_reader<T: Read + Seek>(name: String, reader: &mut T) -> Result<NotebookPackage> {
+        let cabinet = Cabinet::from_reader(reader)?;
+        let contents = cabinet.extract(reader)?;
+        let members = cabinet.files.into_iter().zip(contents)
+            .map(|(file, data)| PackageMember { path: file.path, data })
+            .collect();
+        Ok(NotebookPackage { name, members })
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.members.iter().map(|member| member.path.as_path())
+    }
+
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&PackageMember> {
+        self.members.iter().find(|member| member.path == path.as_ref())
+    }
+
+    // A reader over a member's bytes, for parsing it with OneStore::parse
+    pub fn reader<P: AsRef<Path>>(&self, path: P) -> Result<Cursor<&[u8]>> {
+        let member = self.get(&path)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("{} isn't in the package", path.as_ref().display())))?;
+        Ok(Cursor::new(&member.data))
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<OneStore> {
+        OneStore::parse(&mut self.reader(path)?)
+    }
+
+    pub fn section<P: AsRef<Path>>(&self, path: P) -> Result<Section> {
+        let mut reader = self.reader(path)?;
+        let store = OneStore::parse(&mut reader)?;
+        Section::from_store(&store, &mut reader)
+    }
+
+    pub fn table_of_contents<P: AsRef<Path>>(&self, path: P) -> Result<TableOfContents> {
+        let mut reader = self.reader(path)?;
+        let store = OneStore::parse(&mut reader)?;
+        TableOfContents::from_store(&store, &mut reader)
+    }
+
+    // Reads the notebook the same way Notebook::open reads a folder. Section paths are member paths
+    pub fn notebook(&self) -> Result<Notebook> {
+        Notebook::from_package(self)
+    }
+}
@@ -0,0 +1,120 @@
+use std::io::{Read, Seek};
+
+use crate::error::{Error, Result};
+use crate::structs::exguid::ExGuid;
+use crate::structs::revision::{ROOT_ROLE_DEFAULT_CONTENT, ROOT_ROLE_METADATA};
+
+use super::embedded::{EmbeddedFile, Image};
+use super::jcid;
+use super::object::{ObjectReader, PropertyObject, Space};
+use super::outline::Outline;
+use super::property;
+
+// A page and its contents. Every page lives in its own object space whose root object is a
+// jcidPageManifestNode pointing at the jcidPageNode. Sizes are in half-inch increments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Page {
+    // Id of the page's object space
+    pub id: ExGuid,
+    pub title: Option<Title>,
+    // The title as OneNote cached it in the page's metadata
+    pub cached_title: Option<String>,
+    // Indentation of the page in the section's page list; 1 for top level pages
+    pub level: u32,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub contents: Vec<PageContent>
+}
+
+// A jcidTitleNode
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Title {
+    pub offset_horizontal: Option<f32>,
+    pub offset_vertical: Option<f32>,
+    pub contents: Vec<Outline>
+}
+
+// Elements placed directly on the page
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PageContent {
+    Outline(Outline),
+    Image(Image),
+    EmbeddedFile(EmbeddedFile)
+}
+
+impl Page {
+    pub(crate) fn from_space<T: Read + Seek>(space: &Space, reader: &mut ObjectReader<T>) -> Result<Page> {
+        let mut page_node = reader.read_root(space, ROOT_ROLE_DEFAULT_CONTENT)?;
+        if page_node.jcid == jcid::PAGE_MANIFEST_NODE {
+            let manifest = page_node;
+            page_node = reader.read_all(space, &manifest.object_ids(property::CONTENT_CHILD_NODES))?
+                .into_iter()
+                .find(|child| child.jcid == jcid::PAGE_NODE)
+                .ok_or(Error::InvalidData { structure: "PageManifestNode", offset: manifest.offset, reason: "Page manifest has no page node" })?;
+        }
+        if page_node.jcid != jcid::PAGE_NODE {
+            return Err(Error::InvalidData { structure: "PageNode", offset: page_node.offset, reason: "Page root object is not a page node" });
+        }
+
+        let metadata = match space.root_objects.get(&ROOT_ROLE_METADATA) {
+            Some(id) => reader.read(space, id)?.filter(|metadata| metadata.jcid == jcid::PAGE_META_DATA),
+            None => None
+        };
+
+        let mut title = None;
+        for structure in reader.read_all(space, &page_node.object_ids(property::STRUCTURE_ELEMENT_CHILD_NODES))? {
+            if structure.jcid == jcid::TITLE_NODE {
+                title = Some(Title::from_object(&structure, space, reader)?);
+                break;
+            }
+        }
+
+        let mut contents = Vec::new();
+        for element in reader.read_all(space, &page_node.object_ids(property::ELEMENT_CHILD_NODES))? {
+            match element.jcid {
+                jcid::OUTLINE_NODE => contents.push(PageContent::Outline(Outline::from_object(&element, space, reader)?)),
+                jcid::IMAGE_NODE => contents.push(PageContent::Image(Image::from_object(&element, space, reader)?)),
+                jcid::EMBEDDED_FILE_NODE => contents.push(PageContent::EmbeddedFile(EmbeddedFile::from_object(&element, space, reader)?)),
+                _ => {}
+            }
+        }
+
+        Ok(Page {
+            id: space.gosid,
+            title,
+            cached_title: metadata.as_ref().and_then(|metadata| metadata.string(property::CACHED_TITLE_STRING)),
+            level: metadata.as_ref().and_then(|metadata| metadata.u32(property::PAGE_LEVEL)).unwrap_or(1),
+            width: page_node.f32(property::PAGE_WIDTH),
+            height: page_node.f32(property::PAGE_HEIGHT),
+            contents
+        })
+    }
+
+    // The title's text on one line, or the cached title if the page has no title node
+    pub fn title_text(&self) -> Option<String> {
+        let title = self.title.as_ref()
+            .map(|title| title.contents.iter().map(|outline| outline.text().replace('\n', " ")).collect::<Vec<_>>().join(" "))
+            .filter(|title| !title.trim().is_empty());
+        title.or_else(|| self.cached_title.clone())
+    }
+}
+
+impl Title {
+    fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>) -> Result<Title> {
+        let mut contents = Vec::new();
+        for outline in reader.read_all(space, &object.object_ids(property::ELEMENT_CHILD_NODES))? {
+            if outline.jcid == jcid::OUTLINE_NODE {
+                contents.push(Outline::from_object(&outline, space, reader)?);
+            }
+        }
+
+        Ok(Title {
+            offset_horizontal: object.f32(property::OFFSET_FROM_PARENT_HORIZ),
+            offset_vertical: object.f32(property::OFFSET_FROM_PARENT_VERT),
+            contents
+        })
+    }
+}
@@ -0,0 +1,57 @@
+// Property ids used by the MS-ONE object model. See MS-ONE 2.1.12
+pub const PAGE_WIDTH: u32 = 0x14001C01;
+pub const PAGE_HEIGHT: u32 = 0x14001C02;
+pub const OUTLINE_ELEMENT_CHILD_LEVEL: u32 = 0x0C001C03;
+pub const BOLD: u32 = 0x08001C04;
+pub const ITALIC: u32 = 0x08001C05;
+pub const UNDERLINE: u32 = 0x08001C06;
+pub const STRIKETHROUGH: u32 = 0x08001C07;
+pub const SUPERSCRIPT: u32 = 0x08001C08;
+pub const SUBSCRIPT: u32 = 0x08001C09;
+pub const FONT: u32 = 0x1C001C0A;
+pub const FONT_SIZE: u32 = 0x10001C0B;
+pub const FONT_COLOR: u32 = 0x14001C0C;
+pub const HIGHLIGHT: u32 = 0x14001C0D;
+pub const RG_OUTLINE_INDENT_DISTANCE: u32 = 0x1C001C12;
+pub const OFFSET_FROM_PARENT_HORIZ: u32 = 0x14001C14;
+pub const OFFSET_FROM_PARENT_VERT: u32 = 0x14001C15;
+pub const NUMBER_LIST_FORMAT: u32 = 0x1C001C1A;
+pub const LAYOUT_MAX_WIDTH: u32 = 0x14001C1B;
+pub const LAYOUT_MAX_HEIGHT: u32 = 0x14001C1C;
+pub const CONTENT_CHILD_NODES: u32 = 0x24001C1F;
+pub const ELEMENT_CHILD_NODES: u32 = 0x24001C20;
+pub const RICH_EDIT_TEXT_UNICODE: u32 = 0x1C001C22;
+pub const LIST_NODES: u32 = 0x24001C26;
+pub const LANGUAGE_ID: u32 = 0x14001C3B;
+pub const PICTURE_CONTAINER: u32 = 0x20001C3F;
+pub const LIST_FONT: u32 = 0x1C001C52;
+pub const LIST_RESTART: u32 = 0x14001CB7;
+pub const NOTEBOOK_ELEMENT_ORDERING_ID: u32 = 0x14001CB9;
+pub const NOTEBOOK_COLOR: u32 = 0x14001CBE;
+pub const CACHED_TITLE_STRING: u32 = 0x1C001CF3;
+pub const TOC_CHILDREN: u32 = 0x24001CF6;
+pub const IS_BACKGROUND: u32 = 0x08001D13;
+pub const ROW_COUNT: u32 = 0x14001D57;
+pub const COLUMN_COUNT: u32 = 0x14001D58;
+pub const TABLE_BORDERS_VISIBLE: u32 = 0x08001D5E;
+pub const STRUCTURE_ELEMENT_CHILD_NODES: u32 = 0x24001D5F;
+pub const CHILD_GRAPH_SPACE_ELEMENT_NODES: u32 = 0x2C001D63;
+pub const TABLE_COLUMN_WIDTHS: u32 = 0x1C001D66;
+pub const FOLDER_CHILD_FILENAME: u32 = 0x1C001D6B;
+pub const EMBEDDED_FILE_CONTAINER: u32 = 0x20001D9B;
+pub const EMBEDDED_FILE_NAME: u32 = 0x1C001D9C;
+pub const SOURCE_FILEPATH: u32 = 0x1C001D9D;
+pub const IMAGE_FILENAME: u32 = 0x1C001DD7;
+pub const PAGE_LEVEL: u32 = 0x14001DFF;
+pub const TEXT_RUN_INDEX: u32 = 0x1C001E12;
+pub const TEXT_RUN_FORMATTING: u32 = 0x24001E13;
+pub const HYPERLINK: u32 = 0x08001E14;
+pub const WZ_HYPERLINK_URL: u32 = 0x1C001E20;
+pub const IMAGE_ALT_TEXT: u32 = 0x1C001E58;
+pub const PARAGRAPH_STYLE: u32 = 0x2000342C;
+pub const META_DATA_OBJECTS_ABOVE_GRAPH_SPACE: u32 = 0x24003442;
+pub const PARAGRAPH_STYLE_ID: u32 = 0x1C00345A;
+pub const TEXT_EXTENDED_ASCII: u32 = 0x1C003498;
+pub const SECTION_DISPLAY_NAME: u32 = 0x1C00349B;
+pub const PICTURE_WIDTH: u32 = 0x140034CD;
+pub const PICTURE_HEIGHT: u32 = 0x140034CE;
@@ -0,0 +1,206 @@
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+use crate::error::Result;
+use crate::structs::exguid::ExGuid;
+
+use super::jcid;
+use super::object::{ObjectReader, PropertyObject, Space};
+use super::property;
+
+// Hyperlinks are stored in the text as a field code followed by the text that is displayed
+const HYPERLINK_FIELD_CODE: &str = "\u{FDDF}HYPERLINK \"";
+
+// FontColor and Highlight use this value for the automatic color
+const COLOR_AUTO: u32 = 0xFF000000;
+
+// The text of a jcidRichTextOENode split into runs of identical formatting
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RichText {
+    // Hyperlink field codes are removed
+    pub text: String,
+    pub runs: Vec<TextRun>,
+    pub paragraph_style: Option<ParagraphStyle>,
+    pub language: Option<u32>
+}
+
+// Formatting of part of a RichText. Properties the run doesn't set are taken from the paragraph style
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TextRun {
+    // Byte range in RichText::text
+    pub range: Range<usize>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub superscript: bool,
+    pub subscript: bool,
+    pub font: Option<String>,
+    // In points
+    pub size: Option<f32>,
+    // COLORREF values (0x00BBGGRR), None for the automatic color
+    pub color: Option<u32>,
+    pub highlight: Option<u32>,
+    pub hyperlink: Option<String>,
+    // Windows language code identifier
+    pub language: Option<u32>
+}
+
+// A jcidParagraphStyleObject, used both for the style of a whole paragraph and for single runs
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParagraphStyle {
+    // Name of the built in style such as "h1" or "p"
+    pub style_id: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub superscript: Option<bool>,
+    pub subscript: Option<bool>,
+    pub font: Option<String>,
+    pub size: Option<f32>,
+    pub color: Option<u32>,
+    pub highlight: Option<u32>,
+    pub hyperlink: bool,
+    pub hyperlink_url: Option<String>,
+    pub language: Option<u32>
+}
+
+impl RichText {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>) -> Result<RichText> {
+        let mut units: Vec<u16> = if let Some(bytes) = object.bytes(property::RICH_EDIT_TEXT_UNICODE) {
+            bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+        } else if let Some(bytes) = object.bytes(property::TEXT_EXTENDED_ASCII) {
+            // Single byte text; every byte maps to the code point with the same value
+            bytes.iter().map(|&byte| byte as u16).collect()
+        } else {
+            Vec::new()
+        };
+        while units.last() == Some(&0) {
+            units.pop();
+        }
+
+        let paragraph_style = match object.object_id(property::PARAGRAPH_STYLE) {
+            Some(id) => read_style(&id, space, reader)?,
+            None => None
+        };
+        let language = object.u32(property::LANGUAGE_ID);
+
+        // TextRunIndex holds the end of every run but the last, counted in characters
+        let run_ends: Vec<usize> = object.bytes(property::TEXT_RUN_INDEX)
+            .map(|bytes| bytes.chunks_exact(4).map(|end| u32::from_le_bytes([end[0], end[1], end[2], end[3]]) as usize).collect())
+            .unwrap_or_default();
+        let mut run_styles = Vec::new();
+        for id in object.object_ids(property::TEXT_RUN_FORMATTING) {
+            run_styles.push(read_style(&id, space, reader)?);
+        }
+
+        let mut text = String::new();
+        let mut runs = Vec::new();
+        let mut hyperlink = None;
+        let mut start = 0;
+        for (index, end) in run_ends.into_iter().chain(std::iter::once(units.len())).enumerate() {
+            let end = end.clamp(start, units.len());
+            let run_style = run_styles.get(index).and_then(|style| style.as_ref());
+            let mut run_text = String::from_utf16_lossy(&units[start..end]);
+            start = end;
+
+            let is_hyperlink = run_style.map(|style| style.hyperlink).unwrap_or(false);
+            if !is_hyperlink {
+                hyperlink = None;
+            }
+            if let Some((url, remaining)) = strip_hyperlink_field_code(&run_text) {
+                hyperlink = Some(url);
+                run_text = remaining;
+            }
+            if let Some(url) = run_style.and_then(|style| style.hyperlink_url.clone()) {
+                hyperlink = Some(url);
+            }
+
+            if run_text.is_empty() {
+                continue;
+            }
+            let range = text.len()..text.len() + run_text.len();
+            text.push_str(&run_text);
+            runs.push(TextRun::new(range, run_style, paragraph_style.as_ref(), hyperlink.clone(), language));
+        }
+
+        Ok(RichText { text, runs, paragraph_style, language })
+    }
+
+    pub fn run_text(&self, run: &TextRun) -> &str {
+        self.text.get(run.range.clone()).unwrap_or_default()
+    }
+}
+
+impl TextRun {
+    fn new(range: Range<usize>, style: Option<&ParagraphStyle>, paragraph_style: Option<&ParagraphStyle>, hyperlink: Option<String>, language: Option<u32>) -> TextRun {
+        // Run formatting first, then the paragraph's
+        let flag = |get: fn(&ParagraphStyle) -> Option<bool>| style.and_then(get).or_else(|| paragraph_style.and_then(get)).unwrap_or(false);
+        let font = style.and_then(|style| style.font.clone()).or_else(|| paragraph_style.and_then(|style| style.font.clone()));
+        let size = style.and_then(|style| style.size).or_else(|| paragraph_style.and_then(|style| style.size));
+        let color = style.and_then(|style| style.color).or_else(|| paragraph_style.and_then(|style| style.color));
+        let highlight = style.and_then(|style| style.highlight).or_else(|| paragraph_style.and_then(|style| style.highlight));
+        let run_language = style.and_then(|style| style.language).or_else(|| paragraph_style.and_then(|style| style.language));
+
+        TextRun {
+            range,
+            bold: flag(|style| style.bold),
+            italic: flag(|style| style.italic),
+            underline: flag(|style| style.underline),
+            strikethrough: flag(|style| style.strikethrough),
+            superscript: flag(|style| style.superscript),
+            subscript: flag(|style| style.subscript),
+            font,
+            size,
+            color,
+            highlight,
+            hyperlink,
+            language: run_language.or(language)
+        }
+    }
+}
+
+impl ParagraphStyle {
+    fn from_object(object: &PropertyObject) -> ParagraphStyle {
+        ParagraphStyle {
+            style_id: object.string(property::PARAGRAPH_STYLE_ID),
+            bold: object.bool(property::BOLD),
+            italic: object.bool(property::ITALIC),
+            underline: object.bool(property::UNDERLINE),
+            strikethrough: object.bool(property::STRIKETHROUGH),
+            superscript: object.bool(property::SUPERSCRIPT),
+            subscript: object.bool(property::SUBSCRIPT),
+            font: object.string(property::FONT),
+            // Stored in half points
+            size: object.u16(property::FONT_SIZE).map(|size| size as f32 / 2.0),
+            color: object.u32(property::FONT_COLOR).filter(|color| *color != COLOR_AUTO),
+            highlight: object.u32(property::HIGHLIGHT).filter(|color| *color != COLOR_AUTO),
+            hyperlink: object.bool(property::HYPERLINK).unwrap_or(false),
+            hyperlink_url: object.string(property::WZ_HYPERLINK_URL),
+            language: object.u32(property::LANGUAGE_ID)
+        }
+    }
+}
+
+// Styles are kept positional, so a missing style object gives None rather than being skipped
+fn read_style<T: Read + Seek>(id: &ExGuid, space: &Space, reader: &mut ObjectReader<T>) -> Result<Option<ParagraphStyle>> {
+    Ok(reader.read(space, id)?
+        .filter(|style| style.jcid == jcid::PARAGRAPH_STYLE_OBJECT)
+        .map(|style| ParagraphStyle::from_object(&style)))
+}
+
+// Returns the link target and the text with the field code removed
+fn strip_hyperlink_field_code(text: &str) -> Option<(String, String)> {
+    let code_start = text.find(HYPERLINK_FIELD_CODE)?;
+    let url_start = code_start + HYPERLINK_FIELD_CODE.len();
+    let url_len = text[url_start..].find('"').unwrap_or(text.len() - url_start);
+    let code_end = (url_start + url_len + 1).min(text.len());
+
+    let url = text[url_start..url_start + url_len].to_string();
+    let remaining = format!("{}{}", &text[..code_start], &text[code_end..]);
+    Some((url, remaining))
+}
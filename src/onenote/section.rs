@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::onestore::OneStore;
+use crate::structs::revision::{ROOT_ROLE_DEFAULT_CONTENT, ROOT_ROLE_METADATA};
+
+use super::jcid;
+use super::object::ObjectReader;
+use super::page::Page;
+use super::property;
+
+// The contents of a .one file: a jcidSectionNode at the root of the root object space
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Section {
+    pub display_name: Option<String>,
+    pub page_series: Vec<PageSeries>
+}
+
+// A jcidPageSeriesNode: a run of pages in the order they appear in the section
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PageSeries {
+    pub pages: Vec<Page>
+}
+
+impl Section {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Section> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let store = OneStore::parse(&mut reader)?;
+        Section::from_store(&store, &mut reader)
+    }
+
+    // reader must be the file store was parsed from
+    pub fn from_store<T: Read + Seek>(store: &OneStore, reader: &mut T) -> Result<Section> {
+        let mut reader = ObjectReader::new(store, reader);
+        let space = reader.space(&store.root_object_space_id)
+            .ok_or(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: store.header.file_node_list_root.start, reason: "Root object space has no content revision" })?;
+
+        let section_node = reader.read_root(&space, ROOT_ROLE_DEFAULT_CONTENT)?;
+        if section_node.jcid != jcid::SECTION_NODE {
+            return Err(Error::InvalidData { structure: "SectionNode", offset: section_node.offset, reason: "Root object is not a section node" });
+        }
+
+        let display_name = match space.root_objects.get(&ROOT_ROLE_METADATA) {
+            Some(id) => reader.read(&space, id)?.and_then(|metadata| metadata.string(property::SECTION_DISPLAY_NAME)),
+            None => None
+        };
+
+        let mut page_series = Vec::new();
+        for series in reader.read_all(&space, &section_node.object_ids(property::ELEMENT_CHILD_NODES))? {
+            if series.jcid != jcid::PAGE_SERIES_NODE {
+                continue;
+            }
+
+            let mut pages = Vec::new();
+            for gosid in series.object_space_ids(property::CHILD_GRAPH_SPACE_ELEMENT_NODES) {
+                // Pages without a content revision have never been written and have nothing to show
+                if let Some(page_space) = reader.space(&gosid) {
+                    pages.push(Page::from_space(&page_space, &mut reader)?);
+                }
+            }
+            page_series.push(PageSeries { pages });
+        }
+
+        Ok(Section { display_name, page_series })
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = &Page> {
+        self.page_series.iter().flat_map(|series| series.pages.iter())
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::{Read, Seek};
+
+use crate::error::{Error, Result};
+
+use super::jcid;
+use super::object::{ObjectReader, PropertyObject, Space, MAX_NESTING_DEPTH};
+use super::outline::OutlineElement;
+use super::property;
+
+// A jcidTableNode. Column widths are in half-inch increments
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Table {
+    pub row_count: u32,
+    pub column_count: u32,
+    pub column_widths: Vec<f32>,
+    pub borders_visible: bool,
+    pub rows: Vec<TableRow>
+}
+
+// A jcidTableRowNode
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableRow {
+    pub cells: Vec<TableCell>
+}
+
+// A jcidTableCellNode
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableCell {
+    pub contents: Vec<OutlineElement>
+}
+
+impl Table {
+    pub(crate) fn from_object<T: Read + Seek>(object: &PropertyObject, space: &Space, reader: &mut ObjectReader<T>, depth: u32) -> Result<Table> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(Error::InvalidData { structure: "TableNode", offset: object.offset, reason: "Tables are nested too deeply" });
+        }
+
+        // The first byte is the number of columns, followed by a float per column
+        let column_widths: Vec<f32> = object.bytes(property::TABLE_COLUMN_WIDTHS)
+            .map(|bytes| bytes.get(1..).unwrap_or_default()
+                .chunks_exact(4)
+                .map(|width| f32::from_le_bytes([width[0], width[1], width[2], width[3]]))
+                .collect())
+            .unwrap_or_default();
+
+        let mut rows = Vec::new();
+        for row in reader.read_all(space, &object.object_ids(property::ELEMENT_CHILD_NODES))? {
+            if row.jcid != jcid::TABLE_ROW_NODE {
+                continue;
+            }
+
+            let mut cells = Vec::new();
+            for cell in reader.read_all(space, &row.object_ids(property::ELEMENT_CHILD_NODES))? {
+                if cell.jcid != jcid::TABLE_CELL_NODE {
+                    continue;
+                }
+
+                let mut contents = Vec::new();
+                for element in reader.read_all(space, &cell.object_ids(property::ELEMENT_CHILD_NODES))? {
+                    if element.jcid == jcid::OUTLINE_ELEMENT_NODE {
+                        contents.push(OutlineElement::from_object(&element, space, reader, depth + 1)?);
+                    }
+                }
+                cells.push(TableCell { contents });
+            }
+            rows.push(TableRow { cells });
+        }
+
+        Ok(Table {
+            row_count: object.u32(property::ROW_COUNT).unwrap_or(rows.len() as u32),
+            column_count: object.u32(property::COLUMN_COUNT).unwrap_or(column_widths.len() as u32),
+            column_widths,
+            borders_visible: object.bool(property::TABLE_BORDERS_VISIBLE).unwrap_or(true),
+            rows
+        })
+    }
+}
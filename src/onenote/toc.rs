@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::onestore::OneStore;
+use crate::structs::revision::ROOT_ROLE_DEFAULT_CONTENT;
+
+use super::jcid;
+use super::object::{ObjectReader, PropertyObject};
+use super::property;
+
+// NotebookColor values with this high byte are transparent, which OneNote shows as no color
+const COLOR_NONE_MASK: u32 = 0xFF000000;
+
+// The contents of a .onetoc2 file: the order and colors of the sections and section groups in
+// the folder next to it
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TableOfContents {
+    // Color of the notebook or section group itself
+    pub color: Option<u32>,
+    // In display order
+    pub entries: Vec<TocEntry>
+}
+
+// A section file or section group folder listed in a table of contents
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TocEntry {
+    // Name of the .one file or folder, relative to the .onetoc2
+    pub filename: String,
+    pub ordering_id: Option<u32>,
+    // COLORREF value (0x00BBGGRR)
+    pub color: Option<u32>
+}
+
+impl TableOfContents {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<TableOfContents> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let store = OneStore::parse(&mut reader)?;
+        TableOfContents::from_store(&store, &mut reader)
+    }
+
+    // reader must be the file store was parsed from
+    pub fn from_store<T: Read + Seek>(store: &OneStore, reader: &mut T) -> Result<TableOfContents> {
+        let mut reader = ObjectReader::new(store, reader);
+        let space = reader.space(&store.root_object_space_id)
+            .ok_or(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: store.header.file_node_list_root.start, reason: "Root object space has no content revision" })?;
+
+        let root = reader.read_root(&space, ROOT_ROLE_DEFAULT_CONTENT)?;
+        if root.jcid != jcid::PERSISTABLE_PROPERTY_CONTAINER_FOR_TOC {
+            return Err(Error::InvalidData { structure: "PersistablePropertyContainerForTOC", offset: root.offset, reason: "Root object is not a table of contents" });
+        }
+
+        let mut entries = Vec::new();
+        for child in reader.read_all(&space, &root.object_ids(property::TOC_CHILDREN))? {
+            if child.jcid != jcid::PERSISTABLE_PROPERTY_CONTAINER_FOR_TOC {
+                continue;
+            }
+            // Entries without a file name don't point at anything that can be opened
+            if let Some(filename) = child.string(property::FOLDER_CHILD_FILENAME) {
+                entries.push(TocEntry {
+                    filename: unescape_filename(&filename),
+                    ordering_id: child.u32(property::NOTEBOOK_ELEMENT_ORDERING_ID),
+                    color: color(&child)
+                });
+            }
+        }
+        // Entries are stored in creation order; ordering ids give the order the user arranged them in.
+        // The sort is stable so entries without one keep their stored order, after the others.
+        entries.sort_by_key(|entry| (entry.ordering_id.is_none(), entry.ordering_id));
+
+        Ok(TableOfContents { color: color(&root), entries })
+    }
+}
+
+impl TocEntry {
+    // The name OneNote shows: the file name without its .one extension
+    pub fn display_name(&self) -> &str {
+        display_name(&self.filename)
+    }
+}
+
+pub(crate) fn display_name(filename: &str) -> &str {
+    match filename.len().checked_sub(4) {
+        Some(stem) if filename.is_char_boundary(stem) && filename[stem..].eq_ignore_ascii_case(".one") => &filename[..stem],
+        _ => filename
+    }
+}
+
+fn color(object: &PropertyObject) -> Option<u32> {
+    object.u32(property::NOTEBOOK_COLOR)
+        .filter(|color| color & COLOR_NONE_MASK != COLOR_NONE_MASK)
+        .map(|color| color & !COLOR_NONE_MASK)
+}
+
+// Characters that aren't allowed in every file system are stored escaped
+fn unescape_filename(filename: &str) -> String {
+    filename.replace("^M", "+").replace("^J", ",")
+}
@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::fsshttpb::packaging;
+use crate::structs::exguid::ExGuid;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::filedatastore::FileDataStore;
+use crate::structs::filenodedata::FileNodeData;
+use crate::structs::filenodelist::FileNodeList;
+use crate::structs::freechunklist::FreeChunkList;
+use crate::structs::hashedchunklist::HashedChunkList;
+use crate::structs::header::OneNoteFileHeader;
+use crate::structs::objectspace::ObjectSpace;
+use crate::structs::transactionlog::TransactionLog;
+use crate::structs::{FromFileChunk, ListFromFileChunk};
+
+// How a revision store is laid out on disk
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum StoreFormat {
+    // File node lists, as written by OneNote desktop. See MS-ONESTORE 2.1
+    Native,
+    // FSSHTTPB data elements, as synced through SharePoint and OneDrive. See MS-ONESTORE 2.8
+    Packaged
+}
+
+// A fully parsed revision store file. See MS-ONESTORE 2.1
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OneStore {
+    pub format: StoreFormat,
+    // Packaged stores only fill in the fields their prefix has, see OneNoteFileHeader::packaged
+    pub header: OneNoteFileHeader,
+    // Length of the stream the store was read from, which is at least header.expected_file_length
+    pub file_length: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub transaction_log: TransactionLog,
+    pub root_file_node_list: FileNodeList,
+    pub root_object_space_id: ExGuid,
+    pub object_spaces: Vec<ObjectSpace>,
+    // Empty if the file has no FileDataStoreListReference node
+    pub file_data_store: FileDataStore,
+    // Both are empty if the header doesn't reference them
+    pub free_chunk_list: FreeChunkList,
+    pub hashed_chunk_list: HashedChunkList,
+    // Whether crcName matches the name of the file the store was opened from. None when it was
+    // parsed from a reader, which has no name, and for packaged stores, which have no crcName
+    pub name_matches: Option<bool>
+}
+
+impl OneStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OneStore> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let mut store = OneStore::parse(&mut reader)?;
+        if store.format == StoreFormat::Native {
+            store.name_matches = path.file_name().map(|name| store.header.verify_name(&name.to_string_lossy()).is_ok());
+        }
+        Ok(store)
+    }
+
+    pub fn parse<T: Read + Seek>(reader: &mut T) -> Result<OneStore> {
+        if packaging::is_packaged(reader)? {
+            return packaging::parse_store(reader);
+        }
+
+        // Read header from beginning of document
+        let start_of_file = FileChunkReference { start: 0, len: 1024 };
+        let header = OneNoteFileHeader::from_reader(&start_of_file, reader)?;
+        let file_length = reader.seek(SeekFrom::End(0))?;
+        header.check_file_length(file_length)?;
+
+        // Read transaction log based on fcr and length given in header
+        let transactions_in_log: u64 = header.transactions_in_log.into();
+        let transaction_log = TransactionLog::from_reader(&header.transaction_log, reader, transactions_in_log)?;
+
+        // Number of file nodes in the list is given by the transaction log entry for this list
+        let root_file_node_list = FileNodeList::from_reader(&header.file_node_list_root, reader, &transaction_log)?;
+
+        // See MS-ONESTORE 2.1.14
+        let mut root_object_space_id = None;
+        let mut object_spaces = Vec::new();
+        let mut file_data_store = FileDataStore::default();
+        for node in &root_file_node_list.file_nodes {
+            match &node.data {
+                FileNodeData::ObjectSpaceManifestListReference(reference) => {
+                    let object_space = ObjectSpace::from_reader(&node.file_chunk_ref, reader, &transaction_log)?;
+                    if object_space.gosid != reference.gosid {
+                        return Err(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: node.file_chunk_ref.start, reason: "Object space id doesn't match its reference" });
+                    }
+                    object_spaces.push(object_space);
+                },
+                FileNodeData::ObjectSpaceManifestRoot(root) => root_object_space_id = Some(root.gosid_root),
+                FileNodeData::FileDataStoreListReference => {
+                    file_data_store = FileDataStore::from_reader(&node.file_chunk_ref, reader, &transaction_log)?;
+                },
+                _ => {}
+            }
+        }
+
+        let free_chunk_list = FreeChunkList::from_reader(&header.free_chunk_list, reader)?;
+        let hashed_chunk_list = if header.hashed_chunk_list.is_nil() || header.hashed_chunk_list.is_zero() {
+            HashedChunkList::default()
+        } else {
+            HashedChunkList::from_reader(&header.hashed_chunk_list, reader, &transaction_log)?
+        };
+
+        let root_object_space_id = root_object_space_id
+            .ok_or(Error::InvalidData { structure: "RootFileNodeList", offset: header.file_node_list_root.start, reason: "No ObjectSpaceManifestRoot node" })?;
+
+        Ok(OneStore {
+            format: StoreFormat::Native,
+            header,
+            file_length,
+            transaction_log,
+            root_file_node_list,
+            root_object_space_id,
+            object_spaces,
+            file_data_store,
+            free_chunk_list,
+            hashed_chunk_list,
+            name_matches: None
+        })
+    }
+
+    pub fn object_space(&self, gosid: &ExGuid) -> Option<&ObjectSpace> {
+        self.object_spaces.iter().find(|object_space| object_space.gosid == *gosid)
+    }
+
+    pub fn root_object_space(&self) -> Option<&ObjectSpace> {
+        self.object_space(&self.root_object_space_id)
+    }
+}
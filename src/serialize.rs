@@ -0,0 +1,30 @@
+// Serializer helpers that keep JSON output stable between runs: hash maps are written with sorted keys
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Serialize, Serializer};
+
+use crate::structs::exguid::ExGuid;
+
+pub fn sorted_map<S: Serializer, K: Ord + Serialize, V: Serialize>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[derive(Serialize)]
+struct RevisionRole<'a> {
+    context: &'a ExGuid,
+    role: u32,
+    rid: &'a ExGuid
+}
+
+// Tuple keys can't be JSON object keys, so the roles are written as a list
+pub fn revision_roles<S: Serializer>(roles: &HashMap<(ExGuid, u32), ExGuid>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut roles: Vec<RevisionRole> = roles.iter()
+        .map(|((context, role), rid)| RevisionRole { context, role: *role, rid })
+        .collect();
+    roles.sort_by_key(|role| (*role.context, role.role));
+    roles.serialize(serializer)
+}
+
+pub fn is_zeroed(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&byte| byte == 0)
+}
@@ -0,0 +1,42 @@
+use std::io::{Read, Write, Error, ErrorKind};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+
+// See MS-ONESTORE 2.2.2. Resolved to an ExGuid through a global identification table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CompactId {
+    pub n: u8,
+    pub guid_index: u32
+}
+
+impl CompactId {
+    pub fn from_reader<T: Read>(reader: &mut T) -> Result<CompactId, Error> {
+        let value = reader.read_u32::<LittleEndian>()?;
+
+        Ok(CompactId::from(value))
+    }
+
+    // guid_index has 24 bits
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        if self.guid_index > 0xFFFFFF {
+            return Err(Error::new(ErrorKind::InvalidInput, "CompactID guidIndex is too large for 24 bits"));
+        }
+        writer.write_u32::<LittleEndian>(u32::from(*self))
+    }
+}
+
+impl From<CompactId> for u32 {
+    fn from(id: CompactId) -> Self {
+        id.n as u32 | (id.guid_index << 8)
+    }
+}
+
+impl From<u32> for CompactId {
+    fn from(value: u32) -> Self {
+        CompactId {
+            n: (value & 0xFF) as u8,
+            guid_index: value >> 8
+        }
+    }
+}
@@ -0,0 +1,4 @@
+// See MS-ONESTORE 2.1.2. Used for transaction log entries and crcName
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
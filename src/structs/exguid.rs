@@ -1,7 +1,49 @@
+use std::fmt;
+use std::io::{Read, Write, Error};
 
-use super::guid::Guid;
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
 
+use super::guid::*;
+
+// See MS-ONESTORE 2.2.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ExGuid {
-    guid: Guid,
-    n: u32
-}
\ No newline at end of file
+    pub guid: Guid,
+    pub n: u32
+}
+
+impl ExGuid {
+    pub fn from_reader<T: Read>(reader: &mut T) -> Result<ExGuid, Error> {
+        let guid = Guid::from_reader(reader)?;
+        let n = reader.read_u32::<LittleEndian>()?;
+
+        Ok(ExGuid { guid, n })
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all(&self.guid.to_bytes_le())?;
+        writer.write_u32::<LittleEndian>(self.n)
+    }
+
+    pub fn nil() -> ExGuid {
+        ExGuid { guid: Guid::nil(), n: 0 }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.guid.is_nil() && self.n == 0
+    }
+}
+
+impl fmt::Display for ExGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}},{}", self.guid, self.n)
+    }
+}
+
+// Written as "{guid},n" so ExGuids can be used as JSON object keys
+#[cfg(feature = "serde")]
+impl serde::Serialize for ExGuid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -1,10 +1,13 @@
-use std::io::{Read, Error, ErrorKind};
+use std::io::{Read, Seek, Write};
 
-use byteorder::{ReadBytesExt, LittleEndian};
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+
+use crate::error::{Error, Result};
 
 // See MS-ONESTORE 2.2.4.4. This is basically just a slice
 // TODO: SHOULD FILECHUNKREFERENCE BE A GENERIC TYPE?
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FileChunkReference {
     pub start: u64,
     pub len: u64
@@ -12,39 +15,67 @@ pub struct FileChunkReference {
 
 impl FileChunkReference {
 
-    pub fn from_reader<T: Read>(reader: &mut T, start_size_bits: u32, len_size_bits: u32) -> Result<FileChunkReference, Error> {
-        let mut start: u64;
-        let len: u64;
-
-        match start_size_bits {
-            8 => start = reader.read_u8()?.into(),
-            16 => start = reader.read_u16::<LittleEndian>()?.into(),
-            32 => start = reader.read_u32::<LittleEndian>()?.into(),
-            64 => start = reader.read_u64::<LittleEndian>()?,
-            _ => return Err(Error::new(ErrorKind::InvalidInput, "FCR start field must be 1, 2, 4, or 8 bytes"))
-        }
+    pub fn from_reader<T: Read + Seek>(reader: &mut T, start_size_bits: u32, len_size_bits: u32) -> Result<FileChunkReference> {
+        let offset = reader.stream_position()?;
+        let mut start: u64 = match start_size_bits {
+            8 => reader.read_u8()?.into(),
+            16 => reader.read_u16::<LittleEndian>()?.into(),
+            32 => reader.read_u32::<LittleEndian>()?.into(),
+            64 => reader.read_u64::<LittleEndian>()?,
+            _ => return Err(Error::InvalidData { structure: "FileChunkReference", offset, reason: "FCR start field must be 1, 2, 4, or 8 bytes" })
+        };
 
         // If parsed start field is all ones, make our in-memory representation have all 1's. Used for is_nil()
         if start.count_ones() == start_size_bits {
             start = u64::MAX;
         }
 
-        match len_size_bits {
-            8 => len = reader.read_u8()?.into(),
-            16 => len = reader.read_u16::<LittleEndian>()?.into(),
-            32 => len = reader.read_u32::<LittleEndian>()?.into(),
-            64 => len = reader.read_u64::<LittleEndian>()?,
-            _ => return Err(Error::new(ErrorKind::InvalidInput, "FCR len field must be 1, 2, 4, or 8 bytes"))
-        }
+        let len: u64 = match len_size_bits {
+            8 => reader.read_u8()?.into(),
+            16 => reader.read_u16::<LittleEndian>()?.into(),
+            32 => reader.read_u32::<LittleEndian>()?.into(),
+            64 => reader.read_u64::<LittleEndian>()?,
+            _ => return Err(Error::InvalidData { structure: "FileChunkReference", offset, reason: "FCR len field must be 1, 2, 4, or 8 bytes" })
+        };
 
         Ok(FileChunkReference { start, len })
     }
 
+    // The inverse of from_reader. A nil start is written as all ones of the field's width
+    pub fn to_writer<W: Write>(&self, writer: &mut W, start_size_bits: u32, len_size_bits: u32) -> Result<()> {
+        let start = if self.start == u64::MAX { u64::MAX >> (64 - start_size_bits) } else { self.start };
+        if start_size_bits < 64 && start >> start_size_bits != 0 || len_size_bits < 64 && self.len >> len_size_bits != 0 {
+            return Err(Error::InvalidData { structure: "FileChunkReference", offset: self.start, reason: "FCR doesn't fit in its fields" });
+        }
+
+        write_field(writer, start, start_size_bits)?;
+        write_field(writer, self.len, len_size_bits)
+    }
+
+    pub fn nil() -> FileChunkReference {
+        FileChunkReference { start: u64::MAX, len: 0 }
+    }
+
+    pub fn zero() -> FileChunkReference {
+        FileChunkReference { start: 0, len: 0 }
+    }
+
     pub fn is_nil(&self) -> bool {
-        self.start == std::u64::MAX && self.len == 0
+        self.start == u64::MAX && self.len == 0
     }
 
     pub fn is_zero(&self) -> bool {
         self.start == 0 && self.len == 0
     }
-}
\ No newline at end of file
+}
+
+fn write_field<W: Write>(writer: &mut W, value: u64, size_bits: u32) -> Result<()> {
+    match size_bits {
+        8 => writer.write_u8(value as u8)?,
+        16 => writer.write_u16::<LittleEndian>(value as u16)?,
+        32 => writer.write_u32::<LittleEndian>(value as u32)?,
+        64 => writer.write_u64::<LittleEndian>(value)?,
+        _ => return Err(Error::InvalidData { structure: "FileChunkReference", offset: 0, reason: "FCR fields must be 1, 2, 4, or 8 bytes" })
+    }
+    Ok(())
+}
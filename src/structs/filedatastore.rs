@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Take};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::check_fcr_bounds;
+use super::filechunkreference::FileChunkReference;
+use super::filenodedata::FileNodeData;
+use super::filenodelist::FileNodeList;
+use super::guid::*;
+use super::transactionlog::TransactionLog;
+
+const STRUCTURE: &str = "FileDataStoreObject";
+
+const FILE_DATA_STORE_OBJECT_HEADER: Guid = guid!("BDE316E7-2665-4511-A4C4-8D4D0B7A9EAC");
+const FILE_DATA_STORE_OBJECT_FOOTER: Guid = guid!("71FBA722-0F79-4A0B-BB13-899256426B24");
+
+// guidHeader, cbLength, unused and reserved
+const HEADER_SIZE: u64 = 36;
+const FOOTER_SIZE: u64 = 16;
+
+// The location of a file stored in the file data store. Only the header and footer are read when
+// parsing so large files aren't loaded until they are needed. See MS-ONESTORE 2.6.13
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileDataStoreObject {
+    pub guid: Guid,
+    // Offset of the file's first byte
+    pub data_offset: u64,
+    pub len: u64
+}
+
+// Every FileDataStoreObject in the file, keyed by the GUID ObjectDeclarationFileData3 nodes reference
+// them with. See MS-ONESTORE 2.5.21
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileDataStore {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub objects: HashMap<Guid, FileDataStoreObject>
+}
+
+impl FileDataStoreObject {
+    pub fn from_reader<T: Read + Seek>(guid: Guid, fcr: &FileChunkReference, reader: &mut T) -> Result<FileDataStoreObject> {
+        check_fcr_bounds(fcr, reader, STRUCTURE)?;
+        if fcr.len < HEADER_SIZE + FOOTER_SIZE {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: fcr.start, reason: "Chunk is too small for a file data store object" });
+        }
+        reader.seek(SeekFrom::Start(fcr.start))?;
+
+        let header = Guid::from_reader(reader)?;
+        if header != FILE_DATA_STORE_OBJECT_HEADER {
+            return Err(Error::BadGuid { structure: STRUCTURE, offset: fcr.start, found: header });
+        }
+
+        let len = reader.read_u64::<LittleEndian>()?;
+        // The data is padded to a multiple of 8 bytes before the footer
+        let padded_len = len.checked_add(7).map(|len| len & !7)
+            .filter(|padded_len| HEADER_SIZE + padded_len + FOOTER_SIZE <= fcr.len)
+            .ok_or(Error::InvalidData { structure: STRUCTURE, offset: fcr.start, reason: "File data is larger than its chunk" })?;
+
+        let footer_offset = fcr.start + HEADER_SIZE + padded_len;
+        reader.seek(SeekFrom::Start(footer_offset))?;
+        let footer = Guid::from_reader(reader)?;
+        if footer != FILE_DATA_STORE_OBJECT_FOOTER {
+            return Err(Error::BadGuid { structure: STRUCTURE, offset: footer_offset, found: footer });
+        }
+
+        Ok(FileDataStoreObject { guid, data_offset: fcr.start + HEADER_SIZE, len })
+    }
+
+    // Positions reader at the start of the file's data and limits it to the file's length
+    pub fn open<'a, T: Read + Seek>(&self, reader: &'a mut T) -> Result<Take<&'a mut T>> {
+        reader.seek(SeekFrom::Start(self.data_offset))?;
+        Ok(reader.take(self.len))
+    }
+}
+
+impl FileDataStore {
+    // fcr points at the file node list of FileDataStoreObjectReference nodes
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<FileDataStore> {
+        let list = FileNodeList::from_reader(fcr, reader, transaction_log)?;
+
+        let mut objects = HashMap::new();
+        for node in &list.file_nodes {
+            if let FileNodeData::FileDataStoreObjectReference(reference) = &node.data {
+                let object = FileDataStoreObject::from_reader(reference.guid_reference, &node.file_chunk_ref, reader)?;
+                if objects.insert(reference.guid_reference, object).is_some() {
+                    return Err(Error::InvalidData { structure: "FileDataStoreObjectReferenceFND", offset: node.offset, reason: "File data GUID is used twice" });
+                }
+            }
+        }
+
+        Ok(FileDataStore { objects })
+    }
+
+    pub fn get(&self, guid: &Guid) -> Option<&FileDataStoreObject> {
+        self.objects.get(guid)
+    }
+}
@@ -1,15 +1,17 @@
 use std::io::Read;
-use std::io::Error;
-use std::io::ErrorKind;
 use std::io::Seek;
 use std::io::SeekFrom;
+use std::io::Write;
 
 use byteorder::LittleEndian;
 use byteorder::ReadBytesExt;
 use packed_struct::prelude::*;
 use packed_struct::EnumCatchAll::*;
 
+use crate::error::{Error, Result};
+
 use super::filechunkreference::FileChunkReference;
+use super::filenodedata::FileNodeData;
 
 // TODO: REIMPLEMENT FILE NODE HEADER PARSING WITHOUT PACKED STRUCT
 
@@ -47,6 +49,7 @@ pub enum CbFormat {
 }
 
 #[derive(PrimitiveEnum_u8, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BaseType {
     NoReference,
     DataReference,
@@ -54,6 +57,7 @@ pub enum BaseType {
 }
 
 #[derive(PrimitiveEnum_u16, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FileType {
     ObjectSpaceManifestRoot = 0x04,
     ObjectSpaceManifestListReference = 0x08,
@@ -96,28 +100,35 @@ pub enum FileType {
     ChunkTerminator = 0xFF
 }
 
-// TODO: WHAT IF WE DID SOME KIND OF BOX<OBJECT> OR AN OBJECT ENUM? WOULD THAT BE A WASTE OF SPACE?
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FileNode {
+    pub offset: u64,
     pub file_type: FileType,
     pub size: u16,
     pub file_chunk_ref: FileChunkReference,
-    pub base_type: BaseType
+    pub base_type: BaseType,
+    pub data: FileNodeData
 }
 
+// Largest value of the 13 bit size field
+const MAX_FILE_NODE_SIZE: usize = 0x1FFF;
+
 // TODO: DO WE WANT TO BE ABLE TO READ FILE NODES FROM ARBITRARY FCRS OR JUST RELY ON CURRENT READER POSITION? (fromfilechunk?)
 impl FileNode {
-    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<Self, std::io::Error> where Self: Sized {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<Self> where Self: Sized {
         let start_of_file_node = reader.stream_position()?;
         
         // Unpack file node header
         let mut header_buffer: [u8; 4] = [0; 4];
         reader.read_exact(&mut header_buffer)?;
         header_buffer.reverse();
-        let header = FileNodeHeader::unpack(&header_buffer).unwrap();
+        let header = FileNodeHeader::unpack(&header_buffer)
+            .map_err(|_| Error::InvalidData { structure: "FileNode", offset: start_of_file_node, reason: "Invalid file node header" })?;
 
         // Parse file type from file node id field
-        let file_type: FileType = match header.id.into() {
+        let id: u16 = header.id.into();
+        let file_type: FileType = match id {
             0x04 => FileType::ObjectSpaceManifestRoot,
             0x08 => FileType::ObjectSpaceManifestListReference,
             0x0C => FileType::ObjectSpaceManifestListStart,
@@ -157,28 +168,38 @@ impl FileNode {
             0xC4 => FileType::ReadOnlyObjectDeclaration2RefCount,
             0xC5 => FileType::ReadOnlyObjectDeclaration2LargeRefCount,
             0xFF => FileType::ChunkTerminator,
-            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid file node id field on file node"))
+            _ => return Err(Error::UnknownFileNodeId { offset: start_of_file_node, id })
         };
 
-        let base_type: BaseType;
         let fcr_start: u64;
         let fcr_len: u64;
 
         // Parse base type from node id field
-        match header.base_type {
-            Enum(e) => base_type = e,
-            CatchAll(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid base type"))
-        }
+        let base_type = match header.base_type {
+            Enum(e) => e,
+            CatchAll(_) => return Err(Error::InvalidData { structure: "FileNode", offset: start_of_file_node, reason: "Invalid base type" })
+        };
 
         // Depending on base type, stp format, and cb format, create file chunk reference for node body
         match base_type {
             BaseType::DataReference | BaseType::FileNodeReference => {
-                match header.stp_format {
-                    StpFormat::Uncompressed8 => fcr_start = reader.read_u64::<LittleEndian>()?,
-                    StpFormat::Uncompressed4 => fcr_start = reader.read_u32::<LittleEndian>()?.into(),
-                    StpFormat::Compressed2 => fcr_start = reader.read_u16::<LittleEndian>()? as u64 * 8,
-                    StpFormat::Compressed4 => fcr_start = reader.read_u32::<LittleEndian>()? as u64 * 8
-                }
+                let (raw_start, start_bits) = match header.stp_format {
+                    StpFormat::Uncompressed8 => (reader.read_u64::<LittleEndian>()?, 64),
+                    StpFormat::Uncompressed4 => (reader.read_u32::<LittleEndian>()?.into(), 32),
+                    StpFormat::Compressed2 => (reader.read_u16::<LittleEndian>()?.into(), 16),
+                    StpFormat::Compressed4 => (reader.read_u32::<LittleEndian>()?.into(), 32)
+                };
+
+                // An all ones stp field is fcrNil regardless of compression
+                fcr_start = if raw_start.count_ones() == start_bits {
+                    u64::MAX
+                } else {
+                    match header.stp_format {
+                        StpFormat::Compressed2 | StpFormat::Compressed4 => raw_start * 8,
+                        _ => raw_start
+                    }
+                };
+
                 match header.cb_format {
                     CbFormat::Uncompressed8 => fcr_len = reader.read_u64::<LittleEndian>()?,
                     CbFormat::Uncompressed4 => fcr_len = reader.read_u32::<LittleEndian>()?.into(),
@@ -193,15 +214,86 @@ impl FileNode {
             }
         }
 
-        // Simulate reading through this file node's data
+        let file_chunk_ref = FileChunkReference { start: fcr_start, len: fcr_len };
+        let data = FileNodeData::from_reader(file_type, &file_chunk_ref, reader)?;
+
+        // Skip any padding at the end of this file node's data. A node must never be smaller than what we just read.
         let node_size: u16 = header.size.into();
-        reader.seek(SeekFrom::Start(start_of_file_node + node_size as u64))?;
+        let end_of_file_node = start_of_file_node + node_size as u64;
+        if reader.stream_position()? > end_of_file_node {
+            return Err(Error::InvalidData { structure: "FileNode", offset: start_of_file_node, reason: "File node data is larger than its size field" });
+        }
+        reader.seek(SeekFrom::Start(end_of_file_node))?;
 
         Ok(FileNode {
+            offset: start_of_file_node,
             file_type,
             size: header.size.into(),
-            file_chunk_ref: FileChunkReference { start: fcr_start, len: fcr_len },
-            base_type
+            file_chunk_ref,
+            base_type,
+            data
         })
     }
-}
\ No newline at end of file
+
+    // A node to write. The base type follows from the file type, and offset and size are only known
+    // once the node is read back, so they are left at 0
+    pub fn new(file_type: FileType, file_chunk_ref: FileChunkReference, data: FileNodeData) -> FileNode {
+        let base_type = match file_type {
+            FileType::ObjectSpaceManifestListReference
+            | FileType::RevisionManifestListReference
+            | FileType::FileDataStoreListReference
+            | FileType::ObjectGroupListReference => BaseType::FileNodeReference,
+            FileType::ObjectDeclarationWithRefCount
+            | FileType::ObjectDeclarationWithRefCount2
+            | FileType::ObjectRevisionWithRefCount
+            | FileType::ObjectRevisionWithRefCount2
+            | FileType::ObjectDataEncryptionKeyV2
+            | FileType::ObjectInfoDependencyOverrides
+            | FileType::FileDataStoreObjectReference
+            | FileType::ObjectDeclaration2RefCount
+            | FileType::ObjectDeclaration2LargeRefCount
+            | FileType::HashedChunkDescriptor2
+            | FileType::ReadOnlyObjectDeclaration2RefCount
+            | FileType::ReadOnlyObjectDeclaration2LargeRefCount => BaseType::DataReference,
+            _ => BaseType::NoReference
+        };
+        FileNode { offset: 0, file_type, size: 0, file_chunk_ref, base_type, data }
+    }
+
+    // Writes the node with an uncompressed 8 byte stp and the smallest uncompressed cb that holds
+    // file_chunk_ref. offset and size are ignored; the size field is computed from what is written.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let (cb_format, cb_bits) = if self.file_chunk_ref.len > u32::MAX.into() {
+            (CbFormat::Uncompressed8, 64)
+        } else {
+            (CbFormat::Uncompressed4, 32)
+        };
+
+        let mut body = Vec::new();
+        if self.base_type != BaseType::NoReference {
+            self.file_chunk_ref.to_writer(&mut body, 64, cb_bits)?;
+        }
+        self.data.to_writer(&self.file_chunk_ref, &mut body)?;
+
+        let size = body.len() + 4;
+        if size > MAX_FILE_NODE_SIZE {
+            return Err(Error::InvalidData { structure: "FileNode", offset: self.offset, reason: "File node is too large for its size field" });
+        }
+
+        let header = FileNodeHeader {
+            id: (self.file_type as u16).into(),
+            size: (size as u16).into(),
+            stp_format: StpFormat::Uncompressed8,
+            cb_format,
+            base_type: Enum(self.base_type),
+            reserved: 0.into()
+        };
+        let mut header_buffer = header.pack()
+            .map_err(|_| Error::InvalidData { structure: "FileNode", offset: self.offset, reason: "Invalid file node header" })?;
+        header_buffer.reverse();
+
+        writer.write_all(&header_buffer)?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,826 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::check_fcr_bounds;
+use super::compactid::CompactId;
+use super::exguid::ExGuid;
+use super::filechunkreference::FileChunkReference;
+use super::filenode::FileType;
+use super::guid::*;
+use super::jcid::Jcid;
+
+// Parsed body of a file node. The reference part of the node (if any) lives in FileNode::file_chunk_ref.
+// See MS-ONESTORE 2.5
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FileNodeData {
+    ObjectSpaceManifestRoot(ObjectSpaceManifestRootFnd),
+    ObjectSpaceManifestListReference(ObjectSpaceManifestListReferenceFnd),
+    ObjectSpaceManifestListStart(ObjectSpaceManifestListStartFnd),
+    RevisionManifestListReference,
+    RevisionManifestListStart(RevisionManifestListStartFnd),
+    RevisionManifestStart4(RevisionManifestStart4Fnd),
+    RevisionManifestEnd,
+    RevisionManifestStart6(RevisionManifestStart6Fnd),
+    RevisionManifestStart7(RevisionManifestStart7Fnd),
+    GlobalIdTableStart(GlobalIdTableStartFndx),
+    GlobalIdTableStart2,
+    GlobalIdTableEntry(GlobalIdTableEntryFndx),
+    GlobalIdTableEntry2(GlobalIdTableEntry2Fndx),
+    GlobalIdTableEntry3(GlobalIdTableEntry3Fndx),
+    GlobalIdTableEnd,
+    ObjectDeclarationWithRefCount(ObjectDeclarationWithRefCountFndx),
+    ObjectDeclarationWithRefCount2(ObjectDeclarationWithRefCount2Fndx),
+    ObjectRevisionWithRefCount(ObjectRevisionWithRefCountFndx),
+    ObjectRevisionWithRefCount2(ObjectRevisionWithRefCount2Fndx),
+    RootObjectReference2(RootObjectReference2Fndx),
+    RootObjectReference3(RootObjectReference3Fnd),
+    RevisionRoleDeclaration(RevisionRoleDeclarationFnd),
+    RevisionRoleAndContextDeclaration(RevisionRoleAndContextDeclarationFnd),
+    ObjectDeclarationFileData3RefCount(ObjectDeclarationFileData3RefCountFnd),
+    ObjectDeclarationFileData3LargeRefCount(ObjectDeclarationFileData3LargeRefCountFnd),
+    ObjectDataEncryptionKeyV2,
+    ObjectInfoDependencyOverrides(ObjectInfoDependencyOverridesFnd),
+    DataSignatureGroupDefinition(DataSignatureGroupDefinitionFnd),
+    FileDataStoreListReference,
+    FileDataStoreObjectReference(FileDataStoreObjectReferenceFnd),
+    ObjectDeclaration2RefCount(ObjectDeclaration2RefCountFnd),
+    ObjectDeclaration2LargeRefCount(ObjectDeclaration2LargeRefCountFnd),
+    ObjectGroupListReference(ObjectGroupListReferenceFnd),
+    ObjectGroupStart(ObjectGroupStartFnd),
+    ObjectGroupEnd,
+    HashedChunkDescriptor2(HashedChunkDescriptor2Fnd),
+    ReadOnlyObjectDeclaration2RefCount(ReadOnlyObjectDeclaration2RefCountFnd),
+    ReadOnlyObjectDeclaration2LargeRefCount(ReadOnlyObjectDeclaration2LargeRefCountFnd),
+    ChunkTerminator
+}
+
+// See MS-ONESTORE 2.5.1
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectSpaceManifestRootFnd {
+    pub gosid_root: ExGuid
+}
+
+// See MS-ONESTORE 2.5.2
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectSpaceManifestListReferenceFnd {
+    pub gosid: ExGuid
+}
+
+// See MS-ONESTORE 2.5.3
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectSpaceManifestListStartFnd {
+    pub gosid: ExGuid
+}
+
+// See MS-ONESTORE 2.5.5
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionManifestListStartFnd {
+    pub gosid: ExGuid,
+    pub instance: u32
+}
+
+// See MS-ONESTORE 2.5.6
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionManifestStart4Fnd {
+    pub rid: ExGuid,
+    pub rid_dependent: ExGuid,
+    pub time_creation: u64,
+    pub revision_role: u32,
+    pub odcs_default: u16
+}
+
+// See MS-ONESTORE 2.5.7
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionManifestStart6Fnd {
+    pub rid: ExGuid,
+    pub rid_dependent: ExGuid,
+    pub revision_role: u32,
+    pub odcs_default: u16
+}
+
+// See MS-ONESTORE 2.5.8
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionManifestStart7Fnd {
+    pub base: RevisionManifestStart6Fnd,
+    pub gctxid: ExGuid
+}
+
+// See MS-ONESTORE 2.5.9
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalIdTableStartFndx {
+    pub reserved: u8
+}
+
+// See MS-ONESTORE 2.5.10
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalIdTableEntryFndx {
+    pub index: u32,
+    pub guid: Guid
+}
+
+// See MS-ONESTORE 2.5.11
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalIdTableEntry2Fndx {
+    pub index_map_from: u32,
+    pub index_map_to: u32
+}
+
+// See MS-ONESTORE 2.5.12
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalIdTableEntry3Fndx {
+    pub index_copy_from_start: u32,
+    pub entries_to_copy: u32,
+    pub index_copy_to_start: u32
+}
+
+// See MS-ONESTORE 2.6.15
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclarationWithRefCountBody {
+    pub oid: CompactId,
+    pub jci: u16,
+    pub odc: u8,
+    pub has_oid_references: bool,
+    pub has_osid_references: bool
+}
+
+// See MS-ONESTORE 2.5.23
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclarationWithRefCountFndx {
+    pub body: ObjectDeclarationWithRefCountBody,
+    pub c_ref: u8
+}
+
+// See MS-ONESTORE 2.5.24
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclarationWithRefCount2Fndx {
+    pub body: ObjectDeclarationWithRefCountBody,
+    pub c_ref: u32
+}
+
+// See MS-ONESTORE 2.5.13
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectRevisionWithRefCountFndx {
+    pub oid: CompactId,
+    pub has_oid_references: bool,
+    pub has_osid_references: bool,
+    pub c_ref: u8
+}
+
+// See MS-ONESTORE 2.5.14
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectRevisionWithRefCount2Fndx {
+    pub oid: CompactId,
+    pub has_oid_references: bool,
+    pub has_osid_references: bool,
+    pub c_ref: u32
+}
+
+// See MS-ONESTORE 2.5.15
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RootObjectReference2Fndx {
+    pub oid_root: CompactId,
+    pub root_role: u32
+}
+
+// See MS-ONESTORE 2.5.16
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RootObjectReference3Fnd {
+    pub oid_root: ExGuid,
+    pub root_role: u32
+}
+
+// See MS-ONESTORE 2.5.17
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionRoleDeclarationFnd {
+    pub rid: ExGuid,
+    pub revision_role: u32
+}
+
+// See MS-ONESTORE 2.5.18
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevisionRoleAndContextDeclarationFnd {
+    pub base: RevisionRoleDeclarationFnd,
+    pub gctxid: ExGuid
+}
+
+// See MS-ONESTORE 2.5.27
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclarationFileData3RefCountFnd {
+    pub oid: CompactId,
+    pub jcid: Jcid,
+    pub c_ref: u8,
+    pub file_data_reference: String,
+    pub extension: String
+}
+
+// See MS-ONESTORE 2.5.28
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclarationFileData3LargeRefCountFnd {
+    pub oid: CompactId,
+    pub jcid: Jcid,
+    pub c_ref: u32,
+    pub file_data_reference: String,
+    pub extension: String
+}
+
+// See MS-ONESTORE 2.6.10
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectInfoDependencyOverride8 {
+    pub oid: CompactId,
+    pub c_ref: u8
+}
+
+// See MS-ONESTORE 2.6.11
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectInfoDependencyOverride32 {
+    pub oid: CompactId,
+    pub c_ref: u32
+}
+
+// See MS-ONESTORE 2.6.12
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectInfoDependencyOverrideData {
+    pub crc: u32,
+    pub overrides_1: Vec<ObjectInfoDependencyOverride8>,
+    pub overrides_2: Vec<ObjectInfoDependencyOverride32>
+}
+
+// See MS-ONESTORE 2.5.20. The override data is read from the referenced chunk if the node has one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectInfoDependencyOverridesFnd {
+    pub data: ObjectInfoDependencyOverrideData
+}
+
+// See MS-ONESTORE 2.5.33
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DataSignatureGroupDefinitionFnd {
+    pub data_signature_group: ExGuid
+}
+
+// See MS-ONESTORE 2.5.22
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FileDataStoreObjectReferenceFnd {
+    pub guid_reference: Guid
+}
+
+// See MS-ONESTORE 2.6.16
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclaration2Body {
+    pub oid: CompactId,
+    pub jcid: Jcid,
+    pub has_oid_references: bool,
+    pub has_osid_references: bool
+}
+
+// See MS-ONESTORE 2.5.25
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclaration2RefCountFnd {
+    pub body: ObjectDeclaration2Body,
+    pub c_ref: u8
+}
+
+// See MS-ONESTORE 2.5.26
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectDeclaration2LargeRefCountFnd {
+    pub body: ObjectDeclaration2Body,
+    pub c_ref: u32
+}
+
+// See MS-ONESTORE 2.5.31
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectGroupListReferenceFnd {
+    pub object_group_id: ExGuid
+}
+
+// See MS-ONESTORE 2.5.32
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectGroupStartFnd {
+    pub oid: ExGuid
+}
+
+// See MS-ONESTORE 2.3.4.1
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HashedChunkDescriptor2Fnd {
+    pub guid_hash: [u8; 16]
+}
+
+// See MS-ONESTORE 2.5.29
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReadOnlyObjectDeclaration2RefCountFnd {
+    pub base: ObjectDeclaration2RefCountFnd,
+    pub md5_hash: [u8; 16]
+}
+
+// See MS-ONESTORE 2.5.30
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReadOnlyObjectDeclaration2LargeRefCountFnd {
+    pub base: ObjectDeclaration2LargeRefCountFnd,
+    pub md5_hash: [u8; 16]
+}
+
+impl FileNodeData {
+    // Reader must be positioned directly after the file node's reference (or header if it has none)
+    pub fn from_reader<T: Read + Seek>(file_type: FileType, fcr: &FileChunkReference, reader: &mut T) -> Result<FileNodeData> {
+        let data = match file_type {
+            FileType::ObjectSpaceManifestRoot => FileNodeData::ObjectSpaceManifestRoot(ObjectSpaceManifestRootFnd {
+                gosid_root: ExGuid::from_reader(reader)?
+            }),
+            FileType::ObjectSpaceManifestListReference => FileNodeData::ObjectSpaceManifestListReference(ObjectSpaceManifestListReferenceFnd {
+                gosid: ExGuid::from_reader(reader)?
+            }),
+            FileType::ObjectSpaceManifestListStart => FileNodeData::ObjectSpaceManifestListStart(ObjectSpaceManifestListStartFnd {
+                gosid: ExGuid::from_reader(reader)?
+            }),
+            FileType::RevisionManifestListReference => FileNodeData::RevisionManifestListReference,
+            FileType::RevisionManifestListStart => FileNodeData::RevisionManifestListStart(RevisionManifestListStartFnd {
+                gosid: ExGuid::from_reader(reader)?,
+                instance: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::RevisionManifestStart4 => FileNodeData::RevisionManifestStart4(RevisionManifestStart4Fnd {
+                rid: ExGuid::from_reader(reader)?,
+                rid_dependent: ExGuid::from_reader(reader)?,
+                time_creation: reader.read_u64::<LittleEndian>()?,
+                revision_role: reader.read_u32::<LittleEndian>()?,
+                odcs_default: reader.read_u16::<LittleEndian>()?
+            }),
+            FileType::RevisionManifestEnd => FileNodeData::RevisionManifestEnd,
+            FileType::RevisionManifestStart6 => FileNodeData::RevisionManifestStart6(RevisionManifestStart6Fnd::from_reader(reader)?),
+            FileType::RevisionManifestStart7 => FileNodeData::RevisionManifestStart7(RevisionManifestStart7Fnd {
+                base: RevisionManifestStart6Fnd::from_reader(reader)?,
+                gctxid: ExGuid::from_reader(reader)?
+            }),
+            FileType::GlobalIdTableStart => FileNodeData::GlobalIdTableStart(GlobalIdTableStartFndx {
+                reserved: reader.read_u8()?
+            }),
+            FileType::GlobalIdTableStart2 => FileNodeData::GlobalIdTableStart2,
+            FileType::GlobalIdTableEntry => FileNodeData::GlobalIdTableEntry(GlobalIdTableEntryFndx {
+                index: reader.read_u32::<LittleEndian>()?,
+                guid: Guid::from_reader(reader)?
+            }),
+            FileType::GlobalIdTableEntry2 => FileNodeData::GlobalIdTableEntry2(GlobalIdTableEntry2Fndx {
+                index_map_from: reader.read_u32::<LittleEndian>()?,
+                index_map_to: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::GlobalIdTableEntry3 => FileNodeData::GlobalIdTableEntry3(GlobalIdTableEntry3Fndx {
+                index_copy_from_start: reader.read_u32::<LittleEndian>()?,
+                entries_to_copy: reader.read_u32::<LittleEndian>()?,
+                index_copy_to_start: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::GlobalIdTableEnd => FileNodeData::GlobalIdTableEnd,
+            FileType::ObjectDeclarationWithRefCount => FileNodeData::ObjectDeclarationWithRefCount(ObjectDeclarationWithRefCountFndx {
+                body: ObjectDeclarationWithRefCountBody::from_reader(reader)?,
+                c_ref: reader.read_u8()?
+            }),
+            FileType::ObjectDeclarationWithRefCount2 => FileNodeData::ObjectDeclarationWithRefCount2(ObjectDeclarationWithRefCount2Fndx {
+                body: ObjectDeclarationWithRefCountBody::from_reader(reader)?,
+                c_ref: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::ObjectRevisionWithRefCount => {
+                let oid = CompactId::from_reader(reader)?;
+                let flags = reader.read_u8()?;
+                FileNodeData::ObjectRevisionWithRefCount(ObjectRevisionWithRefCountFndx {
+                    oid,
+                    has_oid_references: flags & 0x1 != 0,
+                    has_osid_references: flags & 0x2 != 0,
+                    c_ref: flags >> 2
+                })
+            },
+            FileType::ObjectRevisionWithRefCount2 => {
+                let oid = CompactId::from_reader(reader)?;
+                let flags = reader.read_u32::<LittleEndian>()?;
+                FileNodeData::ObjectRevisionWithRefCount2(ObjectRevisionWithRefCount2Fndx {
+                    oid,
+                    has_oid_references: flags & 0x1 != 0,
+                    has_osid_references: flags & 0x2 != 0,
+                    c_ref: reader.read_u32::<LittleEndian>()?
+                })
+            },
+            FileType::RootObjectReference2 => FileNodeData::RootObjectReference2(RootObjectReference2Fndx {
+                oid_root: CompactId::from_reader(reader)?,
+                root_role: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::RootObjectReference3 => FileNodeData::RootObjectReference3(RootObjectReference3Fnd {
+                oid_root: ExGuid::from_reader(reader)?,
+                root_role: reader.read_u32::<LittleEndian>()?
+            }),
+            FileType::RevisionRoleDeclaration => FileNodeData::RevisionRoleDeclaration(RevisionRoleDeclarationFnd::from_reader(reader)?),
+            FileType::RevisionRoleAndContextDeclaration => FileNodeData::RevisionRoleAndContextDeclaration(RevisionRoleAndContextDeclarationFnd {
+                base: RevisionRoleDeclarationFnd::from_reader(reader)?,
+                gctxid: ExGuid::from_reader(reader)?
+            }),
+            FileType::ObjectDeclarationFileData3RefCount => FileNodeData::ObjectDeclarationFileData3RefCount(ObjectDeclarationFileData3RefCountFnd {
+                oid: CompactId::from_reader(reader)?,
+                jcid: Jcid::from_reader(reader)?,
+                c_ref: reader.read_u8()?,
+                file_data_reference: read_string_in_storage_buffer(reader)?,
+                extension: read_string_in_storage_buffer(reader)?
+            }),
+            FileType::ObjectDeclarationFileData3LargeRefCount => FileNodeData::ObjectDeclarationFileData3LargeRefCount(ObjectDeclarationFileData3LargeRefCountFnd {
+                oid: CompactId::from_reader(reader)?,
+                jcid: Jcid::from_reader(reader)?,
+                c_ref: reader.read_u32::<LittleEndian>()?,
+                file_data_reference: read_string_in_storage_buffer(reader)?,
+                extension: read_string_in_storage_buffer(reader)?
+            }),
+            FileType::ObjectDataEncryptionKeyV2 => FileNodeData::ObjectDataEncryptionKeyV2,
+            FileType::ObjectInfoDependencyOverrides => {
+                // Override data is stored inline only when the reference is nil
+                let data = if fcr.is_nil() {
+                    ObjectInfoDependencyOverrideData::from_reader(reader)?
+                } else {
+                    check_fcr_bounds(fcr, reader, "ObjectInfoDependencyOverrideData")?;
+                    let return_position = reader.stream_position()?;
+                    reader.seek(SeekFrom::Start(fcr.start))?;
+                    let data = ObjectInfoDependencyOverrideData::from_reader(reader)?;
+                    reader.seek(SeekFrom::Start(return_position))?;
+                    data
+                };
+                FileNodeData::ObjectInfoDependencyOverrides(ObjectInfoDependencyOverridesFnd { data })
+            },
+            FileType::DataSignatureGroupDefinition => FileNodeData::DataSignatureGroupDefinition(DataSignatureGroupDefinitionFnd {
+                data_signature_group: ExGuid::from_reader(reader)?
+            }),
+            FileType::FileDataStoreListReference => FileNodeData::FileDataStoreListReference,
+            FileType::FileDataStoreObjectReference => FileNodeData::FileDataStoreObjectReference(FileDataStoreObjectReferenceFnd {
+                guid_reference: Guid::from_reader(reader)?
+            }),
+            FileType::ObjectDeclaration2RefCount => FileNodeData::ObjectDeclaration2RefCount(ObjectDeclaration2RefCountFnd::from_reader(reader)?),
+            FileType::ObjectDeclaration2LargeRefCount => FileNodeData::ObjectDeclaration2LargeRefCount(ObjectDeclaration2LargeRefCountFnd::from_reader(reader)?),
+            FileType::ObjectGroupListReference => FileNodeData::ObjectGroupListReference(ObjectGroupListReferenceFnd {
+                object_group_id: ExGuid::from_reader(reader)?
+            }),
+            FileType::ObjectGroupStart => FileNodeData::ObjectGroupStart(ObjectGroupStartFnd {
+                oid: ExGuid::from_reader(reader)?
+            }),
+            FileType::ObjectGroupEnd => FileNodeData::ObjectGroupEnd,
+            FileType::HashedChunkDescriptor2 => FileNodeData::HashedChunkDescriptor2(HashedChunkDescriptor2Fnd {
+                guid_hash: read_md5(reader)?
+            }),
+            FileType::ReadOnlyObjectDeclaration2RefCount => FileNodeData::ReadOnlyObjectDeclaration2RefCount(ReadOnlyObjectDeclaration2RefCountFnd {
+                base: ObjectDeclaration2RefCountFnd::from_reader(reader)?,
+                md5_hash: read_md5(reader)?
+            }),
+            FileType::ReadOnlyObjectDeclaration2LargeRefCount => FileNodeData::ReadOnlyObjectDeclaration2LargeRefCount(ReadOnlyObjectDeclaration2LargeRefCountFnd {
+                base: ObjectDeclaration2LargeRefCountFnd::from_reader(reader)?,
+                md5_hash: read_md5(reader)?
+            }),
+            FileType::ChunkTerminator => FileNodeData::ChunkTerminator
+        };
+
+        Ok(data)
+    }
+
+    // Writes the node body in the layout from_reader reads. Override data is only written inline
+    // when fcr is nil; otherwise it belongs in the referenced chunk, see ObjectInfoDependencyOverrideData::to_writer.
+    pub fn to_writer<W: Write>(&self, fcr: &FileChunkReference, writer: &mut W) -> Result<()> {
+        match self {
+            FileNodeData::ObjectSpaceManifestRoot(root) => write_exguid(writer, &root.gosid_root)?,
+            FileNodeData::ObjectSpaceManifestListReference(reference) => write_exguid(writer, &reference.gosid)?,
+            FileNodeData::ObjectSpaceManifestListStart(start) => write_exguid(writer, &start.gosid)?,
+            FileNodeData::RevisionManifestListStart(start) => {
+                write_exguid(writer, &start.gosid)?;
+                writer.write_u32::<LittleEndian>(start.instance)?;
+            },
+            FileNodeData::RevisionManifestStart4(start) => {
+                write_exguid(writer, &start.rid)?;
+                write_exguid(writer, &start.rid_dependent)?;
+                writer.write_u64::<LittleEndian>(start.time_creation)?;
+                writer.write_u32::<LittleEndian>(start.revision_role)?;
+                writer.write_u16::<LittleEndian>(start.odcs_default)?;
+            },
+            FileNodeData::RevisionManifestStart6(start) => start.to_writer(writer)?,
+            FileNodeData::RevisionManifestStart7(start) => {
+                start.base.to_writer(writer)?;
+                write_exguid(writer, &start.gctxid)?;
+            },
+            FileNodeData::GlobalIdTableStart(start) => writer.write_u8(start.reserved)?,
+            FileNodeData::GlobalIdTableEntry(entry) => {
+                writer.write_u32::<LittleEndian>(entry.index)?;
+                writer.write_all(&entry.guid.to_bytes_le())?;
+            },
+            FileNodeData::GlobalIdTableEntry2(entry) => {
+                writer.write_u32::<LittleEndian>(entry.index_map_from)?;
+                writer.write_u32::<LittleEndian>(entry.index_map_to)?;
+            },
+            FileNodeData::GlobalIdTableEntry3(entry) => {
+                writer.write_u32::<LittleEndian>(entry.index_copy_from_start)?;
+                writer.write_u32::<LittleEndian>(entry.entries_to_copy)?;
+                writer.write_u32::<LittleEndian>(entry.index_copy_to_start)?;
+            },
+            FileNodeData::ObjectDeclarationWithRefCount(declaration) => {
+                declaration.body.to_writer(writer)?;
+                writer.write_u8(declaration.c_ref)?;
+            },
+            FileNodeData::ObjectDeclarationWithRefCount2(declaration) => {
+                declaration.body.to_writer(writer)?;
+                writer.write_u32::<LittleEndian>(declaration.c_ref)?;
+            },
+            FileNodeData::ObjectRevisionWithRefCount(revision) => {
+                // cRef shares a byte with the flags; larger counts need ObjectRevisionWithRefCount2FNDX
+                if revision.c_ref > 0x3F {
+                    return Err(Error::InvalidData { structure: "ObjectRevisionWithRefCountFNDX", offset: 0, reason: "Reference count is too large for 6 bits" });
+                }
+                write_compact_id(writer, &revision.oid)?;
+                writer.write_u8(reference_flags(revision.has_oid_references, revision.has_osid_references) as u8 | (revision.c_ref << 2))?;
+            },
+            FileNodeData::ObjectRevisionWithRefCount2(revision) => {
+                write_compact_id(writer, &revision.oid)?;
+                writer.write_u32::<LittleEndian>(reference_flags(revision.has_oid_references, revision.has_osid_references))?;
+                writer.write_u32::<LittleEndian>(revision.c_ref)?;
+            },
+            FileNodeData::RootObjectReference2(reference) => {
+                write_compact_id(writer, &reference.oid_root)?;
+                writer.write_u32::<LittleEndian>(reference.root_role)?;
+            },
+            FileNodeData::RootObjectReference3(reference) => {
+                write_exguid(writer, &reference.oid_root)?;
+                writer.write_u32::<LittleEndian>(reference.root_role)?;
+            },
+            FileNodeData::RevisionRoleDeclaration(declaration) => declaration.to_writer(writer)?,
+            FileNodeData::RevisionRoleAndContextDeclaration(declaration) => {
+                declaration.base.to_writer(writer)?;
+                write_exguid(writer, &declaration.gctxid)?;
+            },
+            FileNodeData::ObjectDeclarationFileData3RefCount(declaration) => {
+                write_compact_id(writer, &declaration.oid)?;
+                writer.write_u32::<LittleEndian>(declaration.jcid.0)?;
+                writer.write_u8(declaration.c_ref)?;
+                write_string_in_storage_buffer(writer, &declaration.file_data_reference)?;
+                write_string_in_storage_buffer(writer, &declaration.extension)?;
+            },
+            FileNodeData::ObjectDeclarationFileData3LargeRefCount(declaration) => {
+                write_compact_id(writer, &declaration.oid)?;
+                writer.write_u32::<LittleEndian>(declaration.jcid.0)?;
+                writer.write_u32::<LittleEndian>(declaration.c_ref)?;
+                write_string_in_storage_buffer(writer, &declaration.file_data_reference)?;
+                write_string_in_storage_buffer(writer, &declaration.extension)?;
+            },
+            FileNodeData::ObjectInfoDependencyOverrides(overrides) => {
+                if fcr.is_nil() {
+                    overrides.data.to_writer(writer)?;
+                }
+            },
+            FileNodeData::DataSignatureGroupDefinition(definition) => write_exguid(writer, &definition.data_signature_group)?,
+            FileNodeData::FileDataStoreObjectReference(reference) => writer.write_all(&reference.guid_reference.to_bytes_le())?,
+            FileNodeData::ObjectDeclaration2RefCount(declaration) => declaration.to_writer(writer)?,
+            FileNodeData::ObjectDeclaration2LargeRefCount(declaration) => declaration.to_writer(writer)?,
+            FileNodeData::ObjectGroupListReference(reference) => write_exguid(writer, &reference.object_group_id)?,
+            FileNodeData::ObjectGroupStart(start) => write_exguid(writer, &start.oid)?,
+            FileNodeData::HashedChunkDescriptor2(descriptor) => writer.write_all(&descriptor.guid_hash)?,
+            FileNodeData::ReadOnlyObjectDeclaration2RefCount(declaration) => {
+                declaration.base.to_writer(writer)?;
+                writer.write_all(&declaration.md5_hash)?;
+            },
+            FileNodeData::ReadOnlyObjectDeclaration2LargeRefCount(declaration) => {
+                declaration.base.to_writer(writer)?;
+                writer.write_all(&declaration.md5_hash)?;
+            },
+            FileNodeData::RevisionManifestListReference
+            | FileNodeData::RevisionManifestEnd
+            | FileNodeData::GlobalIdTableStart2
+            | FileNodeData::GlobalIdTableEnd
+            | FileNodeData::ObjectDataEncryptionKeyV2
+            | FileNodeData::FileDataStoreListReference
+            | FileNodeData::ObjectGroupEnd
+            | FileNodeData::ChunkTerminator => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl RevisionManifestStart6Fnd {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<RevisionManifestStart6Fnd> {
+        Ok(RevisionManifestStart6Fnd {
+            rid: ExGuid::from_reader(reader)?,
+            rid_dependent: ExGuid::from_reader(reader)?,
+            revision_role: reader.read_u32::<LittleEndian>()?,
+            odcs_default: reader.read_u16::<LittleEndian>()?
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_exguid(writer, &self.rid)?;
+        write_exguid(writer, &self.rid_dependent)?;
+        writer.write_u32::<LittleEndian>(self.revision_role)?;
+        writer.write_u16::<LittleEndian>(self.odcs_default)?;
+        Ok(())
+    }
+}
+
+impl RevisionRoleDeclarationFnd {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<RevisionRoleDeclarationFnd> {
+        Ok(RevisionRoleDeclarationFnd {
+            rid: ExGuid::from_reader(reader)?,
+            revision_role: reader.read_u32::<LittleEndian>()?
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_exguid(writer, &self.rid)?;
+        writer.write_u32::<LittleEndian>(self.revision_role)?;
+        Ok(())
+    }
+}
+
+impl ObjectDeclarationWithRefCountBody {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<ObjectDeclarationWithRefCountBody> {
+        let oid = CompactId::from_reader(reader)?;
+
+        // jci (10 bits), odc (4 bits), fReserved1 (2 bits)
+        let jci_and_odc = reader.read_u16::<LittleEndian>()?;
+        // fHasOidReferences (1 bit), fHasOsidReferences (1 bit), fReserved2 (30 bits)
+        let flags = reader.read_u32::<LittleEndian>()?;
+
+        Ok(ObjectDeclarationWithRefCountBody {
+            oid,
+            jci: jci_and_odc & 0x3FF,
+            odc: ((jci_and_odc >> 10) & 0xF) as u8,
+            has_oid_references: flags & 0x1 != 0,
+            has_osid_references: flags & 0x2 != 0
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.jci > 0x3FF || self.odc > 0xF {
+            return Err(Error::InvalidData { structure: "ObjectDeclarationWithRefCountBody", offset: 0, reason: "jci or odc is too large for its bit field" });
+        }
+        write_compact_id(writer, &self.oid)?;
+        writer.write_u16::<LittleEndian>(self.jci | (self.odc as u16) << 10)?;
+        writer.write_u32::<LittleEndian>(reference_flags(self.has_oid_references, self.has_osid_references))?;
+        Ok(())
+    }
+}
+
+impl ObjectDeclaration2Body {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<ObjectDeclaration2Body> {
+        let oid = CompactId::from_reader(reader)?;
+        let jcid = Jcid::from_reader(reader)?;
+        let flags = reader.read_u8()?;
+
+        Ok(ObjectDeclaration2Body {
+            oid,
+            jcid,
+            has_oid_references: flags & 0x1 != 0,
+            has_osid_references: flags & 0x2 != 0
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_compact_id(writer, &self.oid)?;
+        writer.write_u32::<LittleEndian>(self.jcid.0)?;
+        writer.write_u8(reference_flags(self.has_oid_references, self.has_osid_references) as u8)?;
+        Ok(())
+    }
+}
+
+impl ObjectDeclaration2RefCountFnd {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<ObjectDeclaration2RefCountFnd> {
+        Ok(ObjectDeclaration2RefCountFnd {
+            body: ObjectDeclaration2Body::from_reader(reader)?,
+            c_ref: reader.read_u8()?
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.body.to_writer(writer)?;
+        writer.write_u8(self.c_ref)?;
+        Ok(())
+    }
+}
+
+impl ObjectDeclaration2LargeRefCountFnd {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<ObjectDeclaration2LargeRefCountFnd> {
+        Ok(ObjectDeclaration2LargeRefCountFnd {
+            body: ObjectDeclaration2Body::from_reader(reader)?,
+            c_ref: reader.read_u32::<LittleEndian>()?
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.body.to_writer(writer)?;
+        writer.write_u32::<LittleEndian>(self.c_ref)?;
+        Ok(())
+    }
+}
+
+impl ObjectInfoDependencyOverrideData {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<ObjectInfoDependencyOverrideData> {
+        let overrides_1_count = reader.read_u32::<LittleEndian>()?;
+        let overrides_2_count = reader.read_u32::<LittleEndian>()?;
+        let crc = reader.read_u32::<LittleEndian>()?;
+
+        let mut overrides_1 = Vec::new();
+        for _ in 0..overrides_1_count {
+            overrides_1.push(ObjectInfoDependencyOverride8 {
+                oid: CompactId::from_reader(reader)?,
+                c_ref: reader.read_u8()?
+            });
+        }
+
+        let mut overrides_2 = Vec::new();
+        for _ in 0..overrides_2_count {
+            overrides_2.push(ObjectInfoDependencyOverride32 {
+                oid: CompactId::from_reader(reader)?,
+                c_ref: reader.read_u32::<LittleEndian>()?
+            });
+        }
+
+        Ok(ObjectInfoDependencyOverrideData { crc, overrides_1, overrides_2 })
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<LittleEndian>(self.overrides_1.len() as u32)?;
+        writer.write_u32::<LittleEndian>(self.overrides_2.len() as u32)?;
+        writer.write_u32::<LittleEndian>(self.crc)?;
+        for entry in &self.overrides_1 {
+            write_compact_id(writer, &entry.oid)?;
+            writer.write_u8(entry.c_ref)?;
+        }
+        for entry in &self.overrides_2 {
+            write_compact_id(writer, &entry.oid)?;
+            writer.write_u32::<LittleEndian>(entry.c_ref)?;
+        }
+        Ok(())
+    }
+}
+
+// See MS-ONESTORE 2.2.3
+fn read_string_in_storage_buffer<T: Read + Seek>(reader: &mut T) -> Result<String> {
+    let offset = reader.stream_position()?;
+    let character_count = reader.read_u32::<LittleEndian>()?;
+    let mut characters = Vec::new();
+    for _ in 0..character_count {
+        characters.push(reader.read_u16::<LittleEndian>()?);
+    }
+
+    String::from_utf16(&characters).map_err(|_| Error::InvalidData { structure: "StringInStorageBuffer", offset, reason: "String is not valid UTF-16" })
+}
+
+fn write_string_in_storage_buffer<W: Write>(writer: &mut W, string: &str) -> Result<()> {
+    let characters: Vec<u16> = string.encode_utf16().collect();
+    writer.write_u32::<LittleEndian>(characters.len() as u32)?;
+    for character in characters {
+        writer.write_u16::<LittleEndian>(character)?;
+    }
+    Ok(())
+}
+
+fn write_exguid<W: Write>(writer: &mut W, exguid: &ExGuid) -> Result<()> {
+    Ok(exguid.to_writer(writer)?)
+}
+
+fn write_compact_id<W: Write>(writer: &mut W, id: &CompactId) -> Result<()> {
+    Ok(id.to_writer(writer)?)
+}
+
+// fHasOidReferences and fHasOsidReferences, the low two bits of every flags field that has them
+fn reference_flags(has_oid_references: bool, has_osid_references: bool) -> u32 {
+    has_oid_references as u32 | (has_osid_references as u32) << 1
+}
+
+fn read_md5<T: Read>(reader: &mut T) -> Result<[u8; 16]> {
+    let mut hash: [u8; 16] = [0; 16];
+    reader.read_exact(&mut hash)?;
+    Ok(hash)
+}
@@ -1,63 +1,143 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-use super::{filenode::*, filechunkreference::FileChunkReference, transactionlog::TransactionLog};
-use std::io::{Read, Seek, SeekFrom, Error, ErrorKind};
+use crate::error::{Error, Result};
+
+use super::{check_fcr_bounds, filenode::*, filechunkreference::FileChunkReference, transactionlog::TransactionLog};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+const STRUCTURE: &str = "FileNodeListFragment";
+
+// Header (magic, list id, sequence index) plus footer (nextFragment, magic)
+const FRAGMENT_HEADER_SIZE: u64 = 16;
+const FRAGMENT_FOOTER_SIZE: u64 = 20;
 
 pub const FILE_NODE_LIST_HEADER_MAGIC: u64 = 0xA4567AB1F5F7F4C4;
 pub const FILE_NODE_LIST_FOOTER_MAGIC: u64 = 0x8BC215C38233BA4B;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FileNodeList {
     pub id: u32,
     pub fragment_sequence_index: u32,
     pub file_nodes: Vec<FileNode>
 }
 
+// The last fragment of a list and where its committed file nodes end, which is where a writer links
+// in more nodes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FragmentTail {
+    pub fragment: FileChunkReference,
+    pub sequence_index: u32,
+    pub end_of_file_nodes: u64
+}
+
+impl FragmentTail {
+    // Position of the fragment's nextFragment field
+    pub fn next_fragment_position(&self) -> u64 {
+        self.fragment.start + self.fragment.len - FRAGMENT_FOOTER_SIZE
+    }
+}
+
 // TODO: CONVERT FILE NODE LISTS TO ITERATORS?
 // Maybe make the file node list some kind of DFS tree iterator with a find by GUID function to search for specific nodes?
 
 impl FileNodeList {
-    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<Self, std::io::Error> where Self: Sized {
-        reader.seek(SeekFrom::Start(fcr.start))?;
+    // Reads every fragment of the file node list starting at fcr. See MS-ONESTORE 2.4
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<Self> where Self: Sized {
+        Ok(FileNodeList::from_reader_with_tail(fcr, reader, transaction_log)?.0)
+    }
 
-        // Parse file node list header
-        let magic = reader.read_u64::<LittleEndian>()?;
-        if magic != FILE_NODE_LIST_HEADER_MAGIC {
-            return Err(Error::new(ErrorKind::InvalidData, "Invalid file node header magic"));
-        }
+    pub fn from_reader_with_tail<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<(FileNodeList, FragmentTail)> {
+        let mut fragment_fcr = *fcr;
+        let mut id: u32 = 0;
+        let mut first_fragment_sequence_index: u32 = 0;
+        let mut last_fragment_sequence_index: Option<u32> = None;
+        let mut file_node_list_len: u32 = 0;
+        let mut file_nodes = Vec::new();
 
-        let id = reader.read_u32::<LittleEndian>()?;
-        let fragment_sequence_index = reader.read_u32::<LittleEndian>()?;
+        loop {
+            check_fcr_bounds(&fragment_fcr, reader, STRUCTURE)?;
+            if fragment_fcr.len < FRAGMENT_HEADER_SIZE + FRAGMENT_FOOTER_SIZE {
+                return Err(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "Fragment is too small to hold its header and footer" });
+            }
+            reader.seek(SeekFrom::Start(fragment_fcr.start))?;
 
-        let mut file_node_list = FileNodeList{
-            id,
-            fragment_sequence_index,
-            file_nodes: Vec::new()
-        };
+            // Parse file node list fragment header
+            let magic = reader.read_u64::<LittleEndian>()?;
+            if magic != FILE_NODE_LIST_HEADER_MAGIC {
+                return Err(Error::BadMagic { structure: STRUCTURE, offset: fragment_fcr.start, found: magic });
+            }
 
-        // Get length of this file node list from the transaction log
-        let file_node_list_len: u32 = *transaction_log.get(&file_node_list.id).unwrap();
-        let mut current_file_node: u32 = 0;
+            let fragment_id = reader.read_u32::<LittleEndian>()?;
+            let fragment_sequence_index = reader.read_u32::<LittleEndian>()?;
 
-        while current_file_node < file_node_list_len && reader.stream_position()? < fcr.start + fcr.len - 20 {
-            let new_file_node = FileNode::from_reader(reader)?;
-            file_node_list.file_nodes.push(new_file_node);
-            current_file_node += 1;
-        }
+            match last_fragment_sequence_index {
+                None => {
+                    id = fragment_id;
+                    first_fragment_sequence_index = fragment_sequence_index;
+                    // Get length of this file node list from the transaction log
+                    file_node_list_len = *transaction_log.get(&id)
+                        .ok_or(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "File node list id is not in the transaction log" })?;
+                },
+                Some(last_index) => {
+                    if fragment_id != id {
+                        return Err(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "Fragment has a different list id than the first fragment" });
+                    }
+                    if fragment_sequence_index <= last_index {
+                        return Err(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "Fragment sequence index is not increasing" });
+                    }
+                }
+            }
+            last_fragment_sequence_index = Some(fragment_sequence_index);
 
-        // Verify nextFragment file chunk reference always or only if needed?
-        if current_file_node < file_node_list_len {
-            todo!("Move on to next fragment of file node list");
-        }
+            // Stop at the end of the list, at a chunk terminator, or when there is no room left for another file node
+            let next_fragment_position = fragment_fcr.start + fragment_fcr.len - FRAGMENT_FOOTER_SIZE;
+            while file_nodes.len() < file_node_list_len as usize && reader.stream_position()? + 4 <= next_fragment_position {
+                let file_node_offset = reader.stream_position()?;
+                let new_file_node = FileNode::from_reader(reader)?;
+                if reader.stream_position()? > next_fragment_position {
+                    return Err(Error::InvalidData { structure: "FileNode", offset: file_node_offset, reason: "File node extends past the end of its fragment" });
+                }
+                if new_file_node.file_type == FileType::ChunkTerminator {
+                    break;
+                }
+                file_nodes.push(new_file_node);
+            }
+            let end_of_file_nodes = reader.stream_position()?;
 
-        // Verify footer
-        reader.seek(SeekFrom::Start(fcr.start + fcr.len - 20))?;
-        let _next_fragment = FileChunkReference::from_reader(reader, 64, 32)?;
-        let footer = reader.read_u64::<LittleEndian>()?;
-        if footer != FILE_NODE_LIST_FOOTER_MAGIC {
-            return Err(Error::new(ErrorKind::InvalidData, "Incorrect footer magic for FileNodeListFragment"));
+            // Verify footer
+            reader.seek(SeekFrom::Start(next_fragment_position))?;
+            let next_fragment = FileChunkReference::from_reader(reader, 64, 32)?;
+            let footer_offset = reader.stream_position()?;
+            let footer = reader.read_u64::<LittleEndian>()?;
+            if footer != FILE_NODE_LIST_FOOTER_MAGIC {
+                return Err(Error::BadMagic { structure: STRUCTURE, offset: footer_offset, found: footer });
+            }
+
+            // nextFragment only has to be followed if there are file nodes left to read
+            if file_nodes.len() >= file_node_list_len as usize {
+                let list = FileNodeList { id, fragment_sequence_index: first_fragment_sequence_index, file_nodes };
+                let tail = FragmentTail { fragment: fragment_fcr, sequence_index: fragment_sequence_index, end_of_file_nodes };
+                return Ok((list, tail));
+            }
+            if next_fragment.is_nil() || next_fragment.is_zero() {
+                return Err(Error::InvalidData { structure: STRUCTURE, offset: next_fragment_position, reason: "File node list ended before all of its file nodes were read" });
+            }
+            fragment_fcr = next_fragment;
         }
+    }
 
-        Ok(file_node_list)
+    // Writes the whole list as one fragment whose nextFragment is next. Readers take the number of
+    // nodes from the transaction log, so no ChunkTerminator is needed after them.
+    pub fn to_writer<W: Write>(&self, writer: &mut W, next: &FileChunkReference) -> Result<()> {
+        writer.write_u64::<LittleEndian>(FILE_NODE_LIST_HEADER_MAGIC)?;
+        writer.write_u32::<LittleEndian>(self.id)?;
+        writer.write_u32::<LittleEndian>(self.fragment_sequence_index)?;
+        for file_node in &self.file_nodes {
+            file_node.to_writer(writer)?;
+        }
+        next.to_writer(writer, 64, 32)?;
+        writer.write_u64::<LittleEndian>(FILE_NODE_LIST_FOOTER_MAGIC)?;
+        Ok(())
     }
-}
\ No newline at end of file
+}
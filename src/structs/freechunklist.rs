@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::check_fcr_bounds;
+use super::crc::crc32;
+use super::filechunkreference::FileChunkReference;
+
+const STRUCTURE: &str = "FreeChunkListFragment";
+
+// crc and fcrNextChunk
+const FRAGMENT_HEADER_SIZE: u64 = 16;
+// Every fcrFreeChunk is a FileChunkReference64
+const FREE_CHUNK_SIZE: u64 = 16;
+
+// Unused ranges of the file that a writer may reuse. See MS-ONESTORE 2.3.2
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FreeChunkList {
+    // Sorted by start
+    pub free_chunks: Vec<FileChunkReference>
+}
+
+impl FreeChunkList {
+    // Follows the chain of FreeChunkListFragments starting at fcr
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<FreeChunkList> {
+        let mut free_chunks = Vec::new();
+        let mut visited = HashSet::new();
+        let mut fragment_fcr = *fcr;
+
+        while !fragment_fcr.is_nil() && !fragment_fcr.is_zero() {
+            if !visited.insert(fragment_fcr.start) {
+                return Err(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "Free chunk list loops back on itself" });
+            }
+            check_fcr_bounds(&fragment_fcr, reader, STRUCTURE)?;
+            if fragment_fcr.len < FRAGMENT_HEADER_SIZE || !(fragment_fcr.len - FRAGMENT_HEADER_SIZE).is_multiple_of(FREE_CHUNK_SIZE) {
+                return Err(Error::InvalidData { structure: STRUCTURE, offset: fragment_fcr.start, reason: "Fragment size is not a whole number of free chunks" });
+            }
+            reader.seek(SeekFrom::Start(fragment_fcr.start))?;
+
+            let crc = reader.read_u32::<LittleEndian>()?;
+            let next = FileChunkReference::from_reader(reader, 64, 32)?;
+
+            // The CRC covers the fcrFreeChunk array
+            let chunks_offset = reader.stream_position()?;
+            let mut chunk_bytes = vec![0; (fragment_fcr.len - FRAGMENT_HEADER_SIZE) as usize];
+            reader.read_exact(&mut chunk_bytes)?;
+            let actual = crc32(&chunk_bytes);
+            if actual != crc {
+                return Err(Error::CrcMismatch { structure: STRUCTURE, offset: fragment_fcr.start, expected: crc, actual });
+            }
+
+            reader.seek(SeekFrom::Start(chunks_offset))?;
+            for _ in 0..chunk_bytes.len() as u64 / FREE_CHUNK_SIZE {
+                free_chunks.push(FileChunkReference::from_reader(reader, 64, 64)?);
+            }
+
+            fragment_fcr = next;
+        }
+
+        free_chunks.sort_by_key(|chunk| chunk.start);
+        Ok(FreeChunkList { free_chunks })
+    }
+
+    // Writes every free chunk into one fragment
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut chunk_bytes = Vec::new();
+        for chunk in &self.free_chunks {
+            chunk.to_writer(&mut chunk_bytes, 64, 64)?;
+        }
+        writer.write_u32::<LittleEndian>(crc32(&chunk_bytes))?;
+        FileChunkReference::nil().to_writer(writer, 64, 32)?;
+        writer.write_all(&chunk_bytes)?;
+        Ok(())
+    }
+
+    // Takes len bytes from the start of the smallest free chunk that can hold them
+    pub fn allocate(&mut self, len: u64) -> Option<FileChunkReference> {
+        let index = self.free_chunks.iter().enumerate()
+            .filter(|(_, chunk)| chunk.len >= len)
+            .min_by_key(|(_, chunk)| chunk.len)
+            .map(|(index, _)| index)?;
+
+        let chunk = &mut self.free_chunks[index];
+        let allocated = FileChunkReference { start: chunk.start, len };
+        chunk.start += len;
+        chunk.len -= len;
+        if chunk.len == 0 {
+            self.free_chunks.remove(index);
+        }
+        Some(allocated)
+    }
+
+    // Total number of free bytes, which should equal cbFreeSpaceInFreeChunkList in the header
+    pub fn total_len(&self) -> u64 {
+        self.free_chunks.iter().map(|chunk| chunk.len).sum()
+    }
+
+    // The smallest free chunk that can hold len bytes
+    pub fn find(&self, len: u64) -> Option<&FileChunkReference> {
+        self.free_chunks.iter()
+            .filter(|chunk| chunk.len >= len)
+            .min_by_key(|chunk| chunk.len)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{Error, Result};
+
+use super::compactid::CompactId;
+use super::exguid::ExGuid;
+use super::filenode::FileNode;
+use super::filenodedata::FileNodeData;
+use super::guid::Guid;
+
+const STRUCTURE: &str = "GlobalIdTable";
+
+// Maps the guid_index of a CompactId to a full GUID. See MS-ONESTORE 2.1.3
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GlobalIdTable {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub entries: HashMap<u32, Guid>,
+    // Only used by the alternative packaging, where each CompactId stands for a whole ExGuid
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub compact_ids: HashMap<CompactId, ExGuid>
+}
+
+impl GlobalIdTable {
+    // nodes[0] must be a GlobalIdTableStart or GlobalIdTableStart2 node. Entry2 and Entry3 nodes copy
+    // entries out of the dependency revision's table. Returns the table and the number of nodes it spans.
+    pub fn from_file_nodes(nodes: &[FileNode], dependency: Option<&GlobalIdTable>) -> Result<(GlobalIdTable, usize)> {
+        let start = nodes.first().ok_or(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "Empty file node sequence" })?;
+        match start.data {
+            FileNodeData::GlobalIdTableStart(_) | FileNodeData::GlobalIdTableStart2 => {},
+            _ => return Err(Error::InvalidData { structure: STRUCTURE, offset: start.offset, reason: "Global id table must begin with a start node" })
+        }
+
+        let mut table = GlobalIdTable::default();
+        let mut guids = HashSet::new();
+
+        for (i, node) in nodes.iter().enumerate().skip(1) {
+            match &node.data {
+                FileNodeData::GlobalIdTableEntry(entry) => {
+                    if entry.guid.is_nil() {
+                        return Err(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table entry has a nil GUID" });
+                    }
+                    table.insert(entry.index, entry.guid, &mut guids, node)?;
+                },
+                FileNodeData::GlobalIdTableEntry2(entry) => {
+                    let guid = lookup_dependency(dependency, entry.index_map_from, node)?;
+                    table.insert(entry.index_map_to, guid, &mut guids, node)?;
+                },
+                FileNodeData::GlobalIdTableEntry3(entry) => {
+                    for i in 0..entry.entries_to_copy {
+                        let from = entry.index_copy_from_start.checked_add(i);
+                        let to = entry.index_copy_to_start.checked_add(i);
+                        let (from, to) = from.zip(to)
+                            .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table copy range overflows" })?;
+                        let guid = lookup_dependency(dependency, from, node)?;
+                        table.insert(to, guid, &mut guids, node)?;
+                    }
+                },
+                FileNodeData::GlobalIdTableEnd => return Ok((table, i + 1)),
+                _ => return Err(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Unexpected file node inside global id table" })
+            }
+        }
+
+        Err(Error::InvalidData { structure: STRUCTURE, offset: start.offset, reason: "Global id table has no end node" })
+    }
+
+    pub fn get(&self, index: u32) -> Option<&Guid> {
+        self.entries.get(&index)
+    }
+
+    // Returns None if a CompactId is paired with two different ExGuids
+    pub fn from_references<I: IntoIterator<Item = (CompactId, ExGuid)>>(references: I) -> Option<GlobalIdTable> {
+        let mut table = GlobalIdTable::default();
+        for (id, exguid) in references {
+            if table.compact_ids.insert(id, exguid).is_some_and(|existing| existing != exguid) {
+                return None;
+            }
+        }
+        Some(table)
+    }
+
+    // See MS-ONESTORE 2.2.2
+    pub fn resolve(&self, id: CompactId) -> Option<ExGuid> {
+        if let Some(exguid) = self.compact_ids.get(&id) {
+            return Some(*exguid);
+        }
+        self.get(id.guid_index).map(|guid| ExGuid { guid: *guid, n: id.n as u32 })
+    }
+
+    fn insert(&mut self, index: u32, guid: Guid, guids: &mut HashSet<Guid>, node: &FileNode) -> Result<()> {
+        if index >= 0xFFFFFF {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table index is out of range" });
+        }
+        if !guids.insert(guid) || self.entries.insert(index, guid).is_some() {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table entries must be unique" });
+        }
+        Ok(())
+    }
+}
+
+fn lookup_dependency(dependency: Option<&GlobalIdTable>, index: u32, node: &FileNode) -> Result<Guid> {
+    let dependency = dependency
+        .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table copies entries but its revision has no dependency" })?;
+    dependency.get(index).copied()
+        .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Global id table copies an index missing from the dependency revision" })
+}
@@ -0,0 +1,44 @@
+use std::io::{Read, Seek};
+
+use crate::error::Result;
+
+use super::filechunkreference::FileChunkReference;
+use super::filenodedata::FileNodeData;
+use super::filenodelist::FileNodeList;
+use super::transactionlog::TransactionLog;
+
+// Property sets stored once and referenced by the MD5 hash of their contents. See MS-ONESTORE 2.3.4
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HashedChunkList {
+    // In file order
+    pub chunks: Vec<HashedChunk>
+}
+
+// A HashedChunkDescriptor2FND: an ObjectSpaceObjectPropSet and the MD5 hash of its bytes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HashedChunk {
+    pub hash: [u8; 16],
+    pub blob: FileChunkReference
+}
+
+impl HashedChunkList {
+    // fcr points at the file node list of HashedChunkDescriptor2FND nodes
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<HashedChunkList> {
+        let list = FileNodeList::from_reader(fcr, reader, transaction_log)?;
+
+        let chunks = list.file_nodes.iter()
+            .filter_map(|node| match &node.data {
+                FileNodeData::HashedChunkDescriptor2(descriptor) => Some(HashedChunk { hash: descriptor.guid_hash, blob: node.file_chunk_ref }),
+                _ => None
+            })
+            .collect();
+
+        Ok(HashedChunkList { chunks })
+    }
+
+    pub fn get(&self, hash: &[u8; 16]) -> Option<&HashedChunk> {
+        self.chunks.iter().find(|chunk| chunk.hash == *hash)
+    }
+}
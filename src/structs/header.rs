@@ -1,37 +1,68 @@
-use std::io::Error;
-use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
+use std::io::Write;
 
 use byteorder::LittleEndian;
 use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
 
+use crate::error::{Error, Result};
+use crate::structs::crc::crc32;
 use crate::structs::guid::*;
 use crate::structs::filechunkreference::*;
 
 use super::FromFileChunk;
 
-const FILE_TYPE_ONE: Guid = guid!("7B5C52E4-D88C-4DA7-AEB1-5378D02996D3");
-const FILE_TYPE_ONETOC2: Guid = guid!("43FF2FA1-EFD9-4C76-9EE2-10EA5722765F");
+pub(crate) const FILE_TYPE_ONE: Guid = guid!("7B5C52E4-D88C-4DA7-AEB1-5378D02996D3");
+pub(crate) const FILE_TYPE_ONETOC2: Guid = guid!("43FF2FA1-EFD9-4C76-9EE2-10EA5722765F");
 
 const VALID_FILE_FORMAT: Guid = guid!("109ADD3F-911B-49F5-A5D0-1791EDC8AED8");
 
 const CODE_VERSION_ONE: u32 = 0x2A;
 const CODE_VERSION_ONETOC2: u32 = 0x1B;
 
+const CRC_NAME_OFFSET: u64 = 144;
+const EXPECTED_FILE_LENGTH_OFFSET: u64 = 196;
+const RESERVED_SIZE: usize = 728;
+
+pub const HEADER_SIZE: u64 = 1024;
+
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OneNoteFileType {
     One,
     OneToc2
 }
 
-#[derive(Debug)]
+// Every field of the 1024 byte header, in file order. See MS-ONESTORE 2.3.1
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OneNoteFileHeader {
     pub file_type: OneNoteFileType,
     pub file_guid: Guid,
+    pub legacy_file_version: Guid,
+    pub file_format: Guid,
+    // File format versions of the applications that have used the file (ffv___CodeThat____ToThisFile)
+    pub last_code_that_wrote_to_this_file: u32,
+    pub oldest_code_that_has_written_to_this_file: u32,
+    pub newest_code_that_has_written_to_this_file: u32,
+    pub oldest_code_that_may_read_this_file: u32,
+    pub legacy_free_chunk_list: FileChunkReference,
+    pub legacy_transaction_log: FileChunkReference,
     pub transactions_in_log: u32,
+    pub legacy_expected_file_length: u32,
+    pub placeholder: u64,
+    pub legacy_file_node_list_root: FileChunkReference,
+    // The legacy free space count and the four flags after it must be ignored, but are kept for inspection
+    pub legacy_free_space_in_free_chunk_list: u32,
+    pub needs_defrag: bool,
+    pub repaired_file: bool,
+    pub needs_garbage_collect: bool,
+    pub has_no_embedded_file_objects: bool,
     pub ancestor_guid: Guid,
+    // CRC of the file's name when it was last written, see verify_name
+    pub crc_name: u32,
     pub hashed_chunk_list: FileChunkReference,
     pub transaction_log: FileChunkReference,
     pub file_node_list_root: FileChunkReference,
@@ -40,89 +71,112 @@ pub struct OneNoteFileHeader {
     pub free_space_in_free_chunk_list: u64,
     pub file_version: Guid,
     pub file_version_generation: u64,
+    pub deny_read_file_version: Guid,
+    pub debug_log_flags: u32,
+    pub debug_log: FileChunkReference,
+    pub alloc_verification_free_chunk_list: FileChunkReference,
+    // Build numbers of the applications that have written the file (bnCreated, bnLastWroteToThisFile, ...)
+    pub created_build_number: u32,
+    pub last_written_build_number: u32,
+    pub oldest_written_build_number: u32,
+    pub newest_written_build_number: u32,
+    // Must be zero; only written to JSON when it isn't
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "crate::serialize::is_zeroed"))]
+    pub reserved: Vec<u8>
 }
 
 impl FromFileChunk for OneNoteFileHeader {
-    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<OneNoteFileHeader, Error> {
+    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<OneNoteFileHeader> {
         reader.seek(SeekFrom::Start(fcr.start))?;
 
         // Determine file type from GUID
+        let mut offset = reader.stream_position()?;
         let file_type_guid = Guid::from_reader(reader)?;
-        let file_type: OneNoteFileType;
-        match file_type_guid {
-            FILE_TYPE_ONE => file_type = OneNoteFileType::One,
-            FILE_TYPE_ONETOC2 => file_type = OneNoteFileType::OneToc2,
-            _ => return Err(Error::new(ErrorKind::InvalidData, "File type GUID is not ONE or ONETOC2"))
-        }
+        let file_type = match file_type_guid {
+            FILE_TYPE_ONE => OneNoteFileType::One,
+            FILE_TYPE_ONETOC2 => OneNoteFileType::OneToc2,
+            _ => return Err(Error::BadGuid { structure: "OneNoteFileHeader", offset, found: file_type_guid })
+        };
 
         let file_guid = Guid::from_reader(reader)?;
 
         // Verify legacy file version and file format GUIDs
-        {
-            let legacy_file_version_guid = Guid::from_reader(reader)?;
-            if !legacy_file_version_guid.is_nil() {
-                return Err(Error::new(ErrorKind::InvalidData, "Legacy file version GUID must be zero"))
-            }
- 
-            let file_format = Guid::from_reader(reader)?;
-            if file_format != VALID_FILE_FORMAT {
-                return Err(Error::new(ErrorKind::InvalidData, "Invalid file format GUID"))
-            }
+        offset = reader.stream_position()?;
+        let legacy_file_version = Guid::from_reader(reader)?;
+        if !legacy_file_version.is_nil() {
+            return Err(Error::BadGuid { structure: "OneNoteFileHeader", offset, found: legacy_file_version })
+        }
+
+        offset = reader.stream_position()?;
+        let file_format = Guid::from_reader(reader)?;
+        if file_format != VALID_FILE_FORMAT {
+            return Err(Error::BadGuid { structure: "OneNoteFileHeader", offset, found: file_format })
         }
 
         // Verify ffv___CodeThat____ToThisFile
-        for _ in 0..4 {
-            let code_number = reader.read_u32::<LittleEndian>()?;
-            match (&file_type, code_number) {
+        let mut code_versions = [0; 4];
+        for code_version in &mut code_versions {
+            offset = reader.stream_position()?;
+            *code_version = reader.read_u32::<LittleEndian>()?;
+            match (&file_type, *code_version) {
                 (OneNoteFileType::One, CODE_VERSION_ONE) => {},
                 (OneNoteFileType::OneToc2, CODE_VERSION_ONETOC2) => {},
-                _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid code version"))
+                _ => return Err(Error::Unsupported { structure: "OneNoteFileHeader", offset, feature: "file format code version" })
             }
         }
+        let [last_code_that_wrote_to_this_file, oldest_code_that_has_written_to_this_file, newest_code_that_has_written_to_this_file, oldest_code_that_may_read_this_file] = code_versions;
 
-        {
+        let (legacy_free_chunk_list, legacy_transaction_log) = {
+            offset = reader.stream_position()?;
             let legacy_free_chunk_list = FileChunkReference::from_reader(reader, 32, 32)?;
             if !legacy_free_chunk_list.is_zero() {
-                return Err(Error::new(ErrorKind::InvalidData, "Legacy free chunk list must equal 0"));
+                return Err(invalid_header(offset, "Legacy free chunk list must equal 0"));
             }
 
+            offset = reader.stream_position()?;
             let legacy_transaction_log = FileChunkReference::from_reader(reader, 32, 32)?;
             if !legacy_transaction_log.is_nil() {
-                return Err(Error::new(ErrorKind::InvalidData, "Legacy transaction log must equal nil"));
+                return Err(invalid_header(offset, "Legacy transaction log must equal nil"));
             }
-        }
+            (legacy_free_chunk_list, legacy_transaction_log)
+        };
 
+        offset = reader.stream_position()?;
         let transactions_in_log = reader.read_u32::<LittleEndian>()?;
         if transactions_in_log == 0 {
-            return Err(Error::new(ErrorKind::InvalidData, "Zero transactions in transaction log"));
+            return Err(invalid_header(offset, "Zero transactions in transaction log"));
         }
 
-        {
+        let (legacy_expected_file_length, placeholder, legacy_file_node_list_root) = {
+            offset = reader.stream_position()?;
             let legacy_expected_file_length = reader.read_u32::<LittleEndian>()?;
             if legacy_expected_file_length != 0 {
-                return Err(Error::new(ErrorKind::InvalidData, "Legacy expected file length must equal 0"));
+                return Err(invalid_header(offset, "Legacy expected file length must equal 0"));
             }
 
+            offset = reader.stream_position()?;
             let placeholder = reader.read_u64::<LittleEndian>()?;
             if placeholder != 0 {
-                return Err(Error::new(ErrorKind::InvalidData, "Placeholder 1 must equal 0"));
+                return Err(invalid_header(offset, "Placeholder 1 must equal 0"));
             }
 
+            offset = reader.stream_position()?;
             let legacy_file_node_list_root = FileChunkReference::from_reader(reader, 32, 32)?;
             if !legacy_file_node_list_root.is_nil() {
-                return Err(Error::new(ErrorKind::InvalidData, "Legacy transaction log must equal nil"));
+                return Err(invalid_header(offset, "Legacy file node list root must equal nil"));
             }
-            
-            // Next 8 bytes must be ignored
-            let _ = reader.read_u64::<LittleEndian>()?;
-        }
+            (legacy_expected_file_length, placeholder, legacy_file_node_list_root)
+        };
 
-        let ancestor_guid = Guid::from_reader(reader)?;
+        // Must be ignored, so they aren't validated
+        let legacy_free_space_in_free_chunk_list = reader.read_u32::<LittleEndian>()?;
+        let needs_defrag = reader.read_u8()? != 0;
+        let repaired_file = reader.read_u8()? != 0;
+        let needs_garbage_collect = reader.read_u8()? != 0;
+        let has_no_embedded_file_objects = reader.read_u8()? != 0;
 
-        {
-            // crcName field... do we have to check this?
-            let _ = reader.read_u32::<LittleEndian>()?;
-        }
+        let ancestor_guid = Guid::from_reader(reader)?;
+        let crc_name = reader.read_u32::<LittleEndian>()?;
 
         let hashed_chunk_list = FileChunkReference::from_reader(reader, 64, 32)?;
         let transaction_log = FileChunkReference::from_reader(reader, 64, 32)?;
@@ -132,22 +186,187 @@ impl FromFileChunk for OneNoteFileHeader {
         let free_space_in_free_chunk_list = reader.read_u64::<LittleEndian>()?;
         let file_version = Guid::from_reader(reader)?;
         let file_version_generation = reader.read_u64::<LittleEndian>()?;
+        let deny_read_file_version = Guid::from_reader(reader)?;
+        let debug_log_flags = reader.read_u32::<LittleEndian>()?;
+        let debug_log = FileChunkReference::from_reader(reader, 64, 32)?;
+        let alloc_verification_free_chunk_list = FileChunkReference::from_reader(reader, 64, 32)?;
+        let created_build_number = reader.read_u32::<LittleEndian>()?;
+        let last_written_build_number = reader.read_u32::<LittleEndian>()?;
+        let oldest_written_build_number = reader.read_u32::<LittleEndian>()?;
+        let newest_written_build_number = reader.read_u32::<LittleEndian>()?;
 
-        // Ignore rest of header fields.
+        let mut reserved = vec![0; RESERVED_SIZE];
+        reader.read_exact(&mut reserved)?;
 
-        Ok(OneNoteFileHeader { 
-            file_type, 
-            file_guid, 
-            transactions_in_log, 
-            ancestor_guid, 
+        Ok(OneNoteFileHeader {
+            file_type,
+            file_guid,
+            legacy_file_version,
+            file_format,
+            last_code_that_wrote_to_this_file,
+            oldest_code_that_has_written_to_this_file,
+            newest_code_that_has_written_to_this_file,
+            oldest_code_that_may_read_this_file,
+            legacy_free_chunk_list,
+            legacy_transaction_log,
+            transactions_in_log,
+            legacy_expected_file_length,
+            placeholder,
+            legacy_file_node_list_root,
+            legacy_free_space_in_free_chunk_list,
+            needs_defrag,
+            repaired_file,
+            needs_garbage_collect,
+            has_no_embedded_file_objects,
+            ancestor_guid,
+            crc_name,
             hashed_chunk_list,
-            transaction_log, 
-            file_node_list_root, 
-            free_chunk_list, 
-            expected_file_length, 
-            free_space_in_free_chunk_list, 
-            file_version, 
-            file_version_generation 
+            transaction_log,
+            file_node_list_root,
+            free_chunk_list,
+            expected_file_length,
+            free_space_in_free_chunk_list,
+            file_version,
+            file_version_generation,
+            deny_read_file_version,
+            debug_log_flags,
+            debug_log,
+            alloc_verification_free_chunk_list,
+            created_build_number,
+            last_written_build_number,
+            oldest_written_build_number,
+            newest_written_build_number,
+            reserved
         })
     }
-}
\ No newline at end of file
+}
+
+impl OneNoteFileHeader {
+    // Writes all 1024 bytes, in the same order from_reader reads them
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let file_type = match self.file_type {
+            OneNoteFileType::One => FILE_TYPE_ONE,
+            OneNoteFileType::OneToc2 => FILE_TYPE_ONETOC2
+        };
+        for guid in [file_type, self.file_guid, self.legacy_file_version, self.file_format] {
+            writer.write_all(&guid.to_bytes_le())?;
+        }
+        writer.write_u32::<LittleEndian>(self.last_code_that_wrote_to_this_file)?;
+        writer.write_u32::<LittleEndian>(self.oldest_code_that_has_written_to_this_file)?;
+        writer.write_u32::<LittleEndian>(self.newest_code_that_has_written_to_this_file)?;
+        writer.write_u32::<LittleEndian>(self.oldest_code_that_may_read_this_file)?;
+        self.legacy_free_chunk_list.to_writer(writer, 32, 32)?;
+        self.legacy_transaction_log.to_writer(writer, 32, 32)?;
+        writer.write_u32::<LittleEndian>(self.transactions_in_log)?;
+        writer.write_u32::<LittleEndian>(self.legacy_expected_file_length)?;
+        writer.write_u64::<LittleEndian>(self.placeholder)?;
+        self.legacy_file_node_list_root.to_writer(writer, 32, 32)?;
+        writer.write_u32::<LittleEndian>(self.legacy_free_space_in_free_chunk_list)?;
+        writer.write_u8(self.needs_defrag.into())?;
+        writer.write_u8(self.repaired_file.into())?;
+        writer.write_u8(self.needs_garbage_collect.into())?;
+        writer.write_u8(self.has_no_embedded_file_objects.into())?;
+        writer.write_all(&self.ancestor_guid.to_bytes_le())?;
+        writer.write_u32::<LittleEndian>(self.crc_name)?;
+        self.hashed_chunk_list.to_writer(writer, 64, 32)?;
+        self.transaction_log.to_writer(writer, 64, 32)?;
+        self.file_node_list_root.to_writer(writer, 64, 32)?;
+        self.free_chunk_list.to_writer(writer, 64, 32)?;
+        writer.write_u64::<LittleEndian>(self.expected_file_length)?;
+        writer.write_u64::<LittleEndian>(self.free_space_in_free_chunk_list)?;
+        writer.write_all(&self.file_version.to_bytes_le())?;
+        writer.write_u64::<LittleEndian>(self.file_version_generation)?;
+        writer.write_all(&self.deny_read_file_version.to_bytes_le())?;
+        writer.write_u32::<LittleEndian>(self.debug_log_flags)?;
+        self.debug_log.to_writer(writer, 64, 32)?;
+        self.alloc_verification_free_chunk_list.to_writer(writer, 64, 32)?;
+        writer.write_u32::<LittleEndian>(self.created_build_number)?;
+        writer.write_u32::<LittleEndian>(self.last_written_build_number)?;
+        writer.write_u32::<LittleEndian>(self.oldest_written_build_number)?;
+        writer.write_u32::<LittleEndian>(self.newest_written_build_number)?;
+
+        let mut reserved = self.reserved.clone();
+        reserved.resize(RESERVED_SIZE, 0);
+        writer.write_all(&reserved)?;
+        Ok(())
+    }
+
+    // A packaged file's prefix only has the first four fields. The rest are zero or nil, as if the
+    // store had no native structures, except the expected length which is the whole stream.
+    pub(crate) fn packaged(file_type: OneNoteFileType, file_guid: Guid, legacy_file_version: Guid, file_format: Guid, file_length: u64) -> OneNoteFileHeader {
+        OneNoteFileHeader {
+            file_type,
+            file_guid,
+            legacy_file_version,
+            file_format,
+            last_code_that_wrote_to_this_file: 0,
+            oldest_code_that_has_written_to_this_file: 0,
+            newest_code_that_has_written_to_this_file: 0,
+            oldest_code_that_may_read_this_file: 0,
+            legacy_free_chunk_list: FileChunkReference::zero(),
+            legacy_transaction_log: FileChunkReference::nil(),
+            transactions_in_log: 0,
+            legacy_expected_file_length: 0,
+            placeholder: 0,
+            legacy_file_node_list_root: FileChunkReference::nil(),
+            legacy_free_space_in_free_chunk_list: 0,
+            needs_defrag: false,
+            repaired_file: false,
+            needs_garbage_collect: false,
+            has_no_embedded_file_objects: false,
+            ancestor_guid: Guid::nil(),
+            crc_name: 0,
+            hashed_chunk_list: FileChunkReference::zero(),
+            transaction_log: FileChunkReference::nil(),
+            file_node_list_root: FileChunkReference::nil(),
+            free_chunk_list: FileChunkReference::zero(),
+            expected_file_length: file_length,
+            free_space_in_free_chunk_list: 0,
+            file_version: Guid::nil(),
+            file_version_generation: 0,
+            deny_read_file_version: Guid::nil(),
+            debug_log_flags: 0,
+            debug_log: FileChunkReference::zero(),
+            alloc_verification_free_chunk_list: FileChunkReference::zero(),
+            created_build_number: 0,
+            last_written_build_number: 0,
+            oldest_written_build_number: 0,
+            newest_written_build_number: 0,
+            reserved: vec![0; RESERVED_SIZE]
+        }
+    }
+
+    // Checks crcName against the name the file is stored under, without any directories. OneNote
+    // updates crcName when it writes a file, so a mismatch means the file was renamed or copied
+    // since then.
+    pub fn verify_name(&self, file_name: &str) -> Result<()> {
+        let actual = name_crc(file_name);
+        if actual != self.crc_name {
+            return Err(Error::CrcMismatch { structure: "OneNoteFileHeader", offset: CRC_NAME_OFFSET, expected: self.crc_name, actual });
+        }
+        Ok(())
+    }
+
+    // A file shorter than cbExpectedFileLength has been truncated. Longer files are accepted since
+    // nothing the header references lies past the expected end.
+    pub fn check_file_length(&self, file_length: u64) -> Result<()> {
+        if file_length < self.expected_file_length {
+            let fcr = FileChunkReference { start: 0, len: self.expected_file_length };
+            return Err(Error::OutOfBounds { structure: "OneNoteFileHeader", offset: EXPECTED_FILE_LENGTH_OFFSET, fcr });
+        }
+        Ok(())
+    }
+}
+
+// CRC of the name as a null terminated UTF-16 string
+pub fn name_crc(file_name: &str) -> u32 {
+    let bytes: Vec<u8> = file_name.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    crc32(&bytes)
+}
+
+fn invalid_header(offset: u64, reason: &'static str) -> Error {
+    Error::InvalidData { structure: "OneNoteFileHeader", offset, reason }
+}
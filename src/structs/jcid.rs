@@ -0,0 +1,38 @@
+use std::io::{Read, Error};
+
+use byteorder::{ReadBytesExt, LittleEndian};
+
+// See MS-ONESTORE 2.6.14
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Jcid(pub u32);
+
+impl Jcid {
+    pub fn from_reader<T: Read>(reader: &mut T) -> Result<Jcid, Error> {
+        Ok(Jcid(reader.read_u32::<LittleEndian>()?))
+    }
+
+    pub fn index(&self) -> u16 {
+        (self.0 & 0xFFFF) as u16
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.0 & (1 << 16) != 0
+    }
+
+    pub fn is_property_set(&self) -> bool {
+        self.0 & (1 << 17) != 0
+    }
+
+    pub fn is_graph_node(&self) -> bool {
+        self.0 & (1 << 18) != 0
+    }
+
+    pub fn is_file_data(&self) -> bool {
+        self.0 & (1 << 19) != 0
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.0 & (1 << 20) != 0
+    }
+}
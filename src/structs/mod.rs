@@ -1,24 +1,49 @@
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, SeekFrom};
 
+use crate::error::{Error, Result};
+
+pub mod crc;
 pub mod guid;
 pub mod exguid;
+pub mod compactid;
+pub mod jcid;
+pub mod globalidtable;
 pub mod header;
 pub mod filechunkreference;
 pub mod filenodelist;
 pub mod filenode;
+pub mod filenodedata;
+pub mod filedatastore;
+pub mod freechunklist;
+pub mod hashedchunklist;
+pub mod objectpropset;
+pub mod objectspace;
+pub mod objecttable;
+pub mod propertyset;
+pub mod revision;
 pub mod transactionlog;
 
 use filechunkreference::FileChunkReference;
 
-use self::filenode::{FileType, FileNode};
-
 // TODO: Are these traits useless after the refactoring?
 pub trait FromFileChunk {
-    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<Self, std::io::Error> where Self: Sized;
+    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<Self> where Self: Sized;
 }
 
 pub trait ListFromFileChunk {
-    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, len: u64) -> Result<Self, std::io::Error> where Self: Sized;
+    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, len: u64) -> Result<Self> where Self: Sized;
+}
+
+// Make sure a chunk lies entirely within the stream before we try to read it
+pub fn check_fcr_bounds<T: Seek>(fcr: &FileChunkReference, reader: &mut T, structure: &'static str) -> Result<()> {
+    let position = reader.stream_position()?;
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+
+    match fcr.start.checked_add(fcr.len) {
+        Some(end) if end <= stream_len => Ok(()),
+        _ => Err(Error::OutOfBounds { structure, offset: position, fcr: *fcr })
+    }
 }
 
 // Implement this trait for both file node and file node list so we can Rc<> them inside the file node list object as next, child
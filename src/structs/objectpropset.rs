@@ -0,0 +1,130 @@
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::check_fcr_bounds;
+use super::compactid::CompactId;
+use super::filechunkreference::FileChunkReference;
+use super::propertyset::{PropertySet, ReferenceStreams};
+
+const STRUCTURE: &str = "ObjectSpaceObjectPropSet";
+
+// See MS-ONESTORE 2.6.1
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectSpaceObjectPropSet {
+    pub oids: Vec<CompactId>,
+    pub osids: Vec<CompactId>,
+    pub context_ids: Vec<CompactId>,
+    pub body: PropertySet
+}
+
+// See MS-ONESTORE 2.6.5
+struct StreamHeader {
+    count: u32,
+    extended_streams_present: bool,
+    osid_stream_not_present: bool
+}
+
+impl StreamHeader {
+    fn from_reader<T: Read>(reader: &mut T) -> Result<StreamHeader> {
+        let value = reader.read_u32::<LittleEndian>()?;
+
+        Ok(StreamHeader {
+            count: value & 0xFFFFFF,
+            extended_streams_present: value & (1 << 30) != 0,
+            osid_stream_not_present: value & (1 << 31) != 0
+        })
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.count > 0xFFFFFF {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "Stream has more than 2^24 - 1 ids" });
+        }
+        writer.write_u32::<LittleEndian>(self.count | (self.extended_streams_present as u32) << 30 | (self.osid_stream_not_present as u32) << 31)?;
+        Ok(())
+    }
+}
+
+impl ObjectSpaceObjectPropSet {
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T) -> Result<ObjectSpaceObjectPropSet> {
+        check_fcr_bounds(fcr, reader, STRUCTURE)?;
+        reader.seek(SeekFrom::Start(fcr.start))?;
+
+        // Parse from a copy of the chunk so nothing can be read from outside of it
+        let mut data = Vec::new();
+        reader.take(fcr.len).read_to_end(&mut data)?;
+        let mut cursor = Cursor::new(data);
+
+        ObjectSpaceObjectPropSet::parse(&mut cursor).map_err(|e| match e {
+            Error::InvalidData { structure, offset, reason } => Error::InvalidData { structure, offset: fcr.start + offset, reason },
+            Error::Unsupported { structure, offset, feature } => Error::Unsupported { structure, offset: fcr.start + offset, feature },
+            Error::Io(_) => Error::InvalidData { structure: STRUCTURE, offset: fcr.start, reason: "Property set is larger than its chunk" },
+            e => e
+        })
+    }
+
+    // Writes the streams and property set, padded to a multiple of 8 bytes. The OSIDs stream is
+    // left out when it and the ContextIDs stream are both empty.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut bytes = Vec::new();
+        let extended_streams_present = !self.context_ids.is_empty();
+        let osid_stream_not_present = self.osids.is_empty() && !extended_streams_present;
+
+        StreamHeader { count: self.oids.len() as u32, extended_streams_present, osid_stream_not_present }.to_writer(&mut bytes)?;
+        write_compact_ids(&mut bytes, &self.oids)?;
+        if !osid_stream_not_present {
+            StreamHeader { count: self.osids.len() as u32, extended_streams_present, osid_stream_not_present: false }.to_writer(&mut bytes)?;
+            write_compact_ids(&mut bytes, &self.osids)?;
+
+            if extended_streams_present {
+                StreamHeader { count: self.context_ids.len() as u32, extended_streams_present: false, osid_stream_not_present: false }.to_writer(&mut bytes)?;
+                write_compact_ids(&mut bytes, &self.context_ids)?;
+            }
+        }
+        self.body.to_writer(&mut bytes)?;
+
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    fn parse<T: Read + Seek>(reader: &mut T) -> Result<ObjectSpaceObjectPropSet> {
+        let oids_header = StreamHeader::from_reader(reader)?;
+        let oids = read_compact_ids(reader, oids_header.count)?;
+
+        let mut osids = Vec::new();
+        let mut context_ids = Vec::new();
+        if !oids_header.osid_stream_not_present {
+            let osids_header = StreamHeader::from_reader(reader)?;
+            osids = read_compact_ids(reader, osids_header.count)?;
+
+            if osids_header.extended_streams_present {
+                let context_ids_header = StreamHeader::from_reader(reader)?;
+                context_ids = read_compact_ids(reader, context_ids_header.count)?;
+            }
+        }
+
+        let mut streams = ReferenceStreams { oids: &oids, osids: &osids, context_ids: &context_ids };
+        let body = PropertySet::from_reader(reader, &mut streams)?;
+
+        Ok(ObjectSpaceObjectPropSet { oids, osids, context_ids, body })
+    }
+}
+
+fn read_compact_ids<T: Read>(reader: &mut T, count: u32) -> Result<Vec<CompactId>> {
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        ids.push(CompactId::from_reader(reader)?);
+    }
+    Ok(ids)
+}
+
+fn write_compact_ids<W: Write>(writer: &mut W, ids: &[CompactId]) -> Result<()> {
+    for id in ids {
+        id.to_writer(writer)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::error::{Error, Result};
+
+use super::exguid::ExGuid;
+use super::filechunkreference::FileChunkReference;
+use super::filenode::FileNode;
+use super::filenodedata::FileNodeData;
+use super::filenodelist::FileNodeList;
+use super::objecttable::ObjectTable;
+use super::revision::*;
+use super::transactionlog::TransactionLog;
+
+// See MS-ONESTORE 2.1.4
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectSpace {
+    pub gosid: ExGuid,
+    // In the order they appear in the revision manifest list
+    pub revisions: Vec<Revision>,
+    // Maps (context, revision role) to the revision currently holding that role
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::revision_roles"))]
+    pub revision_roles: HashMap<(ExGuid, u32), ExGuid>,
+    pub is_encrypted: bool
+}
+
+impl ObjectSpace {
+    // fcr points to the object space manifest list. See MS-ONESTORE 2.1.6
+    pub fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, transaction_log: &TransactionLog) -> Result<ObjectSpace> {
+        let manifest_list = FileNodeList::from_reader(fcr, reader, transaction_log)?;
+
+        let gosid = match manifest_list.file_nodes.first().map(|node| &node.data) {
+            Some(FileNodeData::ObjectSpaceManifestListStart(start)) => start.gosid,
+            _ => return Err(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: fcr.start, reason: "List doesn't begin with ObjectSpaceManifestListStart" })
+        };
+
+        // The last revision manifest list reference in the object space manifest list is the current one
+        let revision_manifest_list_ref = manifest_list.file_nodes.iter()
+            .rev()
+            .find(|node| matches!(node.data, FileNodeData::RevisionManifestListReference))
+            .ok_or(Error::InvalidData { structure: "ObjectSpaceManifestList", offset: fcr.start, reason: "No revision manifest list reference" })?;
+        let revision_manifest_list = FileNodeList::from_reader(&revision_manifest_list_ref.file_chunk_ref, reader, transaction_log)?;
+
+        let mut object_space = ObjectSpace {
+            gosid,
+            revisions: Vec::new(),
+            revision_roles: HashMap::new(),
+            is_encrypted: false
+        };
+
+        // See MS-ONESTORE 2.1.7
+        let mut revision_start: Option<FileNode> = None;
+        let mut effective_objects = None;
+        let mut revision_nodes: Vec<FileNode> = Vec::new();
+        for node in revision_manifest_list.file_nodes {
+            if let Some(start) = revision_start.take() {
+                if let FileNodeData::RevisionManifestEnd = node.data {
+                    object_space.push_revision(&start, std::mem::take(&mut revision_nodes), &mut effective_objects, reader, transaction_log)?;
+                } else {
+                    revision_nodes.push(node);
+                    revision_start = Some(start);
+                }
+                continue;
+            }
+
+            match &node.data {
+                FileNodeData::RevisionManifestStart4(_) | FileNodeData::RevisionManifestStart6(_) | FileNodeData::RevisionManifestStart7(_) => {
+                    revision_start = Some(node);
+                },
+                FileNodeData::RevisionRoleDeclaration(declaration) => {
+                    object_space.declare_role(declaration.rid, ExGuid::nil(), declaration.revision_role, &node)?;
+                },
+                FileNodeData::RevisionRoleAndContextDeclaration(declaration) => {
+                    object_space.declare_role(declaration.base.rid, declaration.gctxid, declaration.base.revision_role, &node)?;
+                },
+                FileNodeData::ObjectDataEncryptionKeyV2 => object_space.is_encrypted = true,
+                _ => {}
+            }
+        }
+
+        if let Some(start) = revision_start {
+            return Err(Error::InvalidData { structure: "RevisionManifest", offset: start.offset, reason: "Revision manifest has no end node" });
+        }
+
+        Ok(object_space)
+    }
+
+    pub fn revision(&self, rid: &ExGuid) -> Option<&Revision> {
+        self.revisions.iter().rev().find(|revision| revision.rid == *rid)
+    }
+
+    // The revision currently assigned the given role in the given context. See MS-ONESTORE 2.1.8
+    pub fn current_revision(&self, context: &ExGuid, role: u32) -> Option<&Revision> {
+        self.revision_roles.get(&(*context, role)).and_then(|rid| self.revision(rid))
+    }
+
+    // The default content revision of the default context
+    pub fn latest_revision(&self) -> Option<&Revision> {
+        self.current_revision(&ExGuid::nil(), REVISION_ROLE_DEFAULT_CONTENT)
+    }
+
+    // The revision followed by every revision it depends on, newest first
+    pub fn dependency_chain(&self, rid: &ExGuid) -> Vec<&Revision> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = self.revision(rid);
+        while let Some(revision) = next {
+            if !visited.insert(revision.rid) {
+                break;
+            }
+            chain.push(revision);
+            next = if revision.rid_dependent.is_nil() { None } else { self.revision(&revision.rid_dependent) };
+        }
+        chain
+    }
+
+    // Root objects of a revision including those inherited from its dependencies
+    pub fn effective_root_objects(&self, rid: &ExGuid) -> HashMap<u32, ExGuid> {
+        let mut root_objects = HashMap::new();
+        for revision in self.dependency_chain(rid).into_iter().rev() {
+            root_objects.extend(revision.root_objects.iter().map(|(role, oid)| (*role, *oid)));
+        }
+        root_objects
+    }
+
+    // Object groups of a revision including those inherited from its dependencies, oldest first
+    pub fn effective_object_groups(&self, rid: &ExGuid) -> Vec<&ObjectGroup> {
+        self.dependency_chain(rid).into_iter().rev()
+            .flat_map(|revision| revision.object_groups.iter())
+            .collect()
+    }
+
+    // Every object visible in a revision: its own plus everything inherited from its dependencies,
+    // with ref count overrides applied
+    pub fn effective_objects(&self, rid: &ExGuid) -> ObjectTable {
+        let mut objects = ObjectTable::new();
+        for revision in self.dependency_chain(rid).into_iter().rev() {
+            apply_revision(&mut objects, revision);
+        }
+        objects
+    }
+
+    // effective_objects holds the rid and effective object table of the last revision pushed. A
+    // revision usually depends on the one before it, so that table is carried forward rather than
+    // rebuilt from the whole dependency chain.
+    fn push_revision<T: Read + Seek>(&mut self, start: &FileNode, file_nodes: Vec<FileNode>, effective_objects: &mut Option<(ExGuid, ObjectTable)>, reader: &mut T, transaction_log: &TransactionLog) -> Result<()> {
+        let rid_dependent = match &start.data {
+            FileNodeData::RevisionManifestStart4(start) => start.rid_dependent,
+            FileNodeData::RevisionManifestStart6(start) => start.rid_dependent,
+            FileNodeData::RevisionManifestStart7(start) => start.base.rid_dependent,
+            _ => ExGuid::nil()
+        };
+        let dependency = if rid_dependent.is_nil() { None } else { self.revision(&rid_dependent) };
+        let mut objects = match effective_objects.take() {
+            _ if rid_dependent.is_nil() => ObjectTable::new(),
+            Some((rid, objects)) if rid == rid_dependent => objects,
+            _ => self.effective_objects(&rid_dependent)
+        };
+
+        let revision = Revision::from_file_nodes(start, file_nodes, dependency, &objects, reader, transaction_log)?;
+        apply_revision(&mut objects, &revision);
+        *effective_objects = Some((revision.rid, objects));
+        self.revision_roles.insert((revision.context, revision.role), revision.rid);
+        self.revisions.push(revision);
+        Ok(())
+    }
+
+    fn declare_role(&mut self, rid: ExGuid, context: ExGuid, role: u32, node: &FileNode) -> Result<()> {
+        if self.revision(&rid).is_none() {
+            return Err(Error::InvalidData { structure: "RevisionManifestList", offset: node.offset, reason: "Role declared for an unknown revision" });
+        }
+        self.revision_roles.insert((context, role), rid);
+        Ok(())
+    }
+}
+
+// Adds the objects a revision declares to the effective objects of its dependency, then applies its
+// ref count overrides
+fn apply_revision(objects: &mut ObjectTable, revision: &Revision) {
+    objects.extend(revision.objects.iter().map(|(id, object)| (*id, object.clone())));
+    for (id, ref_count) in &revision.ref_count_overrides {
+        if let Some(object) = objects.get_mut(id) {
+            object.ref_count = *ref_count;
+        }
+    }
+}
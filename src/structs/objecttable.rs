@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+use super::compactid::CompactId;
+use super::exguid::ExGuid;
+use super::filechunkreference::FileChunkReference;
+use super::filenode::FileNode;
+use super::filenodedata::*;
+use super::globalidtable::GlobalIdTable;
+use super::jcid::Jcid;
+use super::objectpropset::ObjectSpaceObjectPropSet;
+
+pub type ObjectTable = HashMap<ExGuid, Object>;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ObjectBody {
+    // Location of the object's ObjectSpaceObjectPropSet
+    PropertySet(FileChunkReference),
+    // See MS-ONESTORE 2.5.27. reference is "<file>", "<ifndf>" or "<invfdo>" followed by its target
+    FileData { reference: String, extension: String }
+}
+
+// An object declared in a revision or object group. See MS-ONESTORE 2.1.5
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Object {
+    pub id: ExGuid,
+    pub jcid: Jcid,
+    pub ref_count: u32,
+    pub body: ObjectBody,
+    // Only set for read only objects
+    pub md5_hash: Option<[u8; 16]>,
+    pub has_oid_references: bool,
+    pub has_osid_references: bool,
+    // The table in effect where the object was declared; its property set's CompactIds resolve through it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub global_id_table: Arc<GlobalIdTable>
+}
+
+impl Object {
+    // Returns None if the node doesn't declare an object. ObjectRevisionWithRefCount nodes update the
+    // existing declaration in objects instead.
+    // existing_objects are searched in order for the declaration an ObjectRevisionWithRefCount node revises.
+    pub fn from_file_node(node: &FileNode, global_id_table: &Arc<GlobalIdTable>, existing_objects: &[&ObjectTable]) -> Result<Option<Object>> {
+        let property_set = ObjectBody::PropertySet(node.file_chunk_ref);
+
+        let object = match &node.data {
+            FileNodeData::ObjectDeclarationWithRefCount(declaration) =>
+                Object::from_body_v1(&declaration.body, declaration.c_ref.into(), property_set, global_id_table, node)?,
+            FileNodeData::ObjectDeclarationWithRefCount2(declaration) =>
+                Object::from_body_v1(&declaration.body, declaration.c_ref, property_set, global_id_table, node)?,
+            FileNodeData::ObjectDeclaration2RefCount(declaration) =>
+                Object::from_body_v2(&declaration.body, declaration.c_ref.into(), property_set, None, global_id_table, node)?,
+            FileNodeData::ObjectDeclaration2LargeRefCount(declaration) =>
+                Object::from_body_v2(&declaration.body, declaration.c_ref, property_set, None, global_id_table, node)?,
+            FileNodeData::ReadOnlyObjectDeclaration2RefCount(declaration) =>
+                Object::from_body_v2(&declaration.base.body, declaration.base.c_ref.into(), property_set, Some(declaration.md5_hash), global_id_table, node)?,
+            FileNodeData::ReadOnlyObjectDeclaration2LargeRefCount(declaration) =>
+                Object::from_body_v2(&declaration.base.body, declaration.base.c_ref, property_set, Some(declaration.md5_hash), global_id_table, node)?,
+            FileNodeData::ObjectDeclarationFileData3RefCount(declaration) => Object::from_file_data(
+                declaration.oid, declaration.jcid, declaration.c_ref.into(), &declaration.file_data_reference, &declaration.extension, global_id_table, node)?,
+            FileNodeData::ObjectDeclarationFileData3LargeRefCount(declaration) => Object::from_file_data(
+                declaration.oid, declaration.jcid, declaration.c_ref, &declaration.file_data_reference, &declaration.extension, global_id_table, node)?,
+            FileNodeData::ObjectRevisionWithRefCount(revision) =>
+                Object::from_revision(revision.oid, revision.c_ref.into(), revision.has_oid_references, revision.has_osid_references, global_id_table, existing_objects, node)?,
+            FileNodeData::ObjectRevisionWithRefCount2(revision) =>
+                Object::from_revision(revision.oid, revision.c_ref, revision.has_oid_references, revision.has_osid_references, global_id_table, existing_objects, node)?,
+            _ => return Ok(None)
+        };
+
+        Ok(Some(object))
+    }
+
+    pub fn read_property_set<T: Read + Seek>(&self, reader: &mut T) -> Result<Option<ObjectSpaceObjectPropSet>> {
+        match &self.body {
+            ObjectBody::PropertySet(fcr) => Ok(Some(ObjectSpaceObjectPropSet::from_reader(fcr, reader)?)),
+            ObjectBody::FileData { .. } => Ok(None)
+        }
+    }
+
+    pub fn resolve(&self, id: CompactId) -> Option<ExGuid> {
+        self.global_id_table.resolve(id)
+    }
+
+    fn from_body_v1(body: &ObjectDeclarationWithRefCountBody, ref_count: u32, object_body: ObjectBody, global_id_table: &Arc<GlobalIdTable>, node: &FileNode) -> Result<Object> {
+        Ok(Object {
+            id: resolve_oid(body.oid, global_id_table, node)?,
+            // Only the index is stored; these objects are always property sets. See MS-ONESTORE 2.6.15
+            jcid: Jcid(0x00020000 | body.jci as u32),
+            ref_count,
+            body: object_body,
+            md5_hash: None,
+            has_oid_references: body.has_oid_references,
+            has_osid_references: body.has_osid_references,
+            global_id_table: global_id_table.clone()
+        })
+    }
+
+    fn from_body_v2(body: &ObjectDeclaration2Body, ref_count: u32, object_body: ObjectBody, md5_hash: Option<[u8; 16]>, global_id_table: &Arc<GlobalIdTable>, node: &FileNode) -> Result<Object> {
+        Ok(Object {
+            id: resolve_oid(body.oid, global_id_table, node)?,
+            jcid: body.jcid,
+            ref_count,
+            body: object_body,
+            md5_hash,
+            has_oid_references: body.has_oid_references,
+            has_osid_references: body.has_osid_references,
+            global_id_table: global_id_table.clone()
+        })
+    }
+
+    fn from_file_data(oid: CompactId, jcid: Jcid, ref_count: u32, reference: &str, extension: &str, global_id_table: &Arc<GlobalIdTable>, node: &FileNode) -> Result<Object> {
+        Ok(Object {
+            id: resolve_oid(oid, global_id_table, node)?,
+            jcid,
+            ref_count,
+            body: ObjectBody::FileData { reference: reference.to_string(), extension: extension.to_string() },
+            md5_hash: None,
+            has_oid_references: false,
+            has_osid_references: false,
+            global_id_table: global_id_table.clone()
+        })
+    }
+
+    fn from_revision(oid: CompactId, ref_count: u32, has_oid_references: bool, has_osid_references: bool, global_id_table: &Arc<GlobalIdTable>, existing_objects: &[&ObjectTable], node: &FileNode) -> Result<Object> {
+        let id = resolve_oid(oid, global_id_table, node)?;
+        let declaration = existing_objects.iter().find_map(|objects| objects.get(&id))
+            .ok_or(Error::InvalidData { structure: "ObjectRevisionWithRefCountFNDX", offset: node.offset, reason: "Revised object was never declared" })?;
+
+        Ok(Object {
+            id,
+            jcid: declaration.jcid,
+            ref_count,
+            body: ObjectBody::PropertySet(node.file_chunk_ref),
+            md5_hash: None,
+            has_oid_references,
+            has_osid_references,
+            global_id_table: global_id_table.clone()
+        })
+    }
+}
+
+fn resolve_oid(oid: CompactId, global_id_table: &GlobalIdTable, node: &FileNode) -> Result<ExGuid> {
+    global_id_table.resolve(oid)
+        .ok_or(Error::InvalidData { structure: "ObjectDeclaration", offset: node.offset, reason: "Object id is not in the global id table" })
+}
@@ -0,0 +1,265 @@
+use std::io::{Read, Seek, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::error::{Error, Result};
+
+use super::compactid::CompactId;
+
+const STRUCTURE: &str = "PropertySet";
+
+// Nested property sets deeper than this are treated as corrupt rather than risking the stack
+const MAX_PROPERTY_SET_DEPTH: u32 = 64;
+
+// See MS-ONESTORE 2.6.6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertyId(pub u32);
+
+// See MS-ONESTORE 2.6.6
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PropertyType {
+    NoData = 0x1,
+    Bool = 0x2,
+    OneByteOfData = 0x3,
+    TwoBytesOfData = 0x4,
+    FourBytesOfData = 0x5,
+    EightBytesOfData = 0x6,
+    FourBytesOfLengthFollowedByData = 0x7,
+    ObjectId = 0x8,
+    ArrayOfObjectIds = 0x9,
+    ObjectSpaceId = 0xA,
+    ArrayOfObjectSpaceIds = 0xB,
+    ContextId = 0xC,
+    ArrayOfContextIds = 0xD,
+    ArrayOfPropertyValues = 0x10,
+    PropertySet = 0x11
+}
+
+// Object, object space and context references hold the CompactId taken from the matching stream of
+// the ObjectSpaceObjectPropSet; they still need to be resolved with a global id table.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PropertyValue {
+    NoData,
+    Bool(bool),
+    OneByte(u8),
+    TwoBytes(u16),
+    FourBytes(u32),
+    EightBytes(u64),
+    Bytes(Vec<u8>),
+    ObjectId(CompactId),
+    ObjectIds(Vec<CompactId>),
+    ObjectSpaceId(CompactId),
+    ObjectSpaceIds(Vec<CompactId>),
+    ContextId(CompactId),
+    ContextIds(Vec<CompactId>),
+    PropertyValues(PropertyId, Vec<PropertySet>),
+    PropertySet(PropertySet)
+}
+
+// See MS-ONESTORE 2.6.7
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertySet {
+    pub properties: Vec<(PropertyId, PropertyValue)>
+}
+
+// The OID, OSID and ContextID streams that reference values are pulled from, in order
+pub struct ReferenceStreams<'a> {
+    pub oids: &'a [CompactId],
+    pub osids: &'a [CompactId],
+    pub context_ids: &'a [CompactId]
+}
+
+impl PropertyId {
+    pub fn id(&self) -> u32 {
+        self.0 & 0x3FFFFFF
+    }
+
+    pub fn property_type(&self) -> Option<PropertyType> {
+        match (self.0 >> 26) & 0x1F {
+            0x1 => Some(PropertyType::NoData),
+            0x2 => Some(PropertyType::Bool),
+            0x3 => Some(PropertyType::OneByteOfData),
+            0x4 => Some(PropertyType::TwoBytesOfData),
+            0x5 => Some(PropertyType::FourBytesOfData),
+            0x6 => Some(PropertyType::EightBytesOfData),
+            0x7 => Some(PropertyType::FourBytesOfLengthFollowedByData),
+            0x8 => Some(PropertyType::ObjectId),
+            0x9 => Some(PropertyType::ArrayOfObjectIds),
+            0xA => Some(PropertyType::ObjectSpaceId),
+            0xB => Some(PropertyType::ArrayOfObjectSpaceIds),
+            0xC => Some(PropertyType::ContextId),
+            0xD => Some(PropertyType::ArrayOfContextIds),
+            0x10 => Some(PropertyType::ArrayOfPropertyValues),
+            0x11 => Some(PropertyType::PropertySet),
+            _ => None
+        }
+    }
+
+    pub fn bool_value(&self) -> bool {
+        self.0 & 0x80000000 != 0
+    }
+
+    // Two property ids name the same property if they only differ in boolValue
+    pub fn matches(&self, prid: u32) -> bool {
+        self.0 & 0x7FFFFFFF == prid & 0x7FFFFFFF
+    }
+}
+
+impl PropertySet {
+    pub fn from_reader<T: Read + Seek>(reader: &mut T, streams: &mut ReferenceStreams) -> Result<PropertySet> {
+        PropertySet::from_reader_with_depth(reader, streams, 0)
+    }
+
+    fn from_reader_with_depth<T: Read + Seek>(reader: &mut T, streams: &mut ReferenceStreams, depth: u32) -> Result<PropertySet> {
+        let offset = reader.stream_position()?;
+        if depth > MAX_PROPERTY_SET_DEPTH {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Property sets are nested too deeply" });
+        }
+
+        let property_count = reader.read_u16::<LittleEndian>()?;
+        let mut prids = Vec::new();
+        for _ in 0..property_count {
+            prids.push(PropertyId(reader.read_u32::<LittleEndian>()?));
+        }
+
+        let mut properties = Vec::new();
+        for prid in prids {
+            let value = PropertyValue::from_reader(prid, reader, streams, depth)?;
+            properties.push((prid, value));
+        }
+
+        Ok(PropertySet { properties })
+    }
+
+    // Writes the property ids and values. Reference values only write their counts; the ids
+    // themselves belong in the ObjectSpaceObjectPropSet's streams.
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.properties.len() > u16::MAX.into() {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: 0, reason: "Property set has more than 65535 properties" });
+        }
+
+        writer.write_u16::<LittleEndian>(self.properties.len() as u16)?;
+        for (prid, _) in &self.properties {
+            writer.write_u32::<LittleEndian>(prid.0)?;
+        }
+        for (_, value) in &self.properties {
+            value.to_writer(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, prid: u32) -> Option<&PropertyValue> {
+        self.properties.iter()
+            .find(|(id, _)| id.matches(prid))
+            .map(|(_, value)| value)
+    }
+}
+
+impl PropertyValue {
+    fn from_reader<T: Read + Seek>(prid: PropertyId, reader: &mut T, streams: &mut ReferenceStreams, depth: u32) -> Result<PropertyValue> {
+        let offset = reader.stream_position()?;
+        let property_type = prid.property_type()
+            .ok_or(Error::Unsupported { structure: STRUCTURE, offset, feature: "property type" })?;
+
+        let value = match property_type {
+            PropertyType::NoData => PropertyValue::NoData,
+            PropertyType::Bool => PropertyValue::Bool(prid.bool_value()),
+            PropertyType::OneByteOfData => PropertyValue::OneByte(reader.read_u8()?),
+            PropertyType::TwoBytesOfData => PropertyValue::TwoBytes(reader.read_u16::<LittleEndian>()?),
+            PropertyType::FourBytesOfData => PropertyValue::FourBytes(reader.read_u32::<LittleEndian>()?),
+            PropertyType::EightBytesOfData => PropertyValue::EightBytes(reader.read_u64::<LittleEndian>()?),
+            PropertyType::FourBytesOfLengthFollowedByData => {
+                // See MS-ONESTORE 2.6.8
+                let len = reader.read_u32::<LittleEndian>()?;
+                let mut data = Vec::new();
+                reader.take(len as u64).read_to_end(&mut data)?;
+                if data.len() != len as usize {
+                    return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Property data is longer than the property set" });
+                }
+                PropertyValue::Bytes(data)
+            },
+            PropertyType::ObjectId => PropertyValue::ObjectId(take_ids(&mut streams.oids, 1, offset)?[0]),
+            PropertyType::ArrayOfObjectIds => {
+                let count = reader.read_u32::<LittleEndian>()?;
+                PropertyValue::ObjectIds(take_ids(&mut streams.oids, count, offset)?)
+            },
+            PropertyType::ObjectSpaceId => PropertyValue::ObjectSpaceId(take_ids(&mut streams.osids, 1, offset)?[0]),
+            PropertyType::ArrayOfObjectSpaceIds => {
+                let count = reader.read_u32::<LittleEndian>()?;
+                PropertyValue::ObjectSpaceIds(take_ids(&mut streams.osids, count, offset)?)
+            },
+            PropertyType::ContextId => PropertyValue::ContextId(take_ids(&mut streams.context_ids, 1, offset)?[0]),
+            PropertyType::ArrayOfContextIds => {
+                let count = reader.read_u32::<LittleEndian>()?;
+                PropertyValue::ContextIds(take_ids(&mut streams.context_ids, count, offset)?)
+            },
+            PropertyType::ArrayOfPropertyValues => {
+                // See MS-ONESTORE 2.6.9
+                let count = reader.read_u32::<LittleEndian>()?;
+                if count == 0 {
+                    PropertyValue::PropertyValues(PropertyId(0), Vec::new())
+                } else {
+                    let element_prid = PropertyId(reader.read_u32::<LittleEndian>()?);
+                    if element_prid.property_type() != Some(PropertyType::PropertySet) {
+                        return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Array of property values must hold property sets" });
+                    }
+                    let mut sets = Vec::new();
+                    for _ in 0..count {
+                        sets.push(PropertySet::from_reader_with_depth(reader, streams, depth + 1)?);
+                    }
+                    PropertyValue::PropertyValues(element_prid, sets)
+                }
+            },
+            PropertyType::PropertySet => PropertyValue::PropertySet(PropertySet::from_reader_with_depth(reader, streams, depth + 1)?)
+        };
+
+        Ok(value)
+    }
+
+    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            PropertyValue::NoData
+            | PropertyValue::Bool(_)
+            | PropertyValue::ObjectId(_)
+            | PropertyValue::ObjectSpaceId(_)
+            | PropertyValue::ContextId(_) => {},
+            PropertyValue::OneByte(value) => writer.write_u8(*value)?,
+            PropertyValue::TwoBytes(value) => writer.write_u16::<LittleEndian>(*value)?,
+            PropertyValue::FourBytes(value) => writer.write_u32::<LittleEndian>(*value)?,
+            PropertyValue::EightBytes(value) => writer.write_u64::<LittleEndian>(*value)?,
+            PropertyValue::Bytes(data) => {
+                writer.write_u32::<LittleEndian>(data.len() as u32)?;
+                writer.write_all(data)?;
+            },
+            PropertyValue::ObjectIds(ids) | PropertyValue::ObjectSpaceIds(ids) | PropertyValue::ContextIds(ids) => {
+                writer.write_u32::<LittleEndian>(ids.len() as u32)?;
+            },
+            PropertyValue::PropertyValues(element_prid, sets) => {
+                writer.write_u32::<LittleEndian>(sets.len() as u32)?;
+                if !sets.is_empty() {
+                    writer.write_u32::<LittleEndian>(element_prid.0)?;
+                    for set in sets {
+                        set.to_writer(writer)?;
+                    }
+                }
+            },
+            PropertyValue::PropertySet(set) => set.to_writer(writer)?
+        }
+        Ok(())
+    }
+}
+
+// Pull the next count ids off the front of a reference stream
+fn take_ids(stream: &mut &[CompactId], count: u32, offset: u64) -> Result<Vec<CompactId>> {
+    let count = count as usize;
+    if count > stream.len() {
+        return Err(Error::InvalidData { structure: STRUCTURE, offset, reason: "Property references more ids than its stream holds" });
+    }
+    let (taken, rest) = stream.split_at(count);
+    *stream = rest;
+    Ok(taken.to_vec())
+}
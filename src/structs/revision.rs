@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+use super::exguid::ExGuid;
+use super::filenode::FileNode;
+use super::filenodedata::FileNodeData;
+use super::filenodelist::FileNodeList;
+use super::globalidtable::GlobalIdTable;
+use super::objecttable::{Object, ObjectTable};
+use super::transactionlog::TransactionLog;
+
+// See MS-ONESTORE 2.1.11
+pub const REVISION_ROLE_DEFAULT_CONTENT: u32 = 0x1;
+pub const REVISION_ROLE_METADATA: u32 = 0x2;
+pub const REVISION_ROLE_ENCRYPTION_KEY: u32 = 0x4;
+
+// See MS-ONESTORE 2.5.15
+pub const ROOT_ROLE_DEFAULT_CONTENT: u32 = 0x1;
+pub const ROOT_ROLE_METADATA: u32 = 0x2;
+
+const STRUCTURE: &str = "RevisionManifest";
+
+// A set of object declarations in its own file node list. See MS-ONESTORE 2.1.13
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectGroup {
+    pub id: ExGuid,
+    pub global_id_table: Arc<GlobalIdTable>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub objects: ObjectTable,
+    pub file_nodes: Vec<FileNode>
+}
+
+// A single revision manifest. Objects are inherited from the rid_dependent revision. See MS-ONESTORE 2.1.9
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Revision {
+    pub rid: ExGuid,
+    pub rid_dependent: ExGuid,
+    pub role: u32,
+    // Nil for the default context
+    pub context: ExGuid,
+    pub odcs_default: u16,
+    // Keyed by root role
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub root_objects: HashMap<u32, ExGuid>,
+    pub object_groups: Vec<ObjectGroup>,
+    pub global_id_table: Option<Arc<GlobalIdTable>>,
+    // Objects declared by this revision, both directly and in its object groups
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub objects: ObjectTable,
+    // Ref counts this revision overrides for objects declared by its dependencies
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted_map"))]
+    pub ref_count_overrides: HashMap<ExGuid, u32>,
+    // Every file node between the revision manifest start and end nodes
+    pub file_nodes: Vec<FileNode>
+}
+
+impl Revision {
+    // start must be a RevisionManifestStart node; file_nodes are the nodes that follow it, excluding the end node.
+    // dependency is the revision with id rid_dependent, if there is one, and inherited_objects its effective object table.
+    pub fn from_file_nodes<T: Read + Seek>(start: &FileNode, file_nodes: Vec<FileNode>, dependency: Option<&Revision>, inherited_objects: &ObjectTable, reader: &mut T, transaction_log: &TransactionLog) -> Result<Revision> {
+        let (rid, rid_dependent, role, context, odcs_default) = match &start.data {
+            FileNodeData::RevisionManifestStart4(start) => (start.rid, start.rid_dependent, start.revision_role, ExGuid::nil(), start.odcs_default),
+            FileNodeData::RevisionManifestStart6(start) => (start.rid, start.rid_dependent, start.revision_role, ExGuid::nil(), start.odcs_default),
+            FileNodeData::RevisionManifestStart7(start) => (start.base.rid, start.base.rid_dependent, start.base.revision_role, start.gctxid, start.base.odcs_default),
+            _ => return Err(Error::InvalidData { structure: STRUCTURE, offset: start.offset, reason: "Revision manifest must begin with a start node" })
+        };
+
+        if !rid_dependent.is_nil() && dependency.is_none() {
+            return Err(Error::InvalidData { structure: STRUCTURE, offset: start.offset, reason: "Revision depends on a revision that doesn't precede it" });
+        }
+
+        let mut root_objects = HashMap::new();
+        let mut object_groups = Vec::new();
+        let mut global_id_table: Option<Arc<GlobalIdTable>> = None;
+        let mut objects = ObjectTable::new();
+        let mut ref_count_overrides = HashMap::new();
+
+        let mut i = 0;
+        while i < file_nodes.len() {
+            let node = &file_nodes[i];
+            match &node.data {
+                FileNodeData::GlobalIdTableStart(_) | FileNodeData::GlobalIdTableStart2 => {
+                    let dependency_table = dependency.and_then(|dependency| dependency.global_id_table.as_deref());
+                    let (table, node_count) = GlobalIdTable::from_file_nodes(&file_nodes[i..], dependency_table)?;
+                    global_id_table = Some(Arc::new(table));
+                    i += node_count;
+                    continue;
+                },
+                FileNodeData::ObjectGroupListReference(reference) => {
+                    let list = FileNodeList::from_reader(&node.file_chunk_ref, reader, transaction_log)?;
+                    let object_group = ObjectGroup::from_file_node_list(reference.object_group_id, list, &[&objects, inherited_objects])?;
+                    objects.extend(object_group.objects.iter().map(|(id, object)| (*id, object.clone())));
+                    object_groups.push(object_group);
+                },
+                FileNodeData::ObjectInfoDependencyOverrides(overrides) => {
+                    let table = current_table(&global_id_table, node)?;
+                    let counts = overrides.data.overrides_1.iter().map(|o| (o.oid, o.c_ref as u32))
+                        .chain(overrides.data.overrides_2.iter().map(|o| (o.oid, o.c_ref)));
+                    for (oid, c_ref) in counts {
+                        let id = table.resolve(oid)
+                            .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Overridden object id is not in the global id table" })?;
+                        ref_count_overrides.insert(id, c_ref);
+                    }
+                },
+                FileNodeData::RootObjectReference2(reference) => {
+                    let oid_root = global_id_table.as_ref()
+                        .and_then(|table| table.resolve(reference.oid_root))
+                        .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "Root object id is not in the global id table" })?;
+                    root_objects.insert(reference.root_role, oid_root);
+                },
+                FileNodeData::RootObjectReference3(reference) => {
+                    root_objects.insert(reference.root_role, reference.oid_root);
+                },
+                _ => {
+                    // .onetoc2 revisions declare their objects directly in the revision manifest
+                    if let Some(table) = &global_id_table {
+                        if let Some(object) = Object::from_file_node(node, table, &[&objects, inherited_objects])? {
+                            objects.insert(object.id, object);
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        Ok(Revision {
+            rid,
+            rid_dependent,
+            role,
+            context,
+            odcs_default,
+            root_objects,
+            object_groups,
+            global_id_table,
+            objects,
+            ref_count_overrides,
+            file_nodes
+        })
+    }
+}
+
+impl ObjectGroup {
+    pub fn from_file_node_list(id: ExGuid, list: FileNodeList, existing_objects: &[&ObjectTable]) -> Result<ObjectGroup> {
+        let offset = list.file_nodes.first().map_or(0, |node| node.offset);
+        match list.file_nodes.first().map(|node| &node.data) {
+            Some(FileNodeData::ObjectGroupStart(start)) if start.oid == id => {},
+            _ => return Err(Error::InvalidData { structure: "ObjectGroupList", offset, reason: "Object group list doesn't start with a matching ObjectGroupStart node" })
+        }
+
+        let mut global_id_table = Arc::new(GlobalIdTable::default());
+        let mut objects = ObjectTable::new();
+
+        let mut i = 0;
+        while i < list.file_nodes.len() {
+            let node = &list.file_nodes[i];
+            match &node.data {
+                FileNodeData::GlobalIdTableStart(_) | FileNodeData::GlobalIdTableStart2 => {
+                    let (table, node_count) = GlobalIdTable::from_file_nodes(&list.file_nodes[i..], None)?;
+                    global_id_table = Arc::new(table);
+                    i += node_count;
+                    continue;
+                },
+                _ => {
+                    let mut search = vec![&objects];
+                    search.extend_from_slice(existing_objects);
+                    if let Some(object) = Object::from_file_node(node, &global_id_table, &search)? {
+                        objects.insert(object.id, object);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        Ok(ObjectGroup {
+            id,
+            global_id_table,
+            objects,
+            file_nodes: list.file_nodes
+        })
+    }
+}
+
+fn current_table<'a>(global_id_table: &'a Option<Arc<GlobalIdTable>>, node: &FileNode) -> Result<&'a GlobalIdTable> {
+    global_id_table.as_deref()
+        .ok_or(Error::InvalidData { structure: STRUCTURE, offset: node.offset, reason: "No global id table precedes this file node" })
+}
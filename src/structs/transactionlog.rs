@@ -1,20 +1,24 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-use super::{ListFromFileChunk, filechunkreference::FileChunkReference};
-use std::{io::{SeekFrom, Read, Seek, Error, ErrorKind}, collections::HashMap};
+use crate::error::{Error, Result};
+
+use super::{ListFromFileChunk, check_fcr_bounds, crc::crc32, filechunkreference::FileChunkReference};
+use std::{io::{SeekFrom, Read, Seek, Write}, collections::{HashMap, HashSet}};
 
 pub type TransactionLog = HashMap<u32, u32>;
 
-// TODO: KEEP TRACK OF HOW LONG THE FIRST NODE OF THE TRANSACTION LIST IS (FROM FILECHUNKREF) TO SEE
-// IF WE NEED TO MOVE TO NEXT FILE CHUNK IN THE LIST.
-// TODO: VERIFY CRC FOR EACH TRANSACTION
+const TRANSACTION_ENTRY_SIZE: u64 = 8;
+const SENTINEL_SRC_ID: u32 = 1;
+const NEXT_FRAGMENT_SIZE: u64 = 12;
+
+// See MS-ONESTORE 2.3.3.2
 pub struct TransactionEntry {
     pub src_id: u32,
     pub transaction_entry_switch: u32
 }
 
 impl TransactionEntry {
-    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<TransactionEntry, Error> {
+    fn from_reader<T: Read + Seek>(reader: &mut T) -> Result<TransactionEntry> {
         let src_id = reader.read_u32::<LittleEndian>()?;
         let transaction_entry_switch = reader.read_u32::<LittleEndian>()?;
 
@@ -22,36 +26,123 @@ impl TransactionEntry {
     }
 }
 
+// Writes one transaction: its entries, each setting a file node list's node count, then the
+// sentinel entry holding their CRC
+pub fn write_transaction<W: Write>(writer: &mut W, entries: &[(u32, u32)]) -> Result<()> {
+    let mut bytes = Vec::new();
+    for (src_id, switch) in entries {
+        if *src_id == SENTINEL_SRC_ID {
+            return Err(Error::InvalidData { structure: "TransactionLogFragment", offset: 0, reason: "File node list id is the sentinel id" });
+        }
+        bytes.write_u32::<LittleEndian>(*src_id)?;
+        bytes.write_u32::<LittleEndian>(*switch)?;
+    }
+    writer.write_all(&bytes)?;
+    writer.write_u32::<LittleEndian>(SENTINEL_SRC_ID)?;
+    writer.write_u32::<LittleEndian>(crc32(&bytes))?;
+    Ok(())
+}
+
+// Writes a fragment holding whole transactions and nextFragment. See MS-ONESTORE 2.3.3.1
+pub fn write_fragment<W: Write>(writer: &mut W, transactions: &[Vec<(u32, u32)>], next: &FileChunkReference) -> Result<()> {
+    for entries in transactions {
+        write_transaction(writer, entries)?;
+    }
+    next.to_writer(writer, 64, 32)
+}
+
+// The log fragment holding the end of the last committed transaction, and where that end is. The
+// next transaction is written from there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogTail {
+    pub fragment: FileChunkReference,
+    pub end_of_transactions: u64,
+    // Number of committed transactions, which a torn one at the end makes less than cTransactionsInLog
+    pub transactions: u32
+}
+
+impl LogTail {
+    // Position of the fragment's nextFragment field
+    pub fn next_fragment_position(&self) -> u64 {
+        self.fragment.start + self.fragment.len - NEXT_FRAGMENT_SIZE
+    }
+}
+
 impl ListFromFileChunk for TransactionLog {
-    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, len: u64) -> Result<Self, Error> where Self: Sized {
-        reader.seek(SeekFrom::Start(fcr.start))?;
-        let mut current_transaction: u64 = 0;
-        let mut transaction_log = TransactionLog::new();
-        let mut current_transaction_entry: TransactionEntry = TransactionEntry { src_id: 1, transaction_entry_switch: 0 };
-        
+    fn from_reader<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, len: u64) -> Result<Self> where Self: Sized {
+        Ok(read_with_tail(fcr, reader, len)?.0)
+    }
+}
+
+// Reads len transactions, following nextFragment chains. If the last transaction's CRC doesn't
+// match or its sentinel is missing, it was torn while being written and is discarded; a mismatch on
+// any earlier transaction means the log is corrupt.
+pub fn read_with_tail<T: Read + Seek>(fcr: &FileChunkReference, reader: &mut T, len: u64) -> Result<(TransactionLog, LogTail)> {
+    let mut fragment_fcr = *fcr;
+    let mut tail = LogTail { fragment: *fcr, end_of_transactions: fcr.start, transactions: 0 };
+    let mut current_transaction: u64 = 0;
+    let mut transaction_log = TransactionLog::new();
+    let mut pending_entries: Vec<TransactionEntry> = Vec::new();
+    let mut pending_bytes: Vec<u8> = Vec::new();
+    let mut visited_fragments = HashSet::new();
+
+    loop {
+        check_fcr_bounds(&fragment_fcr, reader, "TransactionLogFragment")?;
+        if fragment_fcr.len < NEXT_FRAGMENT_SIZE {
+            return Err(Error::InvalidData { structure: "TransactionLogFragment", offset: fragment_fcr.start, reason: "Fragment is too small to hold nextFragment" });
+        }
+        if !visited_fragments.insert(fragment_fcr.start) {
+            return Err(Error::InvalidData { structure: "TransactionLogFragment", offset: fragment_fcr.start, reason: "Transaction log fragments form a cycle" });
+        }
+        reader.seek(SeekFrom::Start(fragment_fcr.start))?;
+        let next_fragment_position = fragment_fcr.start + fragment_fcr.len - NEXT_FRAGMENT_SIZE;
+
         // Iterate until we read all transactions or we reach the end of this fragment
-        while current_transaction < len && reader.stream_position()? < (fcr.start + fcr.len - 12) {
-            current_transaction_entry = TransactionEntry::from_reader(reader)?;
+        while current_transaction < len && reader.stream_position()? + TRANSACTION_ENTRY_SIZE <= next_fragment_position {
+            let entry = TransactionEntry::from_reader(reader)?;
 
-            if current_transaction_entry.src_id == 1 {
-                // Sentinel entry; end of transaction
-                current_transaction += 1;
+            if entry.src_id != SENTINEL_SRC_ID {
+                pending_bytes.write_u32::<LittleEndian>(entry.src_id)?;
+                pending_bytes.write_u32::<LittleEndian>(entry.transaction_entry_switch)?;
+                pending_entries.push(entry);
+                continue;
             }
-            else {
-                // Otherwise, update transaction log for this src_id
-                let src_id_to_update = transaction_log.entry(current_transaction_entry.src_id).or_insert(0);
-                *src_id_to_update = current_transaction_entry.transaction_entry_switch;
+
+            // Sentinel entry; end of transaction. Its switch field holds the CRC of the transaction's entries
+            let crc = crc32(&pending_bytes);
+            if crc != entry.transaction_entry_switch {
+                if current_transaction + 1 == len {
+                    return Ok((transaction_log, tail));
+                }
+                let offset = reader.stream_position()? - TRANSACTION_ENTRY_SIZE;
+                return Err(Error::CrcMismatch { structure: "TransactionLogFragment", offset, expected: entry.transaction_entry_switch, actual: crc });
             }
-        }
 
-        if current_transaction_entry.src_id != 1 {
-            return Err(Error::new(ErrorKind::InvalidData, "Final transaction entry of log must be a sentinel"));
+            for pending_entry in pending_entries.drain(..) {
+                transaction_log.insert(pending_entry.src_id, pending_entry.transaction_entry_switch);
+            }
+            pending_bytes.clear();
+            current_transaction += 1;
+            tail = LogTail { fragment: fragment_fcr, end_of_transactions: reader.stream_position()?, transactions: current_transaction as u32 };
         }
 
-        if current_transaction < len {
-            todo!("Read next fragment based on fcr at end of list!");
+        if current_transaction >= len {
+            break;
         }
 
-        Ok(transaction_log)
+        reader.seek(SeekFrom::Start(next_fragment_position))?;
+        let next_fragment = FileChunkReference::from_reader(reader, 64, 32)?;
+        let chain_ended = next_fragment.is_nil() || next_fragment.is_zero();
+        // The last transaction's sentinel never reached the file, so its zero padding was read as
+        // entries until the chain ran out; it's torn the same as if its CRC didn't match
+        if current_transaction + 1 == len && (chain_ended || check_fcr_bounds(&next_fragment, reader, "TransactionLogFragment").is_err()) {
+            return Ok((transaction_log, tail));
+        }
+        if chain_ended {
+            return Err(Error::InvalidData { structure: "TransactionLogFragment", offset: next_fragment_position, reason: "Transaction log ended before all transactions were read" });
+        }
+        fragment_fcr = next_fragment;
     }
-}
\ No newline at end of file
+
+    Ok((transaction_log, tail))
+}
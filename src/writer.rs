@@ -0,0 +1,183 @@
+// Writes a parsed revision store back out as a new .one or .onetoc2 file. Every file node list
+// reachable from the header is copied into a single fragment, children before the lists that
+// reference them, and one transaction records the length of every list. See MS-ONESTORE 2.1
+// Written files are only checked by parsing them again with this crate. None has been opened in
+// OneNote, so whether OneNote accepts them is unverified.
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::onestore::{OneStore, StoreFormat};
+use crate::structs::check_fcr_bounds;
+use crate::structs::filechunkreference::FileChunkReference;
+use crate::structs::filenode::{BaseType, FileNode, FileType};
+use crate::structs::filenodelist::FileNodeList;
+use crate::structs::header::{name_crc, OneNoteFileHeader, HEADER_SIZE};
+use crate::structs::objectpropset::ObjectSpaceObjectPropSet;
+use crate::structs::transactionlog;
+
+// Chunks start on 8 byte boundaries, as OneNote lays them out
+const CHUNK_ALIGNMENT: usize = 8;
+
+struct StoreWriter<'a, R> {
+    store: &'a OneStore,
+    source: &'a mut R,
+    out: Vec<u8>,
+    // Where each list and data chunk of the source went, so shared ones are only written once
+    lists: HashMap<u32, FileChunkReference>,
+    chunks: HashMap<(u64, u64), FileChunkReference>,
+    lists_in_progress: HashSet<u32>,
+    // (list id, node count) for the transaction log, in the order the lists were written
+    list_lengths: Vec<(u32, u32)>
+}
+
+// Writes store, whose property sets and file data are read from source, as a complete file that
+// will be stored under file_name, without any directories. crcName is computed from file_name and
+// the other header fields that describe the file as a whole (GUIDs, build numbers) are kept; the
+// free chunk list, debug log and allocation verification list are dropped.
+pub fn write_store<R: Read + Seek, W: Write>(store: &OneStore, source: &mut R, file_name: &str, writer: &mut W) -> Result<()> {
+    if store.format != StoreFormat::Native {
+        return Err(Error::Unsupported { structure: "OneStore", offset: 0, feature: "writing packaged stores" });
+    }
+
+    let mut store_writer = StoreWriter {
+        store,
+        source,
+        out: vec![0; HEADER_SIZE as usize],
+        lists: HashMap::new(),
+        chunks: HashMap::new(),
+        lists_in_progress: HashSet::new(),
+        list_lengths: Vec::new()
+    };
+    let file_node_list_root = store_writer.copy_list(&store.header.file_node_list_root)?;
+    let hashed_chunk_list = match store.header.hashed_chunk_list {
+        fcr if fcr.is_nil() || fcr.is_zero() => fcr,
+        fcr => store_writer.copy_list(&fcr)?
+    };
+
+    let mut log = Vec::new();
+    transactionlog::write_fragment(&mut log, &[store_writer.list_lengths.clone()], &FileChunkReference::nil())?;
+    let transaction_log = store_writer.append(&log);
+
+    let StoreWriter { mut out, .. } = store_writer;
+    let header = OneNoteFileHeader {
+        transactions_in_log: 1,
+        hashed_chunk_list,
+        transaction_log,
+        file_node_list_root,
+        free_chunk_list: FileChunkReference::nil(),
+        expected_file_length: out.len() as u64,
+        free_space_in_free_chunk_list: 0,
+        debug_log: FileChunkReference::zero(),
+        alloc_verification_free_chunk_list: FileChunkReference::zero(),
+        crc_name: name_crc(file_name),
+        ..store.header.clone()
+    };
+    header.to_writer(&mut &mut out[..HEADER_SIZE as usize])?;
+
+    writer.write_all(&out)?;
+    Ok(())
+}
+
+// Writes store to a new file at path, replacing anything already there
+pub fn save<R: Read + Seek, P: AsRef<Path>>(store: &OneStore, source: &mut R, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_store(store, source, &file_name, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+impl<R: Read + Seek> StoreWriter<'_, R> {
+    // Copies the list at fcr and everything it references, returning where the copy starts
+    fn copy_list(&mut self, fcr: &FileChunkReference) -> Result<FileChunkReference> {
+        let list = FileNodeList::from_reader(fcr, self.source, &self.store.transaction_log)?;
+        if let Some(written) = self.lists.get(&list.id) {
+            return Ok(*written);
+        }
+        if !self.lists_in_progress.insert(list.id) {
+            return Err(Error::InvalidData { structure: "FileNodeListFragment", offset: fcr.start, reason: "File node list references itself" });
+        }
+
+        let mut file_nodes = Vec::new();
+        for node in &list.file_nodes {
+            let reference = &node.file_chunk_ref;
+            let file_chunk_ref = match node.base_type {
+                _ if reference.is_nil() || reference.is_zero() => *reference,
+                BaseType::NoReference => *reference,
+                BaseType::FileNodeReference => self.copy_list(reference)?,
+                BaseType::DataReference => self.copy_chunk(node)?
+            };
+            file_nodes.push(FileNode { file_chunk_ref, ..node.clone() });
+        }
+
+        let copy = FileNodeList { id: list.id, fragment_sequence_index: list.fragment_sequence_index, file_nodes };
+        let mut bytes = Vec::new();
+        copy.to_writer(&mut bytes, &FileChunkReference::nil())?;
+        let written = self.append(&bytes);
+
+        self.lists_in_progress.remove(&list.id);
+        self.lists.insert(list.id, written);
+        self.list_lengths.push((list.id, copy.file_nodes.len() as u32));
+        Ok(written)
+    }
+
+    // Copies the chunk a data reference node points at. Property sets are written from their
+    // parsed form, unless an MD5 hash covers them and the written bytes would differ. Other
+    // chunks are copied as they are.
+    fn copy_chunk(&mut self, node: &FileNode) -> Result<FileChunkReference> {
+        let fcr = node.file_chunk_ref;
+        if let Some(written) = self.chunks.get(&(fcr.start, fcr.len)) {
+            return Ok(*written);
+        }
+
+        check_fcr_bounds(&fcr, self.source, "FileNode")?;
+        self.source.seek(SeekFrom::Start(fcr.start))?;
+        let mut bytes = Vec::new();
+        self.source.by_ref().take(fcr.len).read_to_end(&mut bytes)?;
+
+        if holds_property_set(node.file_type) {
+            let property_set = ObjectSpaceObjectPropSet::from_reader(&fcr, self.source)?;
+            let mut written = Vec::new();
+            property_set.to_writer(&mut written)?;
+            if !is_hashed(node.file_type) || written == bytes {
+                bytes = written;
+            }
+        }
+
+        let written = self.append(&bytes);
+        self.chunks.insert((fcr.start, fcr.len), written);
+        Ok(written)
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> FileChunkReference {
+        self.out.resize(self.out.len().next_multiple_of(CHUNK_ALIGNMENT), 0);
+        let fcr = FileChunkReference { start: self.out.len() as u64, len: bytes.len() as u64 };
+        self.out.extend_from_slice(bytes);
+        fcr
+    }
+}
+
+fn holds_property_set(file_type: FileType) -> bool {
+    matches!(file_type,
+        FileType::ObjectDeclarationWithRefCount
+        | FileType::ObjectDeclarationWithRefCount2
+        | FileType::ObjectRevisionWithRefCount
+        | FileType::ObjectRevisionWithRefCount2
+        | FileType::ObjectDeclaration2RefCount
+        | FileType::ObjectDeclaration2LargeRefCount
+        | FileType::ReadOnlyObjectDeclaration2RefCount
+        | FileType::ReadOnlyObjectDeclaration2LargeRefCount
+        | FileType::HashedChunkDescriptor2)
+}
+
+// Nodes that store an MD5 hash of the property set they reference
+fn is_hashed(file_type: FileType) -> bool {
+    matches!(file_type,
+        FileType::ReadOnlyObjectDeclaration2RefCount
+        | FileType::ReadOnlyObjectDeclaration2LargeRefCount
+        | FileType::HashedChunkDescriptor2)
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_temp(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("onernote-cli-{}-{}", std::process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_onernote")).args(args).output().unwrap().status.code()
+}
+
+#[test]
+fn info_succeeds_on_valid_file() {
+    let path = write_temp("valid.one", &common::minimal_store());
+    assert_eq!(run(&["info", path.to_str().unwrap()]), Some(0));
+    assert_eq!(run(&["dump", path.to_str().unwrap(), "--nodes", "--txlog", "--objects"]), Some(0));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn parse_failure_exit_code() {
+    let path = write_temp("garbage.one", &[0xAB; 2048]);
+    assert_eq!(run(&["info", path.to_str().unwrap()]), Some(1));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn usage_error_exit_code() {
+    assert_eq!(run(&["dump", "file.one"]), Some(2));
+}
+
+#[test]
+fn rewritten_file_has_its_own_name_crc() {
+    let path = write_temp("original.one", &common::minimal_store());
+    let output = std::env::temp_dir().join(format!("onernote-cli-{}-rewritten.one", std::process::id()));
+    assert_eq!(run(&["rewrite", path.to_str().unwrap(), "-o", output.to_str().unwrap()]), Some(0));
+
+    let info = Command::new(env!("CARGO_BIN_EXE_onernote")).args(["info", output.to_str().unwrap()]).output().unwrap();
+    assert!(String::from_utf8_lossy(&info.stdout).contains("(matches)"));
+    fs::remove_file(path).unwrap();
+    fs::remove_file(output).unwrap();
+}
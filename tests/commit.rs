@@ -0,0 +1,157 @@
+mod common;
+
+use std::io::Cursor;
+
+use onernote::commit::{Commit, NewObjectGroup, NewRevision};
+use onernote::structs::compactid::CompactId;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filenode::{FileNode, FileType};
+use onernote::structs::filenodedata::*;
+use onernote::structs::guid::Guid;
+use onernote::structs::jcid::Jcid;
+use onernote::structs::objectpropset::ObjectSpaceObjectPropSet;
+use onernote::structs::propertyset::{PropertyId, PropertySet, PropertyValue};
+use onernote::structs::revision::{REVISION_ROLE_DEFAULT_CONTENT, ROOT_ROLE_DEFAULT_CONTENT};
+use onernote::OneStore;
+
+const OBJECT: [u8; 16] = [0x0B; 16];
+
+fn exguid(guid: [u8; 16], n: u32) -> ExGuid {
+    ExGuid { guid: Guid::from_bytes_le(guid), n }
+}
+
+fn node(file_type: FileType, data: FileNodeData) -> FileNode {
+    FileNode::new(file_type, FileChunkReference::zero(), data)
+}
+
+fn property_set() -> ObjectSpaceObjectPropSet {
+    ObjectSpaceObjectPropSet {
+        body: PropertySet { properties: vec![(PropertyId(0x1C001C03), PropertyValue::Bytes(b"hello".to_vec()))] },
+        ..Default::default()
+    }
+}
+
+// Adds a revision on top of the minimal store's, with one object in one object group
+fn commit_revision(file: &mut Cursor<Vec<u8>>) -> OneStore {
+    let store = OneStore::parse(file).unwrap();
+    let gosid = exguid(common::GOSID, 1);
+    let mut commit = Commit::new(&store, file).unwrap();
+
+    let fcr = commit.write_property_set(&property_set()).unwrap();
+    let declaration = ObjectDeclaration2RefCountFnd {
+        body: ObjectDeclaration2Body { oid: CompactId { n: 1, guid_index: 0 }, jcid: Jcid(0x00060007), has_oid_references: false, has_osid_references: false },
+        c_ref: 1
+    };
+    let group = NewObjectGroup {
+        id: exguid(OBJECT, 100),
+        file_nodes: vec![
+            node(FileType::GlobalIdTableStart2, FileNodeData::GlobalIdTableStart2),
+            node(FileType::GlobalIdTableEntry, FileNodeData::GlobalIdTableEntry(GlobalIdTableEntryFndx { index: 0, guid: Guid::from_bytes_le(OBJECT) })),
+            node(FileType::GlobalIdTableEnd, FileNodeData::GlobalIdTableEnd),
+            FileNode::new(FileType::ObjectDeclaration2RefCount, fcr, FileNodeData::ObjectDeclaration2RefCount(declaration))
+        ]
+    };
+    let root = RootObjectReference3Fnd { oid_root: exguid(OBJECT, 1), root_role: ROOT_ROLE_DEFAULT_CONTENT };
+    commit.add_revision(&gosid, NewRevision {
+        rid: exguid(common::RID, 2),
+        rid_dependent: exguid(common::RID, 1),
+        context: ExGuid::nil(),
+        revision_role: REVISION_ROLE_DEFAULT_CONTENT,
+        odcs_default: 0,
+        object_groups: vec![group],
+        file_nodes: vec![node(FileType::RootObjectReference3, FileNodeData::RootObjectReference3(root))]
+    }).unwrap();
+    commit.finish().unwrap();
+    store
+}
+
+#[test]
+fn committed_revision_becomes_the_latest() {
+    let mut file = Cursor::new(common::minimal_store());
+    let original = commit_revision(&mut file);
+    let store = OneStore::parse(&mut file).unwrap();
+
+    assert_eq!(store.header.transactions_in_log, 2);
+    assert_eq!(store.header.file_version_generation, original.header.file_version_generation + 1);
+    assert_ne!(store.header.file_version, original.header.file_version);
+    assert_eq!(store.header.expected_file_length, file.get_ref().len() as u64);
+
+    let space = store.root_object_space().unwrap();
+    assert_eq!(space.revisions.len(), 2);
+    let revision = space.latest_revision().unwrap();
+    assert_eq!(revision.rid, exguid(common::RID, 2));
+    assert_eq!(revision.root_objects[&ROOT_ROLE_DEFAULT_CONTENT], exguid(OBJECT, 1));
+    let object = &revision.objects[&exguid(OBJECT, 1)];
+    assert_eq!(object.read_property_set(&mut file).unwrap(), Some(property_set()));
+
+    // A second commit continues the lists and log the first one extended
+    let mut commit = Commit::new(&store, &mut file).unwrap();
+    commit.add_revision(&space.gosid, NewRevision {
+        rid: exguid(common::RID, 3),
+        rid_dependent: exguid(common::RID, 2),
+        context: ExGuid::nil(),
+        revision_role: REVISION_ROLE_DEFAULT_CONTENT,
+        odcs_default: 0,
+        object_groups: Vec::new(),
+        file_nodes: Vec::new()
+    }).unwrap();
+    commit.finish().unwrap();
+    let store = OneStore::parse(&mut file).unwrap();
+    let revision = store.root_object_space().unwrap().latest_revision().unwrap();
+    assert_eq!(revision.rid, exguid(common::RID, 3));
+    assert!(store.root_object_space().unwrap().effective_objects(&revision.rid).contains_key(&exguid(OBJECT, 1)));
+}
+
+#[test]
+fn nothing_is_visible_before_the_header_is_written() {
+    let original = common::minimal_store();
+    let mut file = Cursor::new(original.clone());
+    commit_revision(&mut file);
+
+    // Put back the old header, as if writing it had failed
+    let mut file = file.into_inner();
+    file[..common::HEADER_SIZE].copy_from_slice(&original[..common::HEADER_SIZE]);
+    let store = OneStore::parse(&mut Cursor::new(file)).unwrap();
+    let space = store.root_object_space().unwrap();
+    assert_eq!(space.revisions.len(), 1);
+    assert_eq!(space.latest_revision().unwrap().rid, exguid(common::RID, 1));
+}
+
+#[test]
+fn revision_ids_are_checked() {
+    let mut file = Cursor::new(common::minimal_store());
+    let store = OneStore::parse(&mut file).unwrap();
+    let mut commit = Commit::new(&store, &mut file).unwrap();
+    let revision = |rid, rid_dependent| NewRevision {
+        rid,
+        rid_dependent,
+        context: ExGuid::nil(),
+        revision_role: REVISION_ROLE_DEFAULT_CONTENT,
+        odcs_default: 0,
+        object_groups: Vec::new(),
+        file_nodes: Vec::new()
+    };
+    let gosid = exguid(common::GOSID, 1);
+
+    assert!(commit.add_revision(&gosid, revision(exguid(common::RID, 1), ExGuid::nil())).is_err());
+    assert!(commit.add_revision(&gosid, revision(exguid(common::RID, 2), exguid(common::RID, 9))).is_err());
+    assert!(commit.add_revision(&exguid(OBJECT, 1), revision(exguid(common::RID, 2), ExGuid::nil())).is_err());
+}
+
+#[test]
+fn corrupt_log_is_not_committed_over() {
+    let mut file = Cursor::new(common::minimal_store());
+    commit_revision(&mut file);
+    let store = OneStore::parse(&mut file).unwrap();
+    assert_eq!(store.header.transactions_in_log, 2);
+
+    // Flip a bit in the CRC of the first transaction, whose three entries start the log
+    let sentinel = store.header.transaction_log.start as usize + 3 * 8;
+    assert_eq!(file.get_ref()[sentinel..sentinel + 4], 1u32.to_le_bytes());
+    file.get_mut()[sentinel + 4] ^= 1;
+    let corrupt = file.get_ref().clone();
+
+    assert!(Commit::new(&store, &mut file).is_err());
+    assert_eq!(file.get_ref(), &corrupt);
+}
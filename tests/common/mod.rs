@@ -0,0 +1,605 @@
+// Builds small but structurally valid revision store files for tests
+#![allow(dead_code)]
+
+use onernote::structs::crc::crc32;
+
+pub const FILE_TYPE_ONE: [u8; 16] = guid_bytes(0x7B5C52E4, 0xD88C, 0x4DA7, [0xAE, 0xB1, 0x53, 0x78, 0xD0, 0x29, 0x96, 0xD3]);
+pub const FILE_TYPE_ONETOC2: [u8; 16] = guid_bytes(0x43FF2FA1, 0xEFD9, 0x4C76, [0x9E, 0xE2, 0x10, 0xEA, 0x57, 0x22, 0x76, 0x5F]);
+pub const FILE_FORMAT: [u8; 16] = guid_bytes(0x109ADD3F, 0x911B, 0x49F5, [0xA5, 0xD0, 0x17, 0x91, 0xED, 0xC8, 0xAE, 0xD8]);
+pub const GOSID: [u8; 16] = guid_bytes(0x11111111, 0x2222, 0x3333, [0x44, 0x44, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55]);
+pub const RID: [u8; 16] = guid_bytes(0x66666666, 0x7777, 0x8888, [0x99, 0x99, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]);
+
+pub const FILE_DATA_HEADER: [u8; 16] = guid_bytes(0xBDE316E7, 0x2665, 0x4511, [0xA4, 0xC4, 0x8D, 0x4D, 0x0B, 0x7A, 0x9E, 0xAC]);
+pub const FILE_DATA_FOOTER: [u8; 16] = guid_bytes(0x71FBA722, 0x0F79, 0x4A0B, [0xBB, 0x13, 0x89, 0x92, 0x56, 0x42, 0x6B, 0x24]);
+
+pub const FILE_NODE_LIST_HEADER_MAGIC: u64 = 0xA4567AB1F5F7F4C4;
+pub const FILE_NODE_LIST_FOOTER_MAGIC: u64 = 0x8BC215C38233BA4B;
+
+pub const HEADER_SIZE: usize = 1024;
+
+// GUIDs are stored little endian for the first three fields
+pub const fn guid_bytes(d1: u32, d2: u16, d3: u16, d4: [u8; 8]) -> [u8; 16] {
+    let a = d1.to_le_bytes();
+    let b = d2.to_le_bytes();
+    let c = d3.to_le_bytes();
+    [a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d4[0], d4[1], d4[2], d4[3], d4[4], d4[5], d4[6], d4[7]]
+}
+
+pub fn exguid(guid: [u8; 16], n: u32) -> Vec<u8> {
+    let mut bytes = guid.to_vec();
+    bytes.extend_from_slice(&n.to_le_bytes());
+    bytes
+}
+
+// File node with no reference
+pub fn node(id: u16, data: &[u8]) -> Vec<u8> {
+    let size = 4 + data.len() as u32;
+    let header: u32 = id as u32 | (size << 10);
+    let mut bytes = header.to_le_bytes().to_vec();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+// File node referencing a file node list, with an uncompressed 8 byte stp and 4 byte cb
+pub fn reference_node(id: u16, start: u64, len: u32, data: &[u8]) -> Vec<u8> {
+    referencing_node(2, id, (start, len), data)
+}
+
+// File node referencing a chunk of data, with an uncompressed 8 byte stp and 4 byte cb
+pub fn data_node(id: u16, (start, len): (u64, u32), data: &[u8]) -> Vec<u8> {
+    referencing_node(1, id, (start, len), data)
+}
+
+fn referencing_node(base_type: u32, id: u16, (start, len): (u64, u32), data: &[u8]) -> Vec<u8> {
+    let size = 4 + 12 + data.len() as u32;
+    let header: u32 = id as u32 | (size << 10) | (base_type << 27);
+    let mut bytes = header.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&start.to_le_bytes());
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+pub fn fragment(list_id: u32, sequence: u32, nodes: &[Vec<u8>], next: Option<(u64, u32)>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&FILE_NODE_LIST_HEADER_MAGIC.to_le_bytes());
+    bytes.extend_from_slice(&list_id.to_le_bytes());
+    bytes.extend_from_slice(&sequence.to_le_bytes());
+    for node in nodes {
+        bytes.extend_from_slice(node);
+    }
+    let (next_start, next_len) = next.unwrap_or((u64::MAX, 0));
+    bytes.extend_from_slice(&next_start.to_le_bytes());
+    bytes.extend_from_slice(&next_len.to_le_bytes());
+    bytes.extend_from_slice(&FILE_NODE_LIST_FOOTER_MAGIC.to_le_bytes());
+    bytes
+}
+
+// A FileDataStoreObject holding data, padded to 8 bytes
+pub fn file_data_store_object(data: &[u8]) -> Vec<u8> {
+    let mut bytes = FILE_DATA_HEADER.to_vec();
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&[0; 12]);
+    bytes.extend_from_slice(data);
+    bytes.resize(bytes.len() + (8 - data.len() % 8) % 8, 0);
+    bytes.extend_from_slice(&FILE_DATA_FOOTER);
+    bytes
+}
+
+// One transaction setting the node count of every list, followed by its sentinel
+pub fn transaction_log(counts: &[(u32, u32)]) -> Vec<u8> {
+    let mut entries = Vec::new();
+    for (list_id, count) in counts {
+        entries.extend_from_slice(&list_id.to_le_bytes());
+        entries.extend_from_slice(&count.to_le_bytes());
+    }
+    let crc = crc32(&entries);
+    entries.extend_from_slice(&1u32.to_le_bytes());
+    entries.extend_from_slice(&crc.to_le_bytes());
+    entries.extend_from_slice(&u64::MAX.to_le_bytes());
+    entries.extend_from_slice(&0u32.to_le_bytes());
+    entries
+}
+
+pub fn header(transaction_log: (u64, u32), file_node_list_root: (u64, u32), file_length: u64) -> Vec<u8> {
+    header_for(FILE_TYPE_ONE, transaction_log, file_node_list_root, file_length)
+}
+
+pub fn header_for(file_type: [u8; 16], transaction_log: (u64, u32), file_node_list_root: (u64, u32), file_length: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&file_type);
+    bytes.extend_from_slice(&GOSID);
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend_from_slice(&FILE_FORMAT);
+    let code_version: u32 = if file_type == FILE_TYPE_ONETOC2 { 0x1B } else { 0x2A };
+    for _ in 0..4 {
+        bytes.extend_from_slice(&code_version.to_le_bytes());
+    }
+    // fcrLegacyFreeChunkList, fcrLegacyTransactionLog
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    // cTransactionsInLog, cbLegacyExpectedFileLength, rgbPlaceholder
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 12]);
+    // fcrLegacyFileNodeListRoot, cbLegacyFreeSpaceInFreeChunkList, flags
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    // guidAncestor, crcName
+    bytes.extend_from_slice(&[0; 20]);
+    // fcrHashedChunkList
+    bytes.extend_from_slice(&[0; 12]);
+    bytes.extend_from_slice(&transaction_log.0.to_le_bytes());
+    bytes.extend_from_slice(&transaction_log.1.to_le_bytes());
+    bytes.extend_from_slice(&file_node_list_root.0.to_le_bytes());
+    bytes.extend_from_slice(&file_node_list_root.1.to_le_bytes());
+    // fcrFreeChunkList
+    bytes.extend_from_slice(&[0; 12]);
+    bytes.extend_from_slice(&file_length.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&GOSID);
+    bytes.extend_from_slice(&1u64.to_le_bytes());
+    bytes.resize(HEADER_SIZE, 0);
+    bytes
+}
+
+// A .one file with a single object space holding a single empty revision
+pub fn minimal_store() -> Vec<u8> {
+    const ROOT_LIST: u32 = 0x10;
+    const OBJECT_SPACE_LIST: u32 = 0x11;
+    const REVISION_LIST: u32 = 0x12;
+
+    let log = transaction_log(&[(ROOT_LIST, 2), (OBJECT_SPACE_LIST, 2), (REVISION_LIST, 3)]);
+    let log_start = HEADER_SIZE as u64;
+
+    let mut revision_manifest = exguid(RID, 1);
+    revision_manifest.extend(exguid([0; 16], 0));
+    revision_manifest.extend_from_slice(&1u32.to_le_bytes());
+    revision_manifest.extend_from_slice(&0u16.to_le_bytes());
+    let mut revision_list_start = exguid(GOSID, 1);
+    revision_list_start.extend_from_slice(&0u32.to_le_bytes());
+    let revision_list = fragment(REVISION_LIST, 0, &[
+        node(0x14, &revision_list_start),
+        node(0x1E, &revision_manifest),
+        node(0x1C, &[])
+    ], None);
+    let revision_list_start = log_start + log.len() as u64;
+
+    let object_space_list = fragment(OBJECT_SPACE_LIST, 0, &[
+        node(0x0C, &exguid(GOSID, 1)),
+        reference_node(0x10, revision_list_start, revision_list.len() as u32, &[])
+    ], None);
+    let object_space_list_start = revision_list_start + revision_list.len() as u64;
+
+    let root_list = fragment(ROOT_LIST, 0, &[
+        reference_node(0x08, object_space_list_start, object_space_list.len() as u32, &exguid(GOSID, 1)),
+        node(0x04, &exguid(GOSID, 1))
+    ], None);
+    let root_list_start = object_space_list_start + object_space_list.len() as u64;
+
+    let file_length = root_list_start + root_list.len() as u64;
+    let mut file = header((log_start, log.len() as u32), (root_list_start, root_list.len() as u32), file_length);
+    file.extend(log);
+    file.extend(revision_list);
+    file.extend(object_space_list);
+    file.extend(root_list);
+    file
+}
+
+// A CompactID: n and the index of a GUID in the global id table
+pub const fn compact_id(n: u8, guid_index: u32) -> u32 {
+    n as u32 | guid_index << 8
+}
+
+// Builds the bytes of an ObjectSpaceObjectPropSet one property at a time. Reference properties
+// add their ids to the OID or OSID stream in the order the parser takes them back off.
+#[derive(Default, Clone)]
+pub struct Props {
+    prids: Vec<u32>,
+    data: Vec<u8>,
+    oids: Vec<u32>,
+    osids: Vec<u32>
+}
+
+impl Props {
+    pub fn new() -> Props {
+        Props::default()
+    }
+
+    pub fn bool(mut self, prid: u32, value: bool) -> Props {
+        self.prids.push(prid & 0x7FFFFFFF | (value as u32) << 31);
+        self
+    }
+
+    pub fn u8(mut self, prid: u32, value: u8) -> Props {
+        self.prids.push(prid);
+        self.data.push(value);
+        self
+    }
+
+    pub fn u16(mut self, prid: u32, value: u16) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u32(mut self, prid: u32, value: u32) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn f32(self, prid: u32, value: f32) -> Props {
+        self.u32(prid, value.to_bits())
+    }
+
+    pub fn u64(mut self, prid: u32, value: u64) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn bytes(mut self, prid: u32, value: &[u8]) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    // UTF-16LE without a terminator
+    pub fn string(self, prid: u32, value: &str) -> Props {
+        let bytes: Vec<u8> = value.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        self.bytes(prid, &bytes)
+    }
+
+    pub fn object_id(mut self, prid: u32, id: u32) -> Props {
+        self.prids.push(prid);
+        self.oids.push(id);
+        self
+    }
+
+    pub fn object_ids(mut self, prid: u32, ids: &[u32]) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+        self.oids.extend_from_slice(ids);
+        self
+    }
+
+    pub fn object_space_ids(mut self, prid: u32, ids: &[u32]) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+        self.osids.extend_from_slice(ids);
+        self
+    }
+
+    // An ArrayOfPropertyValues whose elements are the property sets in sets
+    pub fn property_sets(mut self, prid: u32, element_prid: u32, sets: &[Props]) -> Props {
+        self.prids.push(prid);
+        self.data.extend_from_slice(&(sets.len() as u32).to_le_bytes());
+        if !sets.is_empty() {
+            self.data.extend_from_slice(&element_prid.to_le_bytes());
+        }
+        for set in sets {
+            self.data.extend(set.body());
+            self.oids.extend_from_slice(&set.oids);
+            self.osids.extend_from_slice(&set.osids);
+        }
+        self
+    }
+
+    pub fn has_oids(&self) -> bool {
+        !self.oids.is_empty()
+    }
+
+    pub fn has_osids(&self) -> bool {
+        !self.osids.is_empty()
+    }
+
+    // cProperties, the property ids and their data
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = (self.prids.len() as u16).to_le_bytes().to_vec();
+        for prid in &self.prids {
+            bytes.extend_from_slice(&prid.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    // The streams and body, padded to 8 bytes. The OSIDs stream is left out when it's empty.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let osid_stream_not_present = if self.osids.is_empty() { 1 << 31 } else { 0 };
+        let mut bytes = (self.oids.len() as u32 | osid_stream_not_present).to_le_bytes().to_vec();
+        for id in &self.oids {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        if !self.osids.is_empty() {
+            bytes.extend_from_slice(&(self.osids.len() as u32).to_le_bytes());
+            for id in &self.osids {
+                bytes.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+        bytes.extend(self.body());
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+        bytes
+    }
+}
+
+// An object declared in the revision of an object space
+pub struct Object {
+    // Resolved through the space's global id table; the object's id is its guid with n
+    pub id: u32,
+    pub jcid: u32,
+    pub props: Props
+}
+
+// An object space with a single revision. guids is its global id table: CompactIDs in its objects,
+// property sets and roots index into it.
+pub struct Space {
+    pub gosid: [u8; 16],
+    pub guids: Vec<[u8; 16]>,
+    pub objects: Vec<Object>,
+    // (root role, CompactID of the root object)
+    pub roots: Vec<(u32, u32)>
+}
+
+// Lays out a revision store chunk by chunk after room for the header. Each file node list is a
+// single fragment, written after every list it references. The first object space added is the
+// root object space.
+pub struct StoreBuilder {
+    file_type: [u8; 16],
+    bytes: Vec<u8>,
+    list_counts: Vec<(u32, u32)>,
+    next_list_id: u32,
+    spaces: Vec<([u8; 16], (u64, u32))>,
+    file_data_store: Option<(u64, u32)>
+}
+
+const ROLE_DEFAULT_CONTENT: u32 = 1;
+
+impl StoreBuilder {
+    pub fn one() -> StoreBuilder {
+        StoreBuilder::new(FILE_TYPE_ONE)
+    }
+
+    pub fn onetoc2() -> StoreBuilder {
+        StoreBuilder::new(FILE_TYPE_ONETOC2)
+    }
+
+    fn new(file_type: [u8; 16]) -> StoreBuilder {
+        StoreBuilder { file_type, bytes: vec![0; HEADER_SIZE], list_counts: Vec::new(), next_list_id: 0x10, spaces: Vec::new(), file_data_store: None }
+    }
+
+    // Appends bytes on an 8 byte boundary and returns where they went
+    pub fn chunk(&mut self, bytes: &[u8]) -> (u64, u32) {
+        self.bytes.resize(self.bytes.len().next_multiple_of(8), 0);
+        let start = self.bytes.len() as u64;
+        self.bytes.extend_from_slice(bytes);
+        (start, bytes.len() as u32)
+    }
+
+    // Appends a file node list with the next unused id
+    pub fn list(&mut self, nodes: &[Vec<u8>]) -> (u64, u32) {
+        let id = self.next_list_id;
+        self.next_list_id += 1;
+        self.list_counts.push((id, nodes.len() as u32));
+        self.chunk(&fragment(id, 0, nodes, None))
+    }
+
+    // .one files declare objects in an object group; .onetoc2 files declare them in the revision
+    // manifest itself
+    pub fn space(&mut self, space: &Space) -> &mut StoreBuilder {
+        let rid = exguid(space.gosid, 2);
+        let resolve = |id: u32| exguid(space.guids[(id >> 8) as usize], id & 0xFF);
+        let mut global_id_table = Vec::new();
+        for (index, guid) in space.guids.iter().enumerate() {
+            let mut entry = (index as u32).to_le_bytes().to_vec();
+            entry.extend_from_slice(guid);
+            global_id_table.push(node(0x24, &entry));
+        }
+        let mut declarations = Vec::new();
+        for object in &space.objects {
+            let property_set = self.chunk(&object.props.to_bytes());
+            let flags = object.props.has_oids() as u8 | (object.props.has_osids() as u8) << 1;
+            let mut body = object.id.to_le_bytes().to_vec();
+            if self.file_type == FILE_TYPE_ONE {
+                body.extend_from_slice(&object.jcid.to_le_bytes());
+                body.extend_from_slice(&[flags, 1]);
+                declarations.push(data_node(0xA4, property_set, &body));
+            } else {
+                body.extend_from_slice(&((object.jcid & 0x3FF) as u16).to_le_bytes());
+                body.extend_from_slice(&(flags as u32).to_le_bytes());
+                body.push(1);
+                declarations.push(data_node(0x2D, property_set, &body));
+            }
+        }
+
+        let mut revision_list = Vec::new();
+        let mut start = rid.clone();
+        start.extend(exguid([0; 16], 0));
+        if self.file_type == FILE_TYPE_ONE {
+            start.extend_from_slice(&ROLE_DEFAULT_CONTENT.to_le_bytes());
+            start.extend_from_slice(&0u16.to_le_bytes());
+            revision_list.push(node(0x1E, &start));
+
+            let group_id = exguid(space.gosid, 3);
+            let mut group = vec![node(0xB4, &group_id), node(0x22, &[])];
+            group.extend(global_id_table);
+            group.push(node(0x28, &[]));
+            group.extend(declarations);
+            group.push(node(0xB8, &[]));
+            let group_list = self.list(&group);
+            revision_list.push(reference_node(0xB0, group_list.0, group_list.1, &group_id));
+            for (role, id) in &space.roots {
+                let mut root = resolve(*id);
+                root.extend_from_slice(&role.to_le_bytes());
+                revision_list.push(node(0x5A, &root));
+            }
+        } else {
+            start.extend_from_slice(&0u64.to_le_bytes());
+            start.extend_from_slice(&ROLE_DEFAULT_CONTENT.to_le_bytes());
+            start.extend_from_slice(&0u16.to_le_bytes());
+            revision_list.push(node(0x1B, &start));
+            revision_list.push(node(0x21, &[0]));
+            revision_list.extend(global_id_table);
+            revision_list.push(node(0x28, &[]));
+            revision_list.extend(declarations);
+            for (role, id) in &space.roots {
+                let mut root = id.to_le_bytes().to_vec();
+                root.extend_from_slice(&role.to_le_bytes());
+                revision_list.push(node(0x59, &root));
+            }
+        }
+        revision_list.push(node(0x1C, &[]));
+        self.revisions(space.gosid, &revision_list)
+    }
+
+    // An object space whose revision manifest list holds nodes after its start node
+    pub fn revisions(&mut self, gosid: [u8; 16], nodes: &[Vec<u8>]) -> &mut StoreBuilder {
+        self.revision_lists(gosid, &[nodes])
+    }
+
+    // An object space whose manifest list references a revision manifest list for each of lists,
+    // in order, each holding its nodes after its start node
+    pub fn revision_lists(&mut self, gosid: [u8; 16], lists: &[&[Vec<u8>]]) -> &mut StoreBuilder {
+        let mut space_list = vec![node(0x0C, &exguid(gosid, 1))];
+        for nodes in lists {
+            let mut start = exguid(gosid, 1);
+            start.extend_from_slice(&0u32.to_le_bytes());
+            let mut revision_list = vec![node(0x14, &start)];
+            revision_list.extend_from_slice(nodes);
+            let revision_list = self.list(&revision_list);
+            space_list.push(reference_node(0x10, revision_list.0, revision_list.1, &[]));
+        }
+
+        let space_list = self.list(&space_list);
+        self.spaces.push((gosid, space_list));
+        self
+    }
+
+    // FileDataStoreObjects for each (guid, data), in one file data store list
+    pub fn file_data(&mut self, objects: &[([u8; 16], &[u8])]) -> &mut StoreBuilder {
+        let mut nodes = Vec::new();
+        for (guid, data) in objects {
+            let object = self.chunk(&file_data_store_object(data));
+            nodes.push(data_node(0x94, object, guid));
+        }
+        self.file_data_store = Some(self.list(&nodes));
+        self
+    }
+
+    pub fn build(&mut self) -> Vec<u8> {
+        let mut root = Vec::new();
+        for (gosid, list) in &self.spaces {
+            root.push(reference_node(0x08, list.0, list.1, &exguid(*gosid, 1)));
+        }
+        root.push(node(0x04, &exguid(self.spaces[0].0, 1)));
+        if let Some(list) = self.file_data_store {
+            root.push(reference_node(0x90, list.0, list.1, &[]));
+        }
+        let root = self.list(&root);
+
+        let log = transaction_log(&self.list_counts);
+        let log = self.chunk(&log);
+        let mut file = std::mem::take(&mut self.bytes);
+        let length = file.len() as u64;
+        file[..HEADER_SIZE].copy_from_slice(&header_for(self.file_type, log, root, length));
+        file
+    }
+}
+
+pub const SECTION_GOSID: [u8; 16] = guid_bytes(0x5EC710A0, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+
+// JCIDs and property ids used by the object builders, from MS-ONE 2.2 and 2.1.12
+pub const JCID_SECTION_NODE: u32 = 0x00060007;
+pub const JCID_PAGE_SERIES_NODE: u32 = 0x00060008;
+pub const JCID_PAGE_NODE: u32 = 0x0006000B;
+pub const JCID_OUTLINE_NODE: u32 = 0x0006000C;
+pub const JCID_OUTLINE_ELEMENT_NODE: u32 = 0x0006000D;
+pub const JCID_RICH_TEXT_OE_NODE: u32 = 0x0006000E;
+pub const JCID_TITLE_NODE: u32 = 0x0006002C;
+pub const JCID_PAGE_META_DATA: u32 = 0x00020030;
+pub const JCID_SECTION_META_DATA: u32 = 0x00020031;
+pub const JCID_PARAGRAPH_STYLE_OBJECT: u32 = 0x0012004D;
+pub const JCID_TOC_CONTAINER: u32 = 0x00020001;
+
+pub const ELEMENT_CHILD_NODES: u32 = 0x24001C20;
+pub const CONTENT_CHILD_NODES: u32 = 0x24001C1F;
+pub const STRUCTURE_ELEMENT_CHILD_NODES: u32 = 0x24001D5F;
+pub const CHILD_GRAPH_SPACE_ELEMENT_NODES: u32 = 0x2C001D63;
+pub const SECTION_DISPLAY_NAME: u32 = 0x1C00349B;
+pub const CACHED_TITLE_STRING: u32 = 0x1C001CF3;
+pub const PAGE_LEVEL: u32 = 0x14001DFF;
+pub const PAGE_WIDTH: u32 = 0x14001C01;
+pub const OFFSET_FROM_PARENT_HORIZ: u32 = 0x14001C14;
+pub const OFFSET_FROM_PARENT_VERT: u32 = 0x14001C15;
+pub const OUTLINE_ELEMENT_CHILD_LEVEL: u32 = 0x0C001C03;
+pub const TEXT_EXTENDED_ASCII: u32 = 0x1C003498;
+pub const RICH_EDIT_TEXT_UNICODE: u32 = 0x1C001C22;
+pub const TEXT_RUN_INDEX: u32 = 0x1C001E12;
+pub const TEXT_RUN_FORMATTING: u32 = 0x24001E13;
+pub const PARAGRAPH_STYLE: u32 = 0x2000342C;
+pub const PARAGRAPH_STYLE_ID: u32 = 0x1C00345A;
+pub const BOLD: u32 = 0x08001C04;
+pub const ITALIC: u32 = 0x08001C05;
+pub const UNDERLINE: u32 = 0x08001C06;
+pub const FONT: u32 = 0x1C001C0A;
+pub const FONT_SIZE: u32 = 0x10001C0B;
+pub const FONT_COLOR: u32 = 0x14001C0C;
+pub const HYPERLINK: u32 = 0x08001E14;
+pub const WZ_HYPERLINK_URL: u32 = 0x1C001E20;
+pub const LANGUAGE_ID: u32 = 0x14001C3B;
+pub const TOC_CHILDREN: u32 = 0x24001CF6;
+pub const FOLDER_CHILD_FILENAME: u32 = 0x1C001D6B;
+pub const NOTEBOOK_ELEMENT_ORDERING_ID: u32 = 0x14001CB9;
+pub const NOTEBOOK_COLOR: u32 = 0x14001CBE;
+
+// A page's object space whose page node is the object with CompactID n = 1. Its metadata
+// caches title
+pub fn page_space(gosid: [u8; 16], title: &str, mut objects: Vec<Object>) -> Space {
+    objects.push(Object { id: compact_id(0xFF, 0), jcid: JCID_PAGE_META_DATA, props: Props::new().string(CACHED_TITLE_STRING, title).u32(PAGE_LEVEL, 1) });
+    Space { gosid, guids: vec![gosid], objects, roots: vec![(1, compact_id(1, 0)), (2, compact_id(0xFF, 0))] }
+}
+
+// A .one file whose section holds one page series with a page for each space in pages
+pub fn section_store(name: &str, pages: &[Space]) -> Vec<u8> {
+    let mut guids = vec![SECTION_GOSID];
+    guids.extend(pages.iter().map(|page| page.gosid));
+    let page_ids: Vec<u32> = (1..guids.len() as u32).map(|index| compact_id(1, index)).collect();
+    let section = Space {
+        gosid: SECTION_GOSID,
+        guids,
+        objects: vec![
+            Object { id: compact_id(1, 0), jcid: JCID_SECTION_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(2, 0)]) },
+            Object { id: compact_id(2, 0), jcid: JCID_PAGE_SERIES_NODE, props: Props::new().object_space_ids(CHILD_GRAPH_SPACE_ELEMENT_NODES, &page_ids) },
+            Object { id: compact_id(3, 0), jcid: JCID_SECTION_META_DATA, props: Props::new().string(SECTION_DISPLAY_NAME, name) }
+        ],
+        roots: vec![(1, compact_id(1, 0)), (2, compact_id(3, 0))]
+    };
+
+    let mut builder = StoreBuilder::one();
+    builder.space(&section);
+    for page in pages {
+        builder.space(page);
+    }
+    builder.build()
+}
+
+// A .onetoc2 file listing entries of (file name, ordering id)
+pub fn toc_store(entries: &[(&str, Option<u32>)]) -> Vec<u8> {
+    let mut objects = Vec::new();
+    let mut children = Vec::new();
+    for (index, (filename, ordering_id)) in entries.iter().enumerate() {
+        let id = compact_id(index as u8 + 2, 0);
+        let mut props = Props::new().string(FOLDER_CHILD_FILENAME, filename);
+        if let Some(ordering_id) = ordering_id {
+            props = props.u32(NOTEBOOK_ELEMENT_ORDERING_ID, *ordering_id);
+        }
+        objects.push(Object { id, jcid: JCID_TOC_CONTAINER, props });
+        children.push(id);
+    }
+    objects.push(Object { id: compact_id(1, 0), jcid: JCID_TOC_CONTAINER, props: Props::new().object_ids(TOC_CHILDREN, &children) });
+
+    let toc = Space { gosid: SECTION_GOSID, guids: vec![SECTION_GOSID], objects, roots: vec![(1, compact_id(1, 0))] };
+    StoreBuilder::onetoc2().space(&toc).build()
+}
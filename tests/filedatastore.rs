@@ -0,0 +1,54 @@
+mod common;
+
+use std::io::{Cursor, Read};
+
+use onernote::onenote::{FileDataLocation, FileDataReference};
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filedatastore::FileDataStoreObject;
+use onernote::structs::guid::Guid;
+use onernote::Error;
+
+fn read_object(file: &[u8], start: u64) -> onernote::Result<FileDataStoreObject> {
+    let fcr = FileChunkReference { start, len: file.len() as u64 - start };
+    FileDataStoreObject::from_reader(Guid::nil(), &fcr, &mut Cursor::new(file))
+}
+
+#[test]
+fn data_is_streamed_without_padding() {
+    let mut file = vec![0xCC; 8];
+    file.extend(common::file_data_store_object(b"hello, world"));
+
+    let object = read_object(&file, 8).unwrap();
+    assert_eq!(object.len, 12);
+
+    let mut cursor = Cursor::new(&file);
+    let mut data = Vec::new();
+    object.open(&mut cursor).unwrap().read_to_end(&mut data).unwrap();
+    assert_eq!(data, b"hello, world");
+}
+
+#[test]
+fn bad_footer_guid() {
+    let mut file = common::file_data_store_object(b"data");
+    let footer = file.len() - 16;
+    file[footer] ^= 0xFF;
+    assert!(matches!(read_object(&file, 0), Err(Error::BadGuid { .. })));
+}
+
+#[test]
+fn length_beyond_chunk() {
+    let mut file = common::file_data_store_object(b"data");
+    file[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(read_object(&file, 0), Err(Error::InvalidData { .. })));
+}
+
+#[test]
+fn file_data_reference_locations() {
+    let reference = |reference: &str| FileDataReference { object_id: ExGuid::nil(), reference: reference.to_string(), extension: ".png".to_string() };
+    let guid = Guid::parse_str("BDE316E7-2665-4511-A4C4-8D4D0B7A9EAC").unwrap();
+
+    assert_eq!(reference("<ifndf>{BDE316E7-2665-4511-A4C4-8D4D0B7A9EAC}").location(), FileDataLocation::FileDataStore(guid));
+    assert_eq!(reference("<file>image.png").location(), FileDataLocation::External("image.png".to_string()));
+    assert_eq!(reference("<invfdo>").location(), FileDataLocation::Invalid);
+}
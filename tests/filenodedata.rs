@@ -0,0 +1,165 @@
+use std::io::Cursor;
+
+use onernote::structs::compactid::CompactId;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filenode::FileType;
+use onernote::structs::filenodedata::*;
+use onernote::structs::guid::Guid;
+
+fn id(n: u8, guid_index: u32) -> CompactId {
+    CompactId { n, guid_index }
+}
+
+fn exguid(byte: u8, n: u32) -> ExGuid {
+    ExGuid { guid: Guid::from_bytes_le([byte; 16]), n }
+}
+
+// Decodes the body of a node without a reference, checking all of bytes is read, that every
+// shorter prefix is an error and that writing the result gives bytes back
+fn decode(file_type: FileType, bytes: &[u8]) -> FileNodeData {
+    let fcr = FileChunkReference::nil();
+    let mut reader = Cursor::new(bytes);
+    let data = FileNodeData::from_reader(file_type, &fcr, &mut reader).unwrap();
+    assert_eq!(reader.position(), bytes.len() as u64);
+    for len in 0..bytes.len() {
+        assert!(FileNodeData::from_reader(file_type, &fcr, &mut Cursor::new(&bytes[..len])).is_err(), "{} bytes", len);
+    }
+
+    let mut written = Vec::new();
+    data.to_writer(&fcr, &mut written).unwrap();
+    assert_eq!(written, bytes);
+    data
+}
+
+#[test]
+fn revision_manifest_start7() {
+    let bytes = [
+        // rid, ridDependent
+        [0x11; 16].as_slice(), &[0x01, 0x00, 0x00, 0x00],
+        &[0x22; 16], &[0x02, 0x00, 0x00, 0x00],
+        // RevisionRole, odcsDefault
+        &[0x01, 0x00, 0x00, 0x00],
+        &[0x02, 0x00],
+        // gctxid
+        &[0x33; 16], &[0x03, 0x00, 0x00, 0x00]
+    ].concat();
+
+    match decode(FileType::RevisionManifestStart7, &bytes) {
+        FileNodeData::RevisionManifestStart7(start) => {
+            assert_eq!(start.base.rid, exguid(0x11, 1));
+            assert_eq!(start.base.rid_dependent, exguid(0x22, 2));
+            assert_eq!(start.base.revision_role, 1);
+            assert_eq!(start.base.odcs_default, 2);
+            assert_eq!(start.gctxid, exguid(0x33, 3));
+        },
+        other => panic!("decoded as {:?}", other)
+    }
+}
+
+const OVERRIDE_DATA: &[u8] = &[
+    // c8BitOverrides, c32BitOverrides, crc
+    0x02, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00,
+    0x78, 0x56, 0x34, 0x12,
+    // Overrides1: CompactIDs with one byte ref counts
+    0x01, 0x00, 0x00, 0x00, 0x07,
+    0x02, 0x05, 0x00, 0x00, 0xFF,
+    // Overrides2: a CompactID with a four byte ref count
+    0x03, 0x01, 0x00, 0x00, 0x2C, 0x01, 0x00, 0x00
+];
+
+fn check_overrides(data: &FileNodeData) {
+    match data {
+        FileNodeData::ObjectInfoDependencyOverrides(overrides) => {
+            let data = &overrides.data;
+            assert_eq!(data.crc, 0x12345678);
+            let overrides_1: Vec<(CompactId, u8)> = data.overrides_1.iter().map(|o| (o.oid, o.c_ref)).collect();
+            assert_eq!(overrides_1, vec![(id(1, 0), 7), (id(2, 5), 0xFF)]);
+            let overrides_2: Vec<(CompactId, u32)> = data.overrides_2.iter().map(|o| (o.oid, o.c_ref)).collect();
+            assert_eq!(overrides_2, vec![(id(3, 1), 300)]);
+        },
+        other => panic!("decoded as {:?}", other)
+    }
+}
+
+#[test]
+fn object_info_dependency_overrides() {
+    check_overrides(&decode(FileType::ObjectInfoDependencyOverrides, OVERRIDE_DATA));
+
+    // With a reference the data is read from the referenced chunk and the node body is empty
+    let mut file = OVERRIDE_DATA.to_vec();
+    file.extend_from_slice(&[0; 8]);
+    let fcr = FileChunkReference { start: 0, len: OVERRIDE_DATA.len() as u64 };
+    let mut reader = Cursor::new(&file);
+    reader.set_position(OVERRIDE_DATA.len() as u64);
+    let data = FileNodeData::from_reader(FileType::ObjectInfoDependencyOverrides, &fcr, &mut reader).unwrap();
+    assert_eq!(reader.position(), OVERRIDE_DATA.len() as u64);
+    check_overrides(&data);
+
+    let mut written = Vec::new();
+    data.to_writer(&fcr, &mut written).unwrap();
+    assert!(written.is_empty());
+}
+
+#[test]
+fn object_declaration_file_data3_large_ref_count() {
+    let bytes = [
+        // oid, jcid, cRef
+        [0x04, 0x02, 0x00, 0x00].as_slice(),
+        &[0x35, 0x00, 0x06, 0x00],
+        &[0x00, 0x00, 0x01, 0x00],
+        // FileDataReference and Extension, as StringInStorageBuffers
+        &[0x07, 0x00, 0x00, 0x00], b"<\0f\0i\0l\0e\0>\0\xE9\0",
+        &[0x04, 0x00, 0x00, 0x00], b".\0p\0n\0g\0"
+    ].concat();
+
+    match decode(FileType::ObjectDeclarationFileData3LargeRefCount, &bytes) {
+        FileNodeData::ObjectDeclarationFileData3LargeRefCount(declaration) => {
+            assert_eq!(declaration.oid, id(4, 2));
+            assert_eq!(declaration.jcid.0, 0x00060035);
+            assert_eq!(declaration.c_ref, 0x10000);
+            assert_eq!(declaration.file_data_reference, "<file>é");
+            assert_eq!(declaration.extension, ".png");
+        },
+        other => panic!("decoded as {:?}", other)
+    }
+}
+
+#[test]
+fn read_only_object_declaration2_large_ref_count() {
+    let md5: Vec<u8> = (0..16).collect();
+    let bytes = [
+        // oid, jcid, fHasOidReferences and fHasOsidReferences, cRef
+        [0x01, 0x03, 0x00, 0x00].as_slice(),
+        &[0x0E, 0x00, 0x06, 0x00],
+        &[0x03],
+        &[0x00, 0x01, 0x00, 0x00],
+        &md5
+    ].concat();
+
+    match decode(FileType::ReadOnlyObjectDeclaration2LargeRefCount, &bytes) {
+        FileNodeData::ReadOnlyObjectDeclaration2LargeRefCount(declaration) => {
+            let base = &declaration.base;
+            assert_eq!(base.body.oid, id(1, 3));
+            assert_eq!(base.body.jcid.0, 0x0006000E);
+            assert!(base.body.has_oid_references && base.body.has_osid_references);
+            assert_eq!(base.c_ref, 256);
+            assert_eq!(declaration.md5_hash.as_slice(), md5);
+        },
+        other => panic!("decoded as {:?}", other)
+    }
+}
+
+#[test]
+fn file_data_store_object_reference() {
+    let bytes = [0x67, 0x45, 0x23, 0x01, 0xAB, 0x89, 0xEF, 0xCD, 0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF];
+
+    match decode(FileType::FileDataStoreObjectReference, &bytes) {
+        FileNodeData::FileDataStoreObjectReference(reference) => {
+            assert_eq!(reference.guid_reference, Guid::from_bytes_le(bytes));
+            assert_eq!(reference.guid_reference.to_string(), "01234567-89ab-cdef-0123-456789abcdef");
+        },
+        other => panic!("decoded as {:?}", other)
+    }
+}
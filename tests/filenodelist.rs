@@ -0,0 +1,138 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::{exguid, fragment, node, GOSID};
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filenodedata::FileNodeData;
+use onernote::structs::filenodelist::FileNodeList;
+use onernote::structs::transactionlog::TransactionLog;
+use onernote::Error;
+
+const LIST_ID: u32 = 0x10;
+
+// ObjectSpaceManifestListStartFND, told apart by the n of their ExGUIDs
+fn numbered_node(n: u32) -> Vec<u8> {
+    node(0x0C, &exguid(GOSID, n))
+}
+
+fn chunk_terminator() -> Vec<u8> {
+    node(0xFF, &[])
+}
+
+fn log(count: u32) -> TransactionLog {
+    TransactionLog::from([(LIST_ID, count)])
+}
+
+fn fcr((start, len): (u64, u32)) -> FileChunkReference {
+    FileChunkReference { start, len: len as u64 }
+}
+
+// Builds a fragment given where every fragment starts and how long it is
+type FragmentBuilder<'a> = &'a dyn Fn(&[(u64, u32)]) -> Vec<u8>;
+
+// Fragments laid out one after another from offset 0. Each builder sees the layout of all of them, so
+// fragments can point at each other
+fn fragments(builders: &[FragmentBuilder]) -> (Vec<u8>, Vec<(u64, u32)>) {
+    // Fragment sizes don't depend on where nextFragment points, so a first pass finds the layout
+    let placeholder = vec![(0, 0); builders.len()];
+    let mut layout = Vec::new();
+    let mut start = 0;
+    for build in builders {
+        let len = build(&placeholder).len();
+        layout.push((start, len as u32));
+        start += len as u64;
+    }
+
+    let file = builders.iter().flat_map(|build| build(&layout)).collect();
+    (file, layout)
+}
+
+fn n_of(list: &FileNodeList) -> Vec<u32> {
+    list.file_nodes.iter().map(|file_node| match &file_node.data {
+        FileNodeData::ObjectSpaceManifestListStart(start) => start.gosid.n,
+        other => panic!("unexpected {:?}", other)
+    }).collect()
+}
+
+fn read(file: &[u8], first: (u64, u32), count: u32) -> onernote::Result<FileNodeList> {
+    FileNodeList::from_reader(&fcr(first), &mut Cursor::new(file), &log(count))
+}
+
+#[test]
+fn list_is_read_across_fragments() {
+    let (file, layout) = fragments(&[
+        &|layout| fragment(LIST_ID, 0, &[numbered_node(1), numbered_node(2)], Some(layout[1])),
+        &|layout| fragment(LIST_ID, 1, &[numbered_node(3)], Some(layout[2])),
+        &|_| fragment(LIST_ID, 4, &[numbered_node(4)], None)
+    ]);
+
+    let (list, tail) = FileNodeList::from_reader_with_tail(&fcr(layout[0]), &mut Cursor::new(&file), &log(4)).unwrap();
+    assert_eq!(list.id, LIST_ID);
+    assert_eq!(list.fragment_sequence_index, 0);
+    assert_eq!(n_of(&list), vec![1, 2, 3, 4]);
+    assert_eq!(list.file_nodes[2].offset, layout[1].0 + 16);
+
+    assert_eq!(tail.fragment, fcr(layout[2]));
+    assert_eq!(tail.sequence_index, 4);
+    assert_eq!(tail.end_of_file_nodes, layout[2].0 + 40);
+}
+
+#[test]
+fn chunk_terminator_moves_to_the_next_fragment() {
+    // The rest of the first fragment after the terminator is unused and isn't read
+    let (file, layout) = fragments(&[
+        &|layout| fragment(LIST_ID, 0, &[numbered_node(1), chunk_terminator(), vec![0xEE; 24]], Some(layout[1])),
+        &|_| fragment(LIST_ID, 1, &[numbered_node(2)], None)
+    ]);
+
+    let list = read(&file, layout[0], 2).unwrap();
+    assert_eq!(n_of(&list), vec![1, 2]);
+    assert_eq!(list.file_nodes[1].offset, layout[1].0 + 16);
+}
+
+#[test]
+fn fragment_with_another_list_id_is_an_error() {
+    let (file, layout) = fragments(&[
+        &|layout| fragment(LIST_ID, 0, &[numbered_node(1)], Some(layout[1])),
+        &|_| fragment(LIST_ID + 1, 1, &[numbered_node(2)], None)
+    ]);
+
+    match read(&file, layout[0], 2) {
+        Err(Error::InvalidData { structure: "FileNodeListFragment", offset, reason }) => {
+            assert_eq!(offset, layout[1].0);
+            assert!(reason.contains("different list id"), "{}", reason);
+        },
+        other => panic!("expected a list id mismatch, got {:?}", other)
+    }
+}
+
+#[test]
+fn sequence_index_has_to_increase() {
+    let (file, layout) = fragments(&[
+        &|layout| fragment(LIST_ID, 3, &[numbered_node(1)], Some(layout[1])),
+        &|_| fragment(LIST_ID, 3, &[numbered_node(2)], None)
+    ]);
+
+    match read(&file, layout[0], 2) {
+        Err(Error::InvalidData { structure: "FileNodeListFragment", offset, reason }) => {
+            assert_eq!(offset, layout[1].0);
+            assert!(reason.contains("not increasing"), "{}", reason);
+        },
+        other => panic!("expected a sequence index error, got {:?}", other)
+    }
+}
+
+#[test]
+fn fragment_chain_that_loops_is_an_error() {
+    // The second fragment links back to the first, which would be read forever without a check
+    let (file, layout) = fragments(&[
+        &|layout| fragment(LIST_ID, 0, &[numbered_node(1)], Some(layout[1])),
+        &|layout| fragment(LIST_ID, 1, &[numbered_node(2)], Some(layout[0]))
+    ]);
+
+    match read(&file, layout[0], 3) {
+        Err(Error::InvalidData { structure: "FileNodeListFragment", offset: 0, reason }) => assert!(reason.contains("not increasing"), "{}", reason),
+        other => panic!("expected the loop to be rejected, got {:?}", other)
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use std::io::Cursor;
+
+use onernote::commit::{Commit, NewRevision};
+use onernote::structs::crc::crc32;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::revision::REVISION_ROLE_DEFAULT_CONTENT;
+use onernote::{Error, OneStore};
+
+fn free_chunk_fragment(next: Option<(u64, u32)>, chunks: &[(u64, u64)]) -> Vec<u8> {
+    let mut chunk_bytes = Vec::new();
+    for (start, len) in chunks {
+        chunk_bytes.extend_from_slice(&start.to_le_bytes());
+        chunk_bytes.extend_from_slice(&len.to_le_bytes());
+    }
+    let (next_start, next_len) = next.unwrap_or((u64::MAX, 0));
+
+    let mut bytes = crc32(&chunk_bytes).to_le_bytes().to_vec();
+    bytes.extend_from_slice(&next_start.to_le_bytes());
+    bytes.extend_from_slice(&next_len.to_le_bytes());
+    bytes.extend(chunk_bytes);
+    bytes
+}
+
+// Appends the fragments to the minimal store and points fcrFreeChunkList at the first one
+fn store_with_free_chunks(fragments: impl FnOnce(u64) -> Vec<Vec<u8>>) -> Vec<u8> {
+    let mut file = common::minimal_store();
+    let start = file.len() as u64;
+    let fragments = fragments(start);
+    file[184..192].copy_from_slice(&start.to_le_bytes());
+    file[192..196].copy_from_slice(&(fragments[0].len() as u32).to_le_bytes());
+    for fragment in fragments {
+        file.extend(fragment);
+    }
+    let file_length = file.len() as u64;
+    file[196..204].copy_from_slice(&file_length.to_le_bytes());
+    file
+}
+
+#[test]
+fn fragments_are_followed() {
+    let file = store_with_free_chunks(|start| {
+        let first = free_chunk_fragment(Some((start + 48, 32)), &[(0x900, 0x40), (0x2000, 0x10)]);
+        let second = free_chunk_fragment(None, &[(0x500, 0x20)]);
+        vec![first, second]
+    });
+
+    let store = OneStore::parse(&mut Cursor::new(file)).unwrap();
+    let free = &store.free_chunk_list;
+    assert_eq!(free.free_chunks.iter().map(|chunk| chunk.start).collect::<Vec<_>>(), vec![0x500, 0x900, 0x2000]);
+    assert_eq!(free.total_len(), 0x70);
+    assert_eq!(free.find(0x20), Some(&FileChunkReference { start: 0x500, len: 0x20 }));
+    assert_eq!(free.find(0x100), None);
+}
+
+#[test]
+fn crc_mismatch() {
+    let mut file = store_with_free_chunks(|_| vec![free_chunk_fragment(None, &[(0x900, 0x40)])]);
+    let last = file.len() - 1;
+    file[last] ^= 0xFF;
+
+    assert!(matches!(OneStore::parse(&mut Cursor::new(file)), Err(Error::CrcMismatch { structure: "FreeChunkListFragment", .. })));
+}
+
+#[test]
+fn commits_allocate_from_free_chunks() {
+    // A 0x100 byte free chunk right after the free chunk list's only fragment
+    let file = store_with_free_chunks(|start| vec![free_chunk_fragment(None, &[(start + 32, 0x100)]), vec![0; 0x100]]);
+    let mut file = Cursor::new(file);
+    let store = OneStore::parse(&mut file).unwrap();
+    let free_chunk = store.free_chunk_list.free_chunks[0];
+
+    let mut commit = Commit::new(&store, &mut file).unwrap();
+    let fcr = commit.write_chunk(&[0xAB; 10]).unwrap();
+    assert_eq!(fcr, FileChunkReference { start: free_chunk.start, len: 10 });
+    let gosid = store.root_object_space_id;
+    let rid = store.root_object_space().unwrap().latest_revision().unwrap().rid;
+    commit.add_revision(&gosid, NewRevision {
+        rid: ExGuid { n: rid.n + 1, ..rid },
+        rid_dependent: rid,
+        context: ExGuid::nil(),
+        revision_role: REVISION_ROLE_DEFAULT_CONTENT,
+        odcs_default: 0,
+        object_groups: Vec::new(),
+        file_nodes: Vec::new()
+    }).unwrap();
+    commit.finish().unwrap();
+
+    // The rewritten free chunk list no longer has the 16 bytes the chunk was rounded up to, nor
+    // the revision's fragment
+    let store = OneStore::parse(&mut file).unwrap();
+    let free = &store.free_chunk_list;
+    assert_eq!(free.total_len(), store.header.free_space_in_free_chunk_list);
+    assert!(free.total_len() < 0x100 - 16);
+    assert!(free.free_chunks.iter().all(|chunk| chunk.start >= fcr.start + 16));
+    assert_eq!(&file.get_ref()[fcr.start as usize..][..10], &[0xAB; 10]);
+}
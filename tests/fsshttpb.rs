@@ -0,0 +1,168 @@
+mod common;
+
+use std::io::Cursor;
+
+use onernote::fsshttpb::types::{read_compact_u64, read_exguid};
+use onernote::structs::compactid::CompactId;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::guid::Guid;
+use onernote::structs::revision::ROOT_ROLE_DEFAULT_CONTENT;
+use onernote::{OneStore, StoreFormat};
+
+const FILE_FORMAT_PACKAGING: [u8; 16] = [0x2F, 0xE9, 0x8D, 0x63, 0xD4, 0xA6, 0xC1, 0x4B, 0x9A, 0x36, 0xB3, 0xFC, 0x25, 0x11, 0xA5, 0xB7];
+const DATA_ROOT: [u8; 16] = [0xB9, 0xFA, 0xDE, 0x84, 0xA3, 0xAA, 0x0D, 0x4A, 0xA3, 0xA8, 0x52, 0x0C, 0x77, 0xAC, 0x70, 0x73];
+const ROOT_ID: [u8; 16] = [0xF8, 0x17, 0x37, 0x4A, 0x14, 0x1C, 0xE7, 0x49, 0x95, 0x26, 0x81, 0xD9, 0x42, 0xDE, 0x17, 0x41];
+const ELEMENT: [u8; 16] = [0xEE; 16];
+const OBJECT: [u8; 16] = [0x0B; 16];
+
+fn guid(bytes: [u8; 16]) -> Guid {
+    Guid::from_bytes_le(bytes)
+}
+
+// 16 bit stream object start header
+fn start(object_type: u16) -> Vec<u8> {
+    ((object_type << 3) | 0x4).to_le_bytes().to_vec()
+}
+
+fn end(object_type: u16) -> Vec<u8> {
+    vec![((object_type << 2) | 0x1) as u8]
+}
+
+fn compact(value: u8) -> Vec<u8> {
+    vec![(value << 1) | 1]
+}
+
+// The 5 byte form of a compact ExGuid with a GUID
+fn exguid(guid: [u8; 16], n: u32) -> Vec<u8> {
+    let mut bytes = vec![0x80];
+    bytes.extend_from_slice(&n.to_le_bytes());
+    bytes.extend_from_slice(&guid);
+    bytes
+}
+
+fn cell(object_space: [u8; 16]) -> Vec<u8> {
+    let mut bytes = vec![0];
+    bytes.extend(exguid(object_space, 1));
+    bytes
+}
+
+fn data_element(n: u32, element_type: u8, body: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = start(0x01);
+    bytes.extend(exguid(ELEMENT, n));
+    bytes.push(0);
+    bytes.extend(compact(element_type));
+    bytes.extend(body.concat());
+    bytes.extend(end(0x01));
+    bytes
+}
+
+fn object_data(object_ids: &[Vec<u8>], data: &[u8]) -> Vec<u8> {
+    let mut bytes = start(0x16);
+    bytes.extend(compact(object_ids.len() as u8));
+    bytes.extend(object_ids.concat());
+    bytes.extend(compact(0));
+    bytes.extend(compact(data.len() as u8));
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn declaration(partition: u8, references: u8) -> Vec<u8> {
+    [start(0x18), exguid(OBJECT, 1), compact(partition), compact(0), compact(references), compact(0)].concat()
+}
+
+// A section with one page object whose only property refers to another object
+fn package() -> Vec<u8> {
+    let storage_index = data_element(1, 0x01, &[
+        start(0x11), exguid(ELEMENT, 2), vec![0],
+        start(0x0E), cell(common::GOSID), exguid(ELEMENT, 3), vec![0],
+        start(0x0D), exguid(common::RID, 1), exguid(ELEMENT, 4), vec![0]
+    ]);
+    let storage_manifest = data_element(2, 0x02, &[
+        start(0x0C), vec![0; 16],
+        start(0x07), exguid(DATA_ROOT, 2), cell(common::GOSID)
+    ]);
+    let cell_manifest = data_element(3, 0x03, &[start(0x0B), exguid(common::RID, 1)]);
+    let revision_manifest = data_element(4, 0x04, &[
+        start(0x1A), exguid(common::RID, 1), vec![0],
+        start(0x0A), exguid(ROOT_ID, 1), exguid(OBJECT, 1),
+        start(0x19), exguid(ELEMENT, 5)
+    ]);
+
+    // One ObjectID property, whose CompactId refers to the object listed with the data
+    let mut property_set = (1u32 | (1 << 31)).to_le_bytes().to_vec();
+    property_set.extend_from_slice(&0x0102u32.to_le_bytes());
+    property_set.extend_from_slice(&1u16.to_le_bytes());
+    property_set.extend_from_slice(&(0x1C00u32 | (0x8 << 26)).to_le_bytes());
+    let object_group = data_element(5, 0x05, &[
+        start(0x1D), declaration(4, 0), declaration(1, 1), end(0x1D),
+        start(0x1E),
+        object_data(&[], &0x00060007u32.to_le_bytes()),
+        object_data(&[exguid(common::RID, 9)], &property_set),
+        end(0x1E)
+    ]);
+
+    let mut file = common::FILE_TYPE_ONE.to_vec();
+    file.extend_from_slice(&[0x42; 16]);
+    file.extend_from_slice(&[0; 16]);
+    file.extend_from_slice(&FILE_FORMAT_PACKAGING);
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&(0x2u32 | 0x4 | (0x7A << 3)).to_le_bytes());
+    file.extend(exguid(ELEMENT, 1));
+    file.extend_from_slice(&[0; 16]);
+    file.extend(start(0x15));
+    file.push(0);
+    for element in [storage_index, storage_manifest, cell_manifest, revision_manifest, object_group] {
+        file.extend(element);
+    }
+    file.extend(end(0x15));
+    file.extend_from_slice(&((0x7Au16 << 2) | 0x3).to_le_bytes());
+    file
+}
+
+#[test]
+fn compact_encodings() {
+    assert_eq!(read_compact_u64(&mut Cursor::new([0x00])).unwrap(), 0);
+    assert_eq!(read_compact_u64(&mut Cursor::new([0x0F])).unwrap(), 7);
+    assert_eq!(read_compact_u64(&mut Cursor::new([0x06, 0x01])).unwrap(), 0x41);
+    assert_eq!(read_compact_u64(&mut Cursor::new([0x80, 1, 0, 0, 0, 0, 0, 0, 1])).unwrap(), 0x0100000000000001);
+
+    assert!(read_exguid(&mut Cursor::new([0x00])).unwrap().is_nil());
+    let mut short = vec![(5 << 3) | 0x04];
+    short.extend_from_slice(&OBJECT);
+    assert_eq!(read_exguid(&mut Cursor::new(short)).unwrap(), ExGuid { guid: guid(OBJECT), n: 5 });
+    assert_eq!(read_exguid(&mut Cursor::new(exguid(OBJECT, 0x12345))).unwrap(), ExGuid { guid: guid(OBJECT), n: 0x12345 });
+}
+
+#[test]
+fn packaged_store_maps_onto_object_spaces() {
+    let file = package();
+    let mut cursor = Cursor::new(&file);
+    let store = OneStore::parse(&mut cursor).unwrap();
+
+    assert_eq!(store.format, StoreFormat::Packaged);
+    assert_eq!(store.header.file_guid, guid([0x42; 16]));
+    let gosid = ExGuid { guid: guid(common::GOSID), n: 1 };
+    assert_eq!(store.root_object_space_id, gosid);
+
+    let space = store.root_object_space().unwrap();
+    let revision = space.latest_revision().unwrap();
+    assert_eq!(revision.rid, ExGuid { guid: guid(common::RID), n: 1 });
+    let object_id = ExGuid { guid: guid(OBJECT), n: 1 };
+    assert_eq!(revision.root_objects[&ROOT_ROLE_DEFAULT_CONTENT], object_id);
+
+    let object = &revision.objects[&object_id];
+    assert_eq!(object.jcid.0, 0x00060007);
+    let property_set = object.read_property_set(&mut cursor).unwrap().unwrap();
+    assert_eq!(property_set.oids, [CompactId { n: 2, guid_index: 1 }]);
+    assert_eq!(object.resolve(property_set.oids[0]), Some(ExGuid { guid: guid(common::RID), n: 9 }));
+}
+
+#[test]
+fn missing_revision_manifest() {
+    let mut file = package();
+    // Point the revision mapping at a data element that doesn't exist
+    let mapping = exguid(ELEMENT, 4);
+    let at = file.windows(mapping.len()).position(|window| window == mapping.as_slice()).unwrap();
+    file[at + 1] = 9;
+    assert!(OneStore::parse(&mut Cursor::new(file)).is_err());
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filenode::{FileNode, FileType};
+use onernote::structs::filenodedata::*;
+use onernote::structs::globalidtable::GlobalIdTable;
+use onernote::structs::guid::Guid;
+use onernote::Error;
+
+fn guid(byte: u8) -> Guid {
+    Guid::from_bytes_le([byte; 16])
+}
+
+// Nodes are numbered by their offset so errors can be traced back to the node that caused them
+fn table_nodes(entries: Vec<(FileType, FileNodeData)>) -> Vec<FileNode> {
+    let mut nodes = vec![FileNode::new(FileType::GlobalIdTableStart2, FileChunkReference::zero(), FileNodeData::GlobalIdTableStart2)];
+    for (file_type, data) in entries {
+        nodes.push(FileNode::new(file_type, FileChunkReference::zero(), data));
+    }
+    nodes.push(FileNode::new(FileType::GlobalIdTableEnd, FileChunkReference::zero(), FileNodeData::GlobalIdTableEnd));
+    for (index, node) in nodes.iter_mut().enumerate() {
+        node.offset = index as u64;
+    }
+    nodes
+}
+
+fn entry(index: u32, byte: u8) -> (FileType, FileNodeData) {
+    (FileType::GlobalIdTableEntry, FileNodeData::GlobalIdTableEntry(GlobalIdTableEntryFndx { index, guid: guid(byte) }))
+}
+
+fn copy(index_map_from: u32, index_map_to: u32) -> (FileType, FileNodeData) {
+    (FileType::GlobalIdTableEntry2, FileNodeData::GlobalIdTableEntry2(GlobalIdTableEntry2Fndx { index_map_from, index_map_to }))
+}
+
+fn copy_range(index_copy_from_start: u32, entries_to_copy: u32, index_copy_to_start: u32) -> (FileType, FileNodeData) {
+    (FileType::GlobalIdTableEntry3, FileNodeData::GlobalIdTableEntry3(GlobalIdTableEntry3Fndx { index_copy_from_start, entries_to_copy, index_copy_to_start }))
+}
+
+// The previous revision's table: index 5 + i holds the GUID of byte 0xA0 + i
+fn dependency() -> GlobalIdTable {
+    GlobalIdTable { entries: (0..4).map(|i| (5 + i, guid(0xA0 + i as u8))).collect(), ..Default::default() }
+}
+
+fn expect_error(result: onernote::Result<(GlobalIdTable, usize)>, node: u64, expected: &str) {
+    match result {
+        Err(Error::InvalidData { structure: "GlobalIdTable", offset, reason }) => {
+            assert_eq!(offset, node, "{}", reason);
+            assert!(reason.contains(expected), "{}", reason);
+        },
+        other => panic!("expected \"{}\", got {:?}", expected, other)
+    }
+}
+
+#[test]
+fn entry2_copies_single_entries_from_the_dependency() {
+    let nodes = table_nodes(vec![copy(7, 0), entry(1, 0x01), copy(5, 2)]);
+    let (table, used) = GlobalIdTable::from_file_nodes(&nodes, Some(&dependency())).unwrap();
+
+    assert_eq!(used, 5);
+    assert_eq!(table.entries, HashMap::from([(0, guid(0xA2)), (1, guid(0x01)), (2, guid(0xA0))]));
+}
+
+#[test]
+fn entry3_copies_a_range_from_the_dependency() {
+    let nodes = table_nodes(vec![copy_range(6, 3, 10), entry(0, 0x01)]);
+    let (table, used) = GlobalIdTable::from_file_nodes(&nodes, Some(&dependency())).unwrap();
+
+    assert_eq!(used, 4);
+    assert_eq!(table.entries, HashMap::from([(0, guid(0x01)), (10, guid(0xA1)), (11, guid(0xA2)), (12, guid(0xA3))]));
+}
+
+#[test]
+fn copies_need_a_dependency_holding_the_index() {
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![copy(5, 0)]), None), 1, "has no dependency");
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![entry(0, 0x01), copy(4, 1)]), Some(&dependency())), 2, "missing from the dependency");
+    // The range runs one past the last entry of the dependency
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![copy_range(6, 4, 0)]), Some(&dependency())), 1, "missing from the dependency");
+}
+
+#[test]
+fn indices_out_of_range_are_rejected() {
+    // Indices have to fit in the 24 bits of a CompactID, less the value reserved for no GUID
+    let (table, _) = GlobalIdTable::from_file_nodes(&table_nodes(vec![entry(0xFFFFFE, 0x01)]), None).unwrap();
+    assert_eq!(table.get(0xFFFFFE), Some(&guid(0x01)));
+
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![entry(0xFFFFFF, 0x01)]), None), 1, "out of range");
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![copy(5, 0x1000000)]), Some(&dependency())), 1, "out of range");
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![copy_range(5, 2, 0xFFFFFE)]), Some(&dependency())), 1, "out of range");
+
+    // A range starting at the last u32 index would wrap around to 0
+    let last = GlobalIdTable { entries: HashMap::from([(u32::MAX, guid(0xB0))]), ..Default::default() };
+    expect_error(GlobalIdTable::from_file_nodes(&table_nodes(vec![copy_range(u32::MAX, 2, 0)]), Some(&last)), 1, "overflows");
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+
+use onernote::structs::header::name_crc;
+use onernote::{Error, OneStore};
+
+#[test]
+fn debug_fields_and_build_numbers() {
+    let mut file = common::minimal_store();
+    // grfDebugLogFlags, then bnCreated through bnNewestWritten after the two debug chunk references
+    file[252..256].copy_from_slice(&7u32.to_le_bytes());
+    for (index, build) in [4000u32, 4001, 4002, 4003].iter().enumerate() {
+        let offset = 280 + index * 4;
+        file[offset..offset + 4].copy_from_slice(&build.to_le_bytes());
+    }
+    file[120] = 1;
+
+    let store = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    let header = &store.header;
+    assert_eq!(header.debug_log_flags, 7);
+    assert!(header.debug_log.is_zero());
+    assert_eq!(header.created_build_number, 4000);
+    assert_eq!(header.last_written_build_number, 4001);
+    assert_eq!(header.newest_written_build_number, 4003);
+    assert_eq!(header.legacy_free_space_in_free_chunk_list, 1);
+    assert_eq!(header.reserved.len(), 728);
+    assert_eq!(store.file_length, file.len() as u64);
+}
+
+// crcName of a file named "Notes.one": the CRC-32 of "Notes.one" as a null terminated UTF-16LE string
+const NOTES_CRC_NAME: u32 = 0x04C5B975;
+
+#[test]
+fn crc_name() {
+    assert_eq!(name_crc("Notes.one"), NOTES_CRC_NAME);
+    assert_eq!(name_crc("Open Notebook.onetoc2"), 0x78EA71A9);
+
+    let mut file = common::minimal_store();
+    file[144..148].copy_from_slice(&NOTES_CRC_NAME.to_le_bytes());
+    let store = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    assert_eq!(store.name_matches, None);
+    assert!(store.header.verify_name("Notes.one").is_ok());
+    assert!(matches!(store.header.verify_name("Renamed.one"), Err(Error::CrcMismatch { offset: 144, .. })));
+
+    // Opening the file by path checks crcName against its name
+    let directory = std::env::temp_dir().join(format!("onernote-header-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("Notes.one"), &file).unwrap();
+    fs::write(directory.join("Renamed.one"), &file).unwrap();
+    let matches = OneStore::open(directory.join("Notes.one")).unwrap().name_matches;
+    let renamed = OneStore::open(directory.join("Renamed.one")).unwrap().name_matches;
+    fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(matches, Some(true));
+    assert_eq!(renamed, Some(false));
+}
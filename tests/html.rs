@@ -0,0 +1,96 @@
+use onernote::export::html::render_page;
+use onernote::export::Resources;
+use onernote::onenote::*;
+use onernote::structs::exguid::ExGuid;
+
+struct Names;
+
+impl Resources for Names {
+    fn link(&mut self, _data: &FileDataReference, name: Option<&str>) -> onernote::Result<Option<String>> {
+        Ok(name.map(|name| name.to_string()))
+    }
+}
+
+fn paragraph(text: RichText, list: Option<&str>) -> OutlineItem {
+    OutlineItem::Element(OutlineElement {
+        child_level: 1,
+        list: list.map(|format| List { format: format.to_string(), font: None, restart: None }),
+        contents: vec![Content::RichText(text)],
+        children: vec![]
+    })
+}
+
+fn page(contents: Vec<PageContent>) -> Page {
+    Page { id: ExGuid::nil(), title: None, cached_title: Some("<Plans>".to_string()), level: 1, width: None, height: None, contents }
+}
+
+#[test]
+fn outlines_are_positioned_absolutely() {
+    let outline = Outline {
+        offset_horizontal: Some(1.0),
+        offset_vertical: Some(2.5),
+        layout_max_width: Some(10.0),
+        layout_max_height: None,
+        items: vec![paragraph(RichText { text: "a & b".to_string(), ..Default::default() }, None)]
+    };
+    let html = render_page(&page(vec![PageContent::Outline(outline)]), &mut Names).unwrap();
+
+    assert!(html.contains("<title>&lt;Plans&gt;</title>"));
+    assert!(html.contains("<div class=\"outline\" style=\"position: absolute; left: 48px; top: 120px; width: 480px\">"));
+    assert!(html.contains("<p>a &amp; b</p>"));
+}
+
+#[test]
+fn lists_runs_and_images() {
+    let mut rich_text = RichText { text: "bold red".to_string(), ..Default::default() };
+    rich_text.runs = vec![
+        TextRun { range: 0..4, bold: true, ..Default::default() },
+        TextRun { range: 4..8, color: Some(0x0000FF), hyperlink: Some("https://example.com/?a=1&b=2".to_string()), ..Default::default() }
+    ];
+    let outline = Outline {
+        offset_horizontal: None,
+        offset_vertical: None,
+        layout_max_width: None,
+        layout_max_height: None,
+        items: vec![paragraph(rich_text, Some("\u{FFFD}")), paragraph(RichText { text: "next".to_string(), ..Default::default() }, Some("\u{FFFD}"))]
+    };
+    let image = Image {
+        data: Some(FileDataReference { object_id: ExGuid::nil(), reference: "<ifndf>{00000000-0000-0000-0000-000000000000}".to_string(), extension: ".png".to_string() }),
+        filename: Some("photo.png".to_string()),
+        alt_text: None,
+        width: Some(2.0), height: Some(1.0), offset_horizontal: Some(0.5), offset_vertical: Some(0.5), layout_max_width: None, layout_max_height: None,
+        is_background: false
+    };
+    let html = render_page(&page(vec![PageContent::Outline(outline), PageContent::Image(image)]), &mut Names).unwrap();
+
+    assert!(html.contains("<ol>\n<li><p><span style=\"font-weight: bold\">bold</span><a href=\"https://example.com/?a=1&amp;b=2\"><span style=\"color: #ff0000\"> red</span></a></p>\n</li>\n<li><p>next</p>\n</li>\n</ol>"));
+    assert!(html.contains("<img class=\"image\" src=\"photo.png\" alt=\"\" style=\"position: absolute; left: 24px; top: 24px; width: 96px; height: 48px\">"));
+}
+
+#[test]
+fn script_links_are_left_out() {
+    let mut rich_text = RichText { text: "run thisor thatmail".to_string(), ..Default::default() };
+    rich_text.runs = vec![
+        TextRun { range: 0..8, hyperlink: Some("JavaScript:alert(1)".to_string()), ..Default::default() },
+        TextRun { range: 8..15, hyperlink: Some("data:text/html,<script>alert(1)</script>".to_string()), ..Default::default() },
+        TextRun { range: 15..19, hyperlink: Some("mailto:someone@example.com".to_string()), ..Default::default() }
+    ];
+    let outline = Outline {
+        offset_horizontal: None,
+        offset_vertical: None,
+        layout_max_width: None,
+        layout_max_height: None,
+        items: vec![paragraph(rich_text, None)]
+    };
+    let attachment = EmbeddedFile {
+        data: Some(FileDataReference { object_id: ExGuid::nil(), reference: "<ifndf>{00000000-0000-0000-0000-000000000000}".to_string(), extension: ".txt".to_string() }),
+        filename: Some("javascript:alert(1)".to_string()),
+        source_path: None,
+        offset_horizontal: None, offset_vertical: None, layout_max_width: None, layout_max_height: None
+    };
+    let html = render_page(&page(vec![PageContent::Outline(outline), PageContent::EmbeddedFile(attachment)]), &mut Names).unwrap();
+
+    assert!(html.contains("<p>run thisor that<a href=\"mailto:someone@example.com\">mail</a></p>"), "{}", html);
+    assert!(html.contains("<span class=\"attachment\">javascript:alert(1)</span>"), "{}", html);
+    assert!(!html.contains("href=\"javascript") && !html.contains("href=\"data"));
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use std::io::Cursor;
+
+use onernote::export::json::{write_section, write_store, SCHEMA_VERSION};
+use onernote::onenote::*;
+use onernote::structs::exguid::ExGuid;
+use onernote::OneStore;
+
+fn to_value(write: impl FnOnce(&mut Vec<u8>) -> onernote::Result<()>) -> serde_json::Value {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    serde_json::from_slice(&out).unwrap()
+}
+
+#[test]
+fn store_uses_string_ids() {
+    let store = OneStore::parse(&mut Cursor::new(common::minimal_store())).unwrap();
+    let json = to_value(|out| write_store(&store, out));
+
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+    let space = &json["store"]["object_spaces"][0];
+    assert_eq!(space["gosid"], "{11111111-2222-3333-4444-555555555555},1");
+    assert_eq!(space["revisions"][0]["rid"], "{66666666-7777-8888-9999-aaaaaaaaaaaa},1");
+    assert!(json["store"]["transaction_log"].is_object());
+}
+
+#[test]
+fn section_pages() {
+    let page = Page {
+        id: ExGuid::nil(),
+        title: None,
+        cached_title: Some("Notes".to_string()),
+        level: 1,
+        width: None,
+        height: None,
+        contents: vec![PageContent::Outline(Outline {
+            offset_horizontal: Some(1.0),
+            offset_vertical: None,
+            layout_max_width: None,
+            layout_max_height: None,
+            items: vec![OutlineItem::Element(OutlineElement {
+                child_level: 1,
+                list: None,
+                contents: vec![Content::RichText(RichText { text: "hello".to_string(), ..Default::default() })],
+                children: vec![]
+            })]
+        })]
+    };
+    let section = Section { display_name: Some("Work".to_string()), page_series: vec![PageSeries { pages: vec![page] }] };
+    let json = to_value(|out| write_section(&section, out));
+
+    let page = &json["section"]["page_series"][0]["pages"][0];
+    assert_eq!(page["cached_title"], "Notes");
+    assert_eq!(page["contents"][0]["Outline"]["items"][0]["Element"]["contents"][0]["RichText"]["text"], "hello");
+}
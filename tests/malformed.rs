@@ -0,0 +1,118 @@
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use onernote::onenote::{Section, TableOfContents};
+use onernote::structs::header::OneNoteFileType;
+use onernote::{Error, OneStore};
+
+fn parse(bytes: &[u8]) -> onernote::Result<OneStore> {
+    OneStore::parse(&mut Cursor::new(bytes))
+}
+
+#[test]
+fn minimal_store_parses() {
+    let store = parse(&common::minimal_store()).unwrap();
+    assert_eq!(store.object_spaces.len(), 1);
+    assert!(store.root_object_space().unwrap().latest_revision().is_some());
+}
+
+// The minimal store, then every file in tests/samples with its name
+fn corpus() -> Vec<(String, Vec<u8>)> {
+    let mut files = vec![("minimal_store()".to_string(), common::minimal_store())];
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("samples");
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+    paths.sort();
+    for path in paths {
+        if matches!(path.extension().and_then(|extension| extension.to_str()), Some("one" | "onetoc2")) {
+            files.push((path.file_name().unwrap().to_string_lossy().into_owned(), fs::read(&path).unwrap()));
+        }
+    }
+    files
+}
+
+// Parses bytes and reads the section or table of contents they hold, so property sets and the
+// object graph are decoded too
+fn parse_contents(bytes: &[u8]) -> onernote::Result<()> {
+    let mut reader = Cursor::new(bytes);
+    let store = OneStore::parse(&mut reader)?;
+    match store.header.file_type {
+        OneNoteFileType::OneToc2 => TableOfContents::from_store(&store, &mut reader).map(|_| ()),
+        OneNoteFileType::One => Section::from_store(&store, &mut reader).map(|_| ())
+    }
+}
+
+#[test]
+fn every_truncation_is_an_error() {
+    for (name, file) in corpus() {
+        for len in 0..file.len() {
+            assert!(parse(&file[..len]).is_err(), "{} truncated to {} bytes", name, len);
+        }
+    }
+}
+
+#[test]
+fn every_single_byte_corruption_returns() {
+    for (_, file) in corpus() {
+        for offset in 0..file.len() {
+            for flip in [0x01, 0x80, 0xFF] {
+                let mut corrupted = file.clone();
+                corrupted[offset] ^= flip;
+                let _ = parse_contents(&corrupted);
+            }
+        }
+    }
+}
+
+#[test]
+fn bad_file_type_guid() {
+    let mut file = common::minimal_store();
+    file[0] ^= 0xFF;
+    assert!(matches!(parse(&file), Err(Error::BadGuid { offset: 0, .. })));
+}
+
+#[test]
+fn bad_fragment_magic() {
+    let mut file = common::minimal_store();
+    let root_list_start = u64::from_le_bytes(file[172..180].try_into().unwrap()) as usize;
+    file[root_list_start] ^= 0xFF;
+    assert!(matches!(parse(&file), Err(Error::BadMagic { .. })));
+}
+
+#[test]
+fn unknown_file_node_id() {
+    let mut file = common::minimal_store();
+    let root_list_start = u64::from_le_bytes(file[172..180].try_into().unwrap()) as usize;
+    // Replace the low byte of the first node's id with an id that doesn't exist
+    file[root_list_start + 16] = 0x01;
+    assert!(matches!(parse(&file), Err(Error::UnknownFileNodeId { id: 0x01, .. })));
+}
+
+#[test]
+fn tiny_fragment_reference() {
+    let mut file = common::minimal_store();
+    // fcrFileNodeListRoot.cb
+    file[180..184].copy_from_slice(&4u32.to_le_bytes());
+    assert!(matches!(parse(&file), Err(Error::InvalidData { .. })));
+}
+
+#[test]
+fn out_of_bounds_fragment_reference() {
+    let mut file = common::minimal_store();
+    file[172..180].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    let result = parse(&file);
+    assert!(matches!(result, Err(Error::OutOfBounds { .. })));
+    assert!(result.unwrap_err().is_truncated());
+}
+
+#[test]
+fn shorter_than_expected_length() {
+    let mut file = common::minimal_store();
+    let expected_length = file.len() as u64 + 1;
+    file[196..204].copy_from_slice(&expected_length.to_le_bytes());
+    let error = parse(&file).unwrap_err();
+    assert!(matches!(error, Error::OutOfBounds { offset: 196, .. }));
+    assert!(error.is_truncated());
+}
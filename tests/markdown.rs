@@ -0,0 +1,106 @@
+use onernote::export::markdown::render_page;
+use onernote::export::Resources;
+use onernote::onenote::*;
+use onernote::structs::exguid::ExGuid;
+
+// Links every resource to its original name without writing anything
+struct Names;
+
+impl Resources for Names {
+    fn link(&mut self, _data: &FileDataReference, name: Option<&str>) -> onernote::Result<Option<String>> {
+        Ok(name.map(|name| name.to_string()))
+    }
+}
+
+fn text(text: &str) -> RichText {
+    RichText { text: text.to_string(), ..Default::default() }
+}
+
+fn element(contents: Vec<Content>, list: Option<&str>, children: Vec<OutlineItem>) -> OutlineItem {
+    OutlineItem::Element(OutlineElement {
+        child_level: 1,
+        list: list.map(|format| List { format: format.to_string(), font: None, restart: None }),
+        contents,
+        children
+    })
+}
+
+fn page(contents: Vec<PageContent>) -> Page {
+    Page { id: ExGuid::nil(), title: None, cached_title: Some("Notes".to_string()), level: 1, width: None, height: None, contents }
+}
+
+fn outline(items: Vec<OutlineItem>) -> PageContent {
+    PageContent::Outline(Outline { offset_horizontal: None, offset_vertical: None, layout_max_width: None, layout_max_height: None, items })
+}
+
+#[test]
+fn title_and_paragraphs() {
+    let page = page(vec![outline(vec![
+        element(vec![Content::RichText(text("first"))], None, vec![]),
+        element(vec![Content::RichText(text("# not a heading"))], None, vec![])
+    ])]);
+
+    assert_eq!(render_page(&page, &mut Names).unwrap(), "# Notes\n\nfirst\n\n\\# not a heading\n");
+}
+
+#[test]
+fn nested_lists() {
+    let page = page(vec![outline(vec![
+        element(vec![Content::RichText(text("one"))], Some("\u{FFFD}"), vec![
+            element(vec![Content::RichText(text("bullet"))], Some("\u{2022}"), vec![])
+        ]),
+        element(vec![Content::RichText(text("two"))], Some("\u{FFFD}"), vec![])
+    ])]);
+
+    assert_eq!(render_page(&page, &mut Names).unwrap(), "# Notes\n\n1. one\n   - bullet\n1. two\n");
+}
+
+#[test]
+fn formatted_runs_and_links() {
+    let mut rich_text = text("bold link");
+    rich_text.runs = vec![
+        TextRun { range: 0..5, bold: true, ..Default::default() },
+        TextRun { range: 5..9, hyperlink: Some("https://example.com".to_string()), ..Default::default() }
+    ];
+    let page = page(vec![outline(vec![element(vec![Content::RichText(rich_text)], None, vec![])])]);
+
+    assert_eq!(render_page(&page, &mut Names).unwrap(), "# Notes\n\n**bold** [link](https://example.com)\n");
+}
+
+#[test]
+fn tables_and_images() {
+    let cell = |value: &str| TableCell { contents: vec![OutlineElement { child_level: 1, list: None, contents: vec![Content::RichText(text(value))], children: vec![] }] };
+    let table = Table {
+        row_count: 2,
+        column_count: 2,
+        column_widths: vec![],
+        borders_visible: true,
+        rows: vec![TableRow { cells: vec![cell("a"), cell("b|c")] }, TableRow { cells: vec![cell("1"), cell("2")] }]
+    };
+    let image = Image {
+        data: Some(FileDataReference { object_id: ExGuid::nil(), reference: "<ifndf>{00000000-0000-0000-0000-000000000000}".to_string(), extension: ".png".to_string() }),
+        filename: Some("my image.png".to_string()),
+        alt_text: Some("diagram".to_string()),
+        width: None, height: None, offset_horizontal: None, offset_vertical: None, layout_max_width: None, layout_max_height: None,
+        is_background: false
+    };
+    let page = page(vec![
+        outline(vec![element(vec![Content::Table(table)], None, vec![])]),
+        PageContent::Image(image)
+    ]);
+
+    assert_eq!(render_page(&page, &mut Names).unwrap(), "# Notes\n\n| a | b\\|c |\n| --- | --- |\n| 1 | 2 |\n\n![diagram](<my image.png>)\n");
+}
+
+#[test]
+fn script_links_are_left_out() {
+    let mut rich_text = text("one two three");
+    rich_text.runs = vec![
+        TextRun { range: 0..4, hyperlink: Some("javascript:alert(1)".to_string()), ..Default::default() },
+        TextRun { range: 4..8, hyperlink: Some("&#106;avascript:alert(1)".to_string()), ..Default::default() },
+        TextRun { range: 8..13, hyperlink: Some("onenote:#Page&section-id={1}".to_string()), ..Default::default() }
+    ];
+    let page = page(vec![outline(vec![element(vec![Content::RichText(rich_text)], None, vec![])])]);
+
+    assert_eq!(render_page(&page, &mut Names).unwrap(), "# Notes\n\none two [three](onenote:#Page&section-id={1})\n");
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use onernote::onenote::{Notebook, NotebookEntry, SectionGroup, TableOfContents};
+use onernote::OneStore;
+
+#[test]
+fn folder_without_table_of_contents() {
+    let directory = std::env::temp_dir().join(format!("onernote-notebook-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let error = Notebook::open(&directory).unwrap_err();
+    fs::remove_dir_all(&directory).unwrap();
+    assert!(matches!(error, onernote::Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+}
+
+// A temporary folder holding files at the given relative paths, removed when dropped
+struct Folder(PathBuf);
+
+impl Folder {
+    fn new(name: &str, files: &[(&str, Vec<u8>)]) -> Folder {
+        let directory = std::env::temp_dir().join(format!("onernote-notebook-{}-{}", name, std::process::id()));
+        for (path, bytes) in files {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, bytes).unwrap();
+        }
+        Folder(directory)
+    }
+}
+
+impl Drop for Folder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn section(name: &str) -> Vec<u8> {
+    common::section_store(name, &[])
+}
+
+// Display names in order, with a group's entries after a slash
+fn names(entries: &[NotebookEntry]) -> Vec<String> {
+    let mut names = Vec::new();
+    for entry in entries {
+        match entry {
+            NotebookEntry::Section(section) => names.push(section.display_name.clone()),
+            NotebookEntry::SectionGroup(group) => names.extend(names_in(group))
+        }
+    }
+    names
+}
+
+fn names_in(group: &SectionGroup) -> Vec<String> {
+    names(&group.entries).into_iter().map(|name| format!("{}/{}", group.display_name, name)).collect()
+}
+
+#[test]
+fn table_of_contents_is_ordered_by_ordering_id() {
+    // Entries without an ordering id come last, in the order they were stored
+    let file = common::toc_store(&[
+        ("Third.one", Some(30)),
+        ("Unordered B.one", None),
+        ("First.one", Some(10)),
+        ("Unordered A.one", None),
+        ("Second.one", Some(20))
+    ]);
+    let store = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    let toc = TableOfContents::from_store(&store, &mut Cursor::new(&file)).unwrap();
+
+    let entries: Vec<(&str, Option<u32>)> = toc.entries.iter().map(|entry| (entry.filename.as_str(), entry.ordering_id)).collect();
+    assert_eq!(entries, vec![
+        ("First.one", Some(10)),
+        ("Second.one", Some(20)),
+        ("Third.one", Some(30)),
+        ("Unordered B.one", None),
+        ("Unordered A.one", None)
+    ]);
+}
+
+#[test]
+fn file_names_are_unescaped_and_shown_without_extension() {
+    let file = common::toc_store(&[("C^M^M and C^J.one", Some(1)), ("Notes.ONE", Some(2)), ("Group", Some(3)), ("Archive.one.bak", Some(4))]);
+    let store = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    let toc = TableOfContents::from_store(&store, &mut Cursor::new(&file)).unwrap();
+
+    let filenames: Vec<&str> = toc.entries.iter().map(|entry| entry.filename.as_str()).collect();
+    assert_eq!(filenames, vec!["C++ and C,.one", "Notes.ONE", "Group", "Archive.one.bak"]);
+    let display_names: Vec<&str> = toc.entries.iter().map(|entry| entry.display_name()).collect();
+    assert_eq!(display_names, vec!["C++ and C,", "Notes", "Group", "Archive.one.bak"]);
+}
+
+#[test]
+fn notebook_folder_is_read_in_table_of_contents_order() {
+    let folder = Folder::new("order", &[
+        ("Open Notebook.onetoc2", common::toc_store(&[("Second.one", Some(2)), ("C^M^M.one", Some(3)), ("First.one", Some(1)), ("Group", Some(4))])),
+        ("First.one", section("First")),
+        ("Second.one", section("Second")),
+        ("C++.one", section("C++")),
+        // Files the table of contents doesn't list come after the ones it does, in name order
+        ("Unlisted B.one", section("Unlisted B")),
+        ("Unlisted A.one", section("Unlisted A")),
+        ("notes.txt", b"not a section".to_vec()),
+        ("Group/Open Notebook.onetoc2", common::toc_store(&[("Subgroup", Some(2)), ("Inner.one", Some(1))])),
+        ("Group/Inner.one", section("Inner")),
+        ("Group/Subgroup/Open Notebook.onetoc2", common::toc_store(&[("Deepest.one", None)])),
+        ("Group/Subgroup/Deepest.one", section("Deepest")),
+        // Deleted sections keep their own table of contents but aren't part of the notebook
+        ("OneNote_RecycleBin/Open Notebook.onetoc2", common::toc_store(&[("Deleted.one", None)])),
+        ("OneNote_RecycleBin/Deleted.one", section("Deleted"))
+    ]);
+
+    let notebook = Notebook::open(&folder.0).unwrap();
+    assert_eq!(names(&notebook.entries), vec![
+        "First",
+        "Second",
+        "C++",
+        "Group/Inner",
+        "Group/Subgroup/Deepest",
+        "Unlisted A",
+        "Unlisted B"
+    ]);
+    assert_eq!(notebook.sections().iter().map(|entry| entry.section.display_name.as_deref().unwrap()).collect::<Vec<_>>(),
+        vec!["First", "Second", "C++", "Inner", "Deepest", "Unlisted A", "Unlisted B"]);
+    match &notebook.entries[3] {
+        NotebookEntry::SectionGroup(group) => assert_eq!(group.path, folder.0.join("Group")),
+        other => panic!("expected the section group, got {:?}", other)
+    }
+}
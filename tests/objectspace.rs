@@ -0,0 +1,139 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::guid::Guid;
+use onernote::structs::objectspace::ObjectSpace;
+use onernote::OneStore;
+
+const CONTEXT: [u8; 16] = [0x0C; 16];
+const OBJECT: [u8; 16] = [0x0B; 16];
+const CONTEXT_OBJECT: [u8; 16] = [0x0D; 16];
+
+const ROLE_DEFAULT_CONTENT: u32 = 1;
+const ROLE_METADATA: u32 = 2;
+
+fn id(guid: [u8; 16], n: u32) -> ExGuid {
+    ExGuid { guid: Guid::from_bytes_le(guid), n }
+}
+
+fn rid(n: u32) -> ExGuid {
+    id(RID, n)
+}
+
+// RevisionManifestStart6FND, or RevisionManifestStart7FND when the revision is in a context
+fn revision_start(n: u32, dependent_n: Option<u32>, context: Option<[u8; 16]>) -> Vec<u8> {
+    let mut start = exguid(RID, n);
+    start.extend(dependent_n.map_or(exguid([0; 16], 0), |dependent_n| exguid(RID, dependent_n)));
+    start.extend_from_slice(&ROLE_DEFAULT_CONTENT.to_le_bytes());
+    start.extend_from_slice(&0u16.to_le_bytes());
+    match context {
+        Some(context) => {
+            start.extend(exguid(context, 1));
+            node(0x1F, &start)
+        },
+        None => node(0x1E, &start)
+    }
+}
+
+// A revision declaring one object, whose GUID is guid, with n = 1
+fn revision(builder: &mut StoreBuilder, n: u32, dependent_n: Option<u32>, context: Option<[u8; 16]>, guid: [u8; 16]) -> Vec<Vec<u8>> {
+    let property_set = builder.chunk(&Props::new().to_bytes());
+    let mut entry = 0u32.to_le_bytes().to_vec();
+    entry.extend_from_slice(&guid);
+    // ObjectDeclarationWithRefCountFNDX without references
+    let mut declaration = compact_id(1, 0).to_le_bytes().to_vec();
+    declaration.extend_from_slice(&0x0001u16.to_le_bytes());
+    declaration.extend_from_slice(&0u32.to_le_bytes());
+    declaration.push(1);
+
+    vec![
+        revision_start(n, dependent_n, context),
+        node(0x22, &[]),
+        node(0x24, &entry),
+        node(0x28, &[]),
+        data_node(0x2D, property_set, &declaration),
+        node(0x1C, &[])
+    ]
+}
+
+// RevisionRoleDeclarationFND, or RevisionRoleAndContextDeclarationFND when a context is given
+fn role_declaration(n: u32, role: u32, context: Option<[u8; 16]>) -> Vec<u8> {
+    let mut declaration = exguid(RID, n);
+    declaration.extend_from_slice(&role.to_le_bytes());
+    match context {
+        Some(context) => {
+            declaration.extend(exguid(context, 1));
+            node(0x5D, &declaration)
+        },
+        None => node(0x5C, &declaration)
+    }
+}
+
+fn parse(file: Vec<u8>) -> ObjectSpace {
+    let mut store = OneStore::parse(&mut Cursor::new(file)).unwrap();
+    store.object_spaces.remove(0)
+}
+
+fn current_rid(space: &ObjectSpace, context: ExGuid, role: u32) -> Option<ExGuid> {
+    space.current_revision(&context, role).map(|revision| revision.rid)
+}
+
+#[test]
+fn role_declaration_moves_default_content_to_an_older_revision() {
+    let mut builder = StoreBuilder::one();
+    let mut nodes = revision(&mut builder, 1, None, None, OBJECT);
+    nodes.extend(revision(&mut builder, 2, Some(1), None, OBJECT));
+    let space = parse(builder.revisions(GOSID, &nodes).build());
+    assert_eq!(space.latest_revision().unwrap().rid, rid(2));
+
+    let mut builder = StoreBuilder::one();
+    nodes = revision(&mut builder, 1, None, None, OBJECT);
+    nodes.extend(revision(&mut builder, 2, Some(1), None, OBJECT));
+    nodes.push(role_declaration(1, ROLE_DEFAULT_CONTENT, None));
+    let space = parse(builder.revisions(GOSID, &nodes).build());
+
+    assert_eq!(space.revisions.len(), 2);
+    assert_eq!(space.latest_revision().unwrap().rid, rid(1));
+    assert_eq!(current_rid(&space, ExGuid::nil(), ROLE_DEFAULT_CONTENT), Some(rid(1)));
+}
+
+#[test]
+fn revisions_in_another_context_have_their_own_roles() {
+    // The third revision depends on the first rather than the context revision before it
+    let mut builder = StoreBuilder::one();
+    let mut nodes = revision(&mut builder, 1, None, None, OBJECT);
+    nodes.extend(revision(&mut builder, 2, Some(1), Some(CONTEXT), CONTEXT_OBJECT));
+    nodes.extend(revision(&mut builder, 3, Some(1), None, OBJECT));
+    nodes.push(role_declaration(1, ROLE_METADATA, Some(CONTEXT)));
+    let space = parse(builder.revisions(GOSID, &nodes).build());
+
+    let context = id(CONTEXT, 1);
+    assert_eq!(space.revision(&rid(2)).unwrap().context, context);
+    assert_eq!(space.latest_revision().unwrap().rid, rid(3));
+    assert_eq!(current_rid(&space, context, ROLE_DEFAULT_CONTENT), Some(rid(2)));
+    assert_eq!(current_rid(&space, context, ROLE_METADATA), Some(rid(1)));
+    assert_eq!(current_rid(&space, ExGuid::nil(), ROLE_METADATA), None);
+
+    let mut context_objects: Vec<ExGuid> = space.effective_objects(&rid(2)).into_keys().collect();
+    context_objects.sort_by_key(|id| id.guid.to_string());
+    assert_eq!(context_objects, vec![id(OBJECT, 1), id(CONTEXT_OBJECT, 1)]);
+    assert_eq!(space.effective_objects(&rid(3)).into_keys().collect::<Vec<_>>(), vec![id(OBJECT, 1)]);
+}
+
+#[test]
+fn last_revision_manifest_list_is_used() {
+    let mut builder = StoreBuilder::one();
+    let old = revision(&mut builder, 1, None, None, OBJECT);
+    let mut current = revision(&mut builder, 2, None, None, OBJECT);
+    current.extend(revision(&mut builder, 3, Some(2), None, CONTEXT_OBJECT));
+    let space = parse(builder.revision_lists(GOSID, &[&old, &current]).build());
+
+    assert!(space.revision(&rid(1)).is_none());
+    assert_eq!(space.revisions.len(), 2);
+    assert_eq!(space.latest_revision().unwrap().rid, rid(3));
+    assert_eq!(current_rid(&space, ExGuid::nil(), ROLE_DEFAULT_CONTENT), Some(rid(3)));
+    assert_eq!(space.effective_objects(&rid(3)).len(), 2);
+}
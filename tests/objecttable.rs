@@ -0,0 +1,111 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::guid::Guid;
+use onernote::OneStore;
+
+const OBJECT: [u8; 16] = [0x0B; 16];
+const OTHER_OBJECT: [u8; 16] = [0x0C; 16];
+// A FourBytesOfData property, whose value tells the declared and revised property sets apart
+const VALUE: u32 = 0x14000001;
+
+fn id(guid: [u8; 16], n: u32) -> ExGuid {
+    ExGuid { guid: Guid::from_bytes_le(guid), n }
+}
+
+// RevisionManifestStart4FND for the revision with rid n, depending on the one with dependent_n
+fn revision_start(n: u32, dependent_n: Option<u32>) -> Vec<u8> {
+    let mut start = exguid(GOSID, n);
+    start.extend(dependent_n.map_or(exguid([0; 16], 0), |dependent_n| exguid(GOSID, dependent_n)));
+    start.extend_from_slice(&0u64.to_le_bytes());
+    start.extend_from_slice(&1u32.to_le_bytes());
+    start.extend_from_slice(&0u16.to_le_bytes());
+    node(0x1B, &start)
+}
+
+// A global id table mapping index 0 to OBJECT and index 1 to OTHER_OBJECT
+fn global_id_table() -> Vec<Vec<u8>> {
+    let mut nodes = vec![node(0x21, &[0])];
+    for (index, guid) in [OBJECT, OTHER_OBJECT].iter().enumerate() {
+        let mut entry = (index as u32).to_le_bytes().to_vec();
+        entry.extend_from_slice(guid);
+        nodes.push(node(0x24, &entry));
+    }
+    nodes.push(node(0x28, &[]));
+    nodes
+}
+
+// ObjectDeclarationWithRefCountFNDX for an object without references
+fn declaration(property_set: (u64, u32), oid: u32, c_ref: u8) -> Vec<u8> {
+    let mut body = oid.to_le_bytes().to_vec();
+    body.extend_from_slice(&0x0001u16.to_le_bytes());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    body.push(c_ref);
+    data_node(0x2D, property_set, &body)
+}
+
+// A store whose first revision declares OBJECT and OTHER_OBJECT with ref count 1 and whose second
+// revision, which depends on it, holds the nodes changes builds
+fn store(changes: impl FnOnce(&mut StoreBuilder) -> Vec<Vec<u8>>) -> OneStore {
+    let mut builder = StoreBuilder::onetoc2();
+    let property_set = builder.chunk(&Props::new().u32(VALUE, 1).to_bytes());
+
+    let mut nodes = vec![revision_start(1, None)];
+    nodes.extend(global_id_table());
+    nodes.push(declaration(property_set, compact_id(1, 0), 1));
+    nodes.push(declaration(property_set, compact_id(1, 1), 1));
+    nodes.push(node(0x1C, &[]));
+
+    nodes.push(revision_start(2, Some(1)));
+    nodes.extend(global_id_table());
+    nodes.extend(changes(&mut builder));
+    nodes.push(node(0x1C, &[]));
+
+    let file = builder.revisions(GOSID, &nodes).build();
+    OneStore::parse(&mut Cursor::new(file)).unwrap()
+}
+
+fn ref_counts(store: &OneStore, revision: u32) -> (u32, u32) {
+    let objects = store.root_object_space().unwrap().effective_objects(&id(GOSID, revision));
+    (objects[&id(OBJECT, 1)].ref_count, objects[&id(OTHER_OBJECT, 1)].ref_count)
+}
+
+#[test]
+fn object_revision_overrides_the_ref_count() {
+    let store = store(|builder| {
+        let revised = builder.chunk(&Props::new().u32(VALUE, 2).to_bytes());
+        // ObjectRevisionWithRefCountFNDX: the ref count shares a byte with the reference flags
+        let mut body = compact_id(1, 0).to_le_bytes().to_vec();
+        body.push(5 << 2);
+        vec![data_node(0x41, revised, &body)]
+    });
+
+    assert_eq!(ref_counts(&store, 1), (1, 1));
+    assert_eq!(ref_counts(&store, 2), (5, 1));
+    let objects = store.root_object_space().unwrap().effective_objects(&id(GOSID, 2));
+    assert_ne!(objects[&id(OBJECT, 1)].body, objects[&id(OTHER_OBJECT, 1)].body);
+}
+
+#[test]
+fn dependency_overrides_change_inherited_ref_counts() {
+    let store = store(|_| {
+        // ObjectInfoDependencyOverrideData stored in the node itself: one 8 bit and one 32 bit override
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&compact_id(1, 0).to_le_bytes());
+        data.push(7);
+        data.extend_from_slice(&compact_id(1, 1).to_le_bytes());
+        data.extend_from_slice(&300u32.to_le_bytes());
+        vec![data_node(0x84, (u64::MAX, 0), &data)]
+    });
+
+    assert_eq!(ref_counts(&store, 1), (1, 1));
+    assert_eq!(ref_counts(&store, 2), (7, 300));
+    let revision = store.root_object_space().unwrap().revision(&id(GOSID, 2)).unwrap();
+    assert!(revision.objects.is_empty());
+    assert_eq!(revision.ref_count_overrides.len(), 2);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::Path;
+
+use miniz_oxide::deflate::compress_to_vec;
+use onernote::cab::Cabinet;
+use onernote::onenote::NotebookPackage;
+use onernote::Error;
+
+const COMPRESS_NONE: u16 = 0;
+const COMPRESS_MSZIP: u16 = 1;
+
+// A CFDATA block. A checksum of 0 means none was computed
+fn block(checksum: u32, data: &[u8], uncompressed_len: usize) -> Vec<u8> {
+    let mut block = checksum.to_le_bytes().to_vec();
+    block.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    block.extend_from_slice(&(uncompressed_len as u16).to_le_bytes());
+    block.extend_from_slice(data);
+    block
+}
+
+// A cabinet with one folder. The folder's stream is split into blocks of block_size bytes
+fn cabinet(compression: u16, files: &[(&str, &[u8])], block_size: usize) -> Vec<u8> {
+    let stream: Vec<u8> = files.iter().flat_map(|(_, data)| data.iter().copied()).collect();
+    let blocks: Vec<Vec<u8>> = stream.chunks(block_size).map(|chunk| match compression {
+        COMPRESS_MSZIP => block(0, &[b"CK".to_vec(), compress_to_vec(chunk, 6)].concat(), chunk.len()),
+        _ => block(0, chunk, chunk.len())
+    }).collect();
+    folder(compression, files, &blocks)
+}
+
+// A cabinet with one folder made of the given CFDATA blocks
+fn folder(compression: u16, files: &[(&str, &[u8])], blocks: &[Vec<u8>]) -> Vec<u8> {
+    let mut entries = Vec::new();
+    let mut folder_offset = 0;
+    for (name, data) in files {
+        entries.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        entries.extend_from_slice(&(folder_offset as u32).to_le_bytes());
+        entries.extend_from_slice(&[0; 6]);
+        entries.extend_from_slice(&0u16.to_le_bytes());
+        entries.extend_from_slice(name.as_bytes());
+        entries.push(0);
+        folder_offset += data.len();
+    }
+    let block_count = blocks.len() as u16;
+    let blocks = blocks.concat();
+
+    let files_offset = 36 + 8;
+    let data_offset = files_offset + entries.len();
+    let mut file = b"MSCF".to_vec();
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&((data_offset + blocks.len()) as u32).to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&(files_offset as u32).to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&[3, 1]);
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    file.extend_from_slice(&[0; 6]);
+    file.extend_from_slice(&(data_offset as u32).to_le_bytes());
+    file.extend_from_slice(&block_count.to_le_bytes());
+    file.extend_from_slice(&compression.to_le_bytes());
+    file.extend(entries);
+    file.extend(blocks);
+    file
+}
+
+#[test]
+fn mszip_members_span_blocks() {
+    let text: Vec<u8> = (0..50_000u32).flat_map(|n| (n % 251).to_le_bytes()).collect();
+    let file = cabinet(COMPRESS_MSZIP, &[("Open Notebook.onetoc2", b"toc"), ("Group\\Notes.one", &text)], 0x8000);
+
+    let package = NotebookPackage::from_reader("Notebook".to_string(), &mut Cursor::new(file)).unwrap();
+    let paths: Vec<&Path> = package.paths().collect();
+    assert_eq!(paths, [Path::new("Open Notebook.onetoc2"), &Path::new("Group").join("Notes.one")]);
+    assert_eq!(package.get("Open Notebook.onetoc2").unwrap().data, b"toc");
+    assert_eq!(package.get(Path::new("Group").join("Notes.one")).unwrap().data, text);
+}
+
+#[test]
+fn members_parse_as_revision_stores() {
+    let store = common::minimal_store();
+    let file = cabinet(COMPRESS_NONE, &[("Section.one", &store)], 100);
+
+    let package = NotebookPackage::from_reader("Notebook".to_string(), &mut Cursor::new(file)).unwrap();
+    let parsed = package.store("Section.one").unwrap();
+    assert_eq!(parsed.object_spaces.len(), 1);
+    assert!(matches!(package.notebook(), Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+}
+
+#[test]
+fn mszip_blocks_reference_earlier_blocks() {
+    // The first block stores "OneNote!" in a stored deflate block
+    let first = [b"CK".as_slice(), &[0x01, 0x08, 0x00, 0xF7, 0xFF], b"OneNote!"].concat();
+    // The second is a fixed Huffman deflate block holding only a match of length 8 at distance 8,
+    // which reaches back into the first block's output
+    let second = [b"CK".as_slice(), &[0x83, 0xD1, 0x00]].concat();
+    assert!(miniz_oxide::inflate::decompress_to_vec(&second[2..]).is_err());
+
+    let file = folder(COMPRESS_MSZIP, &[("Section.one", b"OneNote!OneNote!")], &[block(0, &first, 8), block(0, &second, 8)]);
+    let mut reader = Cursor::new(file);
+    let files = Cabinet::from_reader(&mut reader).unwrap().extract(&mut reader).unwrap();
+    assert_eq!(files[0], b"OneNote!OneNote!");
+}
+
+#[test]
+fn corrupt_and_unsafe_cabinets() {
+    // See MS-CAB 2.6: the words of "OneNote" with the trailing "ote" taken as 0x006F7465, and then
+    // the block's sizes, XORed together
+    const CHECKSUM: u32 = 0x4E0D1A2D;
+    let extract = |data: &[u8]| {
+        let file = folder(COMPRESS_NONE, &[("Section.one", b"OneNote")], &[block(CHECKSUM, data, 7)]);
+        let mut reader = Cursor::new(file);
+        Cabinet::from_reader(&mut reader).unwrap().extract(&mut reader)
+    };
+    assert_eq!(extract(b"OneNote").unwrap()[0], b"OneNote");
+    match extract(b"OneNotf") {
+        Err(Error::CrcMismatch { structure: "CFDATA", expected: CHECKSUM, actual: 0x4E0D1A2E, .. }) => {},
+        other => panic!("expected a checksum mismatch, got {:?}", other)
+    }
+
+    let file = cabinet(COMPRESS_NONE, &[("..\\Section.one", b"section data")], 100);
+    assert!(matches!(Cabinet::from_reader(&mut Cursor::new(file)), Err(Error::InvalidData { structure: "CFFILE", .. })));
+}
@@ -0,0 +1,148 @@
+use std::io::Cursor;
+
+use onernote::structs::compactid::CompactId;
+use onernote::structs::propertyset::{PropertyId, PropertySet, PropertyValue, ReferenceStreams};
+use onernote::Error;
+
+fn id(n: u8, guid_index: u32) -> CompactId {
+    CompactId { n, guid_index }
+}
+
+// Decodes bytes as a property set and checks all of it was read
+fn decode(bytes: &[u8], streams: &mut ReferenceStreams) -> onernote::Result<PropertySet> {
+    let mut reader = Cursor::new(bytes);
+    let set = PropertySet::from_reader(&mut reader, streams)?;
+    assert_eq!(reader.position(), bytes.len() as u64);
+    Ok(set)
+}
+
+fn no_streams() -> ReferenceStreams<'static> {
+    ReferenceStreams { oids: &[], osids: &[], context_ids: &[] }
+}
+
+fn set(properties: Vec<(u32, PropertyValue)>) -> PropertySet {
+    PropertySet { properties: properties.into_iter().map(|(prid, value)| (PropertyId(prid), value)).collect() }
+}
+
+#[test]
+fn values_stored_in_the_set_are_decoded() {
+    const BYTES: &[u8] = &[
+        0x08, 0x00,
+        // NoData, Bool true, Bool false, OneByteOfData
+        0x01, 0x00, 0x00, 0x04,
+        0x02, 0x00, 0x00, 0x88,
+        0x03, 0x00, 0x00, 0x08,
+        0x04, 0x00, 0x00, 0x0C,
+        // TwoBytesOfData, FourBytesOfData, EightBytesOfData, FourBytesOfLengthFollowedByData
+        0x05, 0x00, 0x00, 0x10,
+        0x06, 0x00, 0x00, 0x14,
+        0x07, 0x00, 0x00, 0x18,
+        0x08, 0x00, 0x00, 0x1C,
+        0xAB,
+        0x34, 0x12,
+        0xEF, 0xBE, 0xAD, 0xDE,
+        0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+        0x03, 0x00, 0x00, 0x00, b'a', b'b', b'c'
+    ];
+
+    let set_read = decode(BYTES, &mut no_streams()).unwrap();
+    assert_eq!(set_read, set(vec![
+        (0x04000001, PropertyValue::NoData),
+        (0x88000002, PropertyValue::Bool(true)),
+        (0x08000003, PropertyValue::Bool(false)),
+        (0x0C000004, PropertyValue::OneByte(0xAB)),
+        (0x10000005, PropertyValue::TwoBytes(0x1234)),
+        (0x14000006, PropertyValue::FourBytes(0xDEADBEEF)),
+        (0x18000007, PropertyValue::EightBytes(0x0102030405060708)),
+        (0x1C000008, PropertyValue::Bytes(b"abc".to_vec()))
+    ]));
+    // Properties are looked up without their boolValue bit
+    assert_eq!(set_read.get(0x08000002), Some(&PropertyValue::Bool(true)));
+}
+
+#[test]
+fn references_are_taken_from_their_streams_in_order() {
+    const BYTES: &[u8] = &[
+        0x06, 0x00,
+        // ObjectID, ArrayOfObjectIDs, ObjectSpaceID, ArrayOfObjectSpaceIDs, ContextID, ArrayOfContextIDs
+        0x01, 0x00, 0x00, 0x20,
+        0x02, 0x00, 0x00, 0x24,
+        0x03, 0x00, 0x00, 0x28,
+        0x04, 0x00, 0x00, 0x2C,
+        0x05, 0x00, 0x00, 0x30,
+        0x06, 0x00, 0x00, 0x34,
+        // Only the arrays store anything: their counts
+        0x02, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00,
+        0x02, 0x00, 0x00, 0x00
+    ];
+    let oids = [id(1, 0), id(2, 0), id(3, 1)];
+    let osids = [id(1, 5), id(1, 6)];
+    let context_ids = [id(7, 0), id(8, 0), id(9, 0)];
+    let mut streams = ReferenceStreams { oids: &oids, osids: &osids, context_ids: &context_ids };
+
+    assert_eq!(decode(BYTES, &mut streams).unwrap(), set(vec![
+        (0x20000001, PropertyValue::ObjectId(id(1, 0))),
+        (0x24000002, PropertyValue::ObjectIds(vec![id(2, 0), id(3, 1)])),
+        (0x28000003, PropertyValue::ObjectSpaceId(id(1, 5))),
+        (0x2C000004, PropertyValue::ObjectSpaceIds(vec![id(1, 6)])),
+        (0x30000005, PropertyValue::ContextId(id(7, 0))),
+        (0x34000006, PropertyValue::ContextIds(vec![id(8, 0), id(9, 0)]))
+    ]));
+    assert!(streams.oids.is_empty() && streams.osids.is_empty() && streams.context_ids.is_empty());
+}
+
+#[test]
+fn nested_property_sets_are_decoded() {
+    const BYTES: &[u8] = &[
+        0x03, 0x00,
+        // PropertySet, ArrayOfPropertyValues, empty ArrayOfPropertyValues
+        0x01, 0x00, 0x00, 0x44,
+        0x02, 0x00, 0x00, 0x40,
+        0x04, 0x00, 0x00, 0x40,
+        // The nested set: one FourBytesOfData
+        0x01, 0x00,
+        0x05, 0x00, 0x00, 0x14,
+        0x2A, 0x00, 0x00, 0x00,
+        // Two elements, both property sets holding an ObjectID
+        0x02, 0x00, 0x00, 0x00,
+        0x03, 0x00, 0x00, 0x44,
+        0x01, 0x00,
+        0x06, 0x00, 0x00, 0x20,
+        0x01, 0x00,
+        0x06, 0x00, 0x00, 0x20,
+        // An empty array has no element property id
+        0x00, 0x00, 0x00, 0x00
+    ];
+    let oids = [id(1, 0), id(2, 0)];
+    let mut streams = ReferenceStreams { oids: &oids, osids: &[], context_ids: &[] };
+
+    assert_eq!(decode(BYTES, &mut streams).unwrap(), set(vec![
+        (0x44000001, PropertyValue::PropertySet(set(vec![(0x14000005, PropertyValue::FourBytes(42))]))),
+        (0x40000002, PropertyValue::PropertyValues(PropertyId(0x44000003), vec![
+            set(vec![(0x20000006, PropertyValue::ObjectId(id(1, 0)))]),
+            set(vec![(0x20000006, PropertyValue::ObjectId(id(2, 0)))])
+        ])),
+        (0x40000004, PropertyValue::PropertyValues(PropertyId(0), vec![]))
+    ]));
+}
+
+#[test]
+fn malformed_values_are_errors() {
+    // Property type 0xE isn't defined
+    match PropertySet::from_reader(&mut Cursor::new(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x38]), &mut no_streams()) {
+        Err(Error::Unsupported { feature: "property type", offset: 6, .. }) => {},
+        other => panic!("expected an unsupported type, got {:?}", other)
+    }
+
+    let invalid = |bytes: &[u8], streams: &mut ReferenceStreams| match PropertySet::from_reader(&mut Cursor::new(bytes), streams) {
+        Err(Error::InvalidData { structure: "PropertySet", reason, .. }) => reason,
+        other => panic!("expected invalid data, got {:?}", other)
+    };
+    // Four bytes of data announced, two present
+    assert!(invalid(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x1C, 0x04, 0x00, 0x00, 0x00, 0x61, 0x62], &mut no_streams()).contains("longer than the property set"));
+    // An ObjectID with nothing left in the OID stream
+    assert!(invalid(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x20], &mut no_streams()).contains("more ids than its stream holds"));
+    // Array elements that are FourBytesOfData rather than property sets
+    assert!(invalid(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x40, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x14], &mut no_streams()).contains("must hold property sets"));
+}
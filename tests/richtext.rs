@@ -0,0 +1,174 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use onernote::onenote::{Content, OutlineItem, PageContent, RichText, Section, TextRun};
+use onernote::OneStore;
+
+const PAGE_GOSID: [u8; 16] = guid_bytes(0x9A6E0001, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+const FIELD_CODE: &str = "\u{FDDF}HYPERLINK \"https://example.com/a\"";
+
+// Style objects for the rich text take CompactIDs from n = 10 on
+fn style_id(index: u8) -> u32 {
+    compact_id(10 + index, 0)
+}
+
+// Decodes props as the only jcidRichTextOENode on a page, with each of styles as a
+// jcidParagraphStyleObject
+fn decode(props: Props, styles: Vec<Props>) -> RichText {
+    let mut objects = vec![
+        Object { id: compact_id(1, 0), jcid: JCID_PAGE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(2, 0)]) },
+        Object { id: compact_id(2, 0), jcid: JCID_OUTLINE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(3, 0)]) },
+        Object { id: compact_id(3, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(4, 0)]) },
+        Object { id: compact_id(4, 0), jcid: JCID_RICH_TEXT_OE_NODE, props }
+    ];
+    for (index, style) in styles.into_iter().enumerate() {
+        objects.push(Object { id: style_id(index as u8), jcid: JCID_PARAGRAPH_STYLE_OBJECT, props: style });
+    }
+    let file = section_store("Rich text", &[page_space(PAGE_GOSID, "Rich text", objects)]);
+
+    let store = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    let section = Section::from_store(&store, &mut Cursor::new(&file)).unwrap();
+    let page = section.pages().next().unwrap();
+    match &page.contents[..] {
+        [PageContent::Outline(outline)] => match &outline.items[..] {
+            [OutlineItem::Element(element)] => match &element.contents[..] {
+                [Content::RichText(text)] => text.clone(),
+                other => panic!("expected one rich text, got {:?}", other)
+            },
+            other => panic!("expected one outline element, got {:?}", other)
+        },
+        other => panic!("expected one outline, got {:?}", other)
+    }
+}
+
+fn run_index(ends: &[u32]) -> Vec<u8> {
+    ends.iter().flat_map(|end| end.to_le_bytes()).collect()
+}
+
+fn formatting(count: u8) -> Vec<u32> {
+    (0..count).map(style_id).collect()
+}
+
+#[test]
+fn runs_are_split_at_the_run_index() {
+    // Ends are counted in UTF-16 code units and turned into byte ranges
+    let text = decode(
+        Props::new()
+            .string(RICH_EDIT_TEXT_UNICODE, "Héllo wörld")
+            .bytes(TEXT_RUN_INDEX, &run_index(&[5, 6]))
+            .object_ids(TEXT_RUN_FORMATTING, &formatting(3)),
+        vec![Props::new().bool(BOLD, true), Props::new(), Props::new().bool(ITALIC, true)]
+    );
+
+    assert_eq!(text.text, "Héllo wörld");
+    assert_eq!(text.runs, vec![
+        TextRun { range: 0..6, bold: true, ..Default::default() },
+        TextRun { range: 6..7, ..Default::default() },
+        TextRun { range: 7..13, italic: true, ..Default::default() }
+    ]);
+    assert_eq!(text.run_text(&text.runs[2]), "wörld");
+}
+
+#[test]
+fn run_formatting_is_mapped() {
+    let text = decode(
+        Props::new()
+            .bytes(TEXT_EXTENDED_ASCII, b"styled auto\0")
+            .bytes(TEXT_RUN_INDEX, &run_index(&[7]))
+            .object_ids(TEXT_RUN_FORMATTING, &formatting(2))
+            .u32(LANGUAGE_ID, 0x0409),
+        vec![
+            Props::new()
+                .bool(UNDERLINE, true)
+                .string(FONT, "Calibri")
+                .u16(FONT_SIZE, 23)
+                .u32(FONT_COLOR, 0x00336699)
+                .u32(LANGUAGE_ID, 0x0407),
+            // The automatic color is no color
+            Props::new().u32(FONT_COLOR, 0xFF000000)
+        ]
+    );
+
+    // The trailing NUL of single byte text is dropped
+    assert_eq!(text.text, "styled auto");
+    assert_eq!(text.language, Some(0x0409));
+    assert_eq!(text.runs, vec![
+        TextRun { range: 0..7, underline: true, font: Some("Calibri".to_string()), size: Some(11.5), color: Some(0x00336699), language: Some(0x0407), ..Default::default() },
+        TextRun { range: 7..11, language: Some(0x0409), ..Default::default() }
+    ]);
+}
+
+#[test]
+fn paragraph_style_fills_in_what_runs_leave_unset() {
+    let text = decode(
+        Props::new()
+            .string(RICH_EDIT_TEXT_UNICODE, "Heading text")
+            .object_id(PARAGRAPH_STYLE, style_id(0))
+            .bytes(TEXT_RUN_INDEX, &run_index(&[8]))
+            .object_ids(TEXT_RUN_FORMATTING, &[style_id(1), style_id(2)]),
+        vec![
+            Props::new().string(PARAGRAPH_STYLE_ID, "h1").bool(BOLD, true).string(FONT, "Segoe UI").u16(FONT_SIZE, 32),
+            Props::new().bool(ITALIC, true),
+            // A run can turn off what its paragraph turns on
+            Props::new().bool(BOLD, false).u16(FONT_SIZE, 20)
+        ]
+    );
+
+    let style = text.paragraph_style.as_ref().unwrap();
+    assert_eq!(style.style_id.as_deref(), Some("h1"));
+    assert_eq!(style.bold, Some(true));
+    assert_eq!(style.italic, None);
+    assert_eq!(style.size, Some(16.0));
+    assert_eq!(text.runs, vec![
+        TextRun { range: 0..8, bold: true, italic: true, font: Some("Segoe UI".to_string()), size: Some(16.0), ..Default::default() },
+        TextRun { range: 8..12, bold: false, font: Some("Segoe UI".to_string()), size: Some(10.0), ..Default::default() }
+    ]);
+}
+
+#[test]
+fn hyperlink_field_codes_are_removed() {
+    // OneNote stores the field code in a run of its own ahead of the text shown for the link
+    let code_end = 4 + FIELD_CODE.chars().count() as u32;
+    let text = decode(
+        Props::new()
+            .string(RICH_EDIT_TEXT_UNICODE, &format!("See {}Example and more", FIELD_CODE))
+            .bytes(TEXT_RUN_INDEX, &run_index(&[4, code_end, code_end + 7, code_end + 12]))
+            .object_ids(TEXT_RUN_FORMATTING, &formatting(5)),
+        vec![
+            Props::new(),
+            Props::new().bool(HYPERLINK, true),
+            Props::new().bool(HYPERLINK, true).bool(UNDERLINE, true),
+            Props::new(),
+            // A URL on the run itself is used as is
+            Props::new().bool(HYPERLINK, true).string(WZ_HYPERLINK_URL, "onenote:#Other")
+        ]
+    );
+
+    assert_eq!(text.text, "See Example and more");
+    assert_eq!(text.runs, vec![
+        TextRun { range: 0..4, ..Default::default() },
+        TextRun { range: 4..11, underline: true, hyperlink: Some("https://example.com/a".to_string()), ..Default::default() },
+        TextRun { range: 11..16, ..Default::default() },
+        TextRun { range: 16..20, hyperlink: Some("onenote:#Other".to_string()), ..Default::default() }
+    ]);
+}
+
+#[test]
+fn run_ends_past_the_text_are_clamped() {
+    // The second end is past the text and the third goes backwards; neither leaves an empty run
+    let text = decode(
+        Props::new()
+            .string(RICH_EDIT_TEXT_UNICODE, "abcd")
+            .bytes(TEXT_RUN_INDEX, &run_index(&[2, 40, 1]))
+            .object_ids(TEXT_RUN_FORMATTING, &formatting(4)),
+        vec![Props::new().bool(BOLD, true), Props::new().bool(ITALIC, true), Props::new().bool(UNDERLINE, true), Props::new().bool(BOLD, true)]
+    );
+
+    assert_eq!(text.text, "abcd");
+    assert_eq!(text.runs, vec![
+        TextRun { range: 0..2, bold: true, ..Default::default() },
+        TextRun { range: 2..4, italic: true, ..Default::default() }
+    ]);
+}
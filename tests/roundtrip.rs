@@ -0,0 +1,309 @@
+// Parses every sample file, writes it back out with writer::write_store, parses the result and
+// compares the two structure by structure. Samples are read from tests/samples, and also from the
+// directory in ONERNOTE_SAMPLES if it is set, so private notebooks can be checked without adding
+// them to the repository.
+//
+// The samples in tests/samples are generated by samples_match_their_generator from the byte level
+// builders in tests/common, which don't share code with the writer; see tests/samples/README.md.
+//
+// A node's size includes its reference, and the writer may encode a reference with a different
+// stp and cb width than the original. Sizes are compared with the reference's width taken off.
+mod common;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filedatastore::FileDataStoreObject;
+use onernote::structs::filenode::{BaseType, FileNode, FileType};
+use onernote::structs::filenodedata::FileNodeData;
+use onernote::structs::filenodelist::FileNodeList;
+use onernote::structs::header::OneNoteFileHeader;
+use onernote::structs::objectpropset::ObjectSpaceObjectPropSet;
+use onernote::writer::write_store;
+use onernote::OneStore;
+
+// A parsed file and the bytes it was parsed from
+struct Side<'a> {
+    store: OneStore,
+    bytes: &'a [u8]
+}
+
+impl Side<'_> {
+    fn reader(&self) -> Cursor<&[u8]> {
+        Cursor::new(self.bytes)
+    }
+
+    fn list(&self, fcr: &FileChunkReference) -> Result<FileNodeList, String> {
+        FileNodeList::from_reader(fcr, &mut self.reader(), &self.store.transaction_log)
+            .map_err(|e| format!("file node list at {:#x} doesn't parse: {}", fcr.start, e))
+    }
+
+    fn chunk(&self, fcr: &FileChunkReference) -> &[u8] {
+        &self.bytes[fcr.start as usize..(fcr.start + fcr.len) as usize]
+    }
+
+    // The stp and cb widths are read back from the node's header. See MS-ONESTORE 2.4.3
+    fn size_without_reference(&self, node: &FileNode) -> u16 {
+        if node.base_type == BaseType::NoReference {
+            return node.size;
+        }
+        let header = u32::from_le_bytes(self.bytes[node.offset as usize..][..4].try_into().unwrap());
+        let stp_width = [8, 4, 2, 4][(header >> 23 & 0x3) as usize];
+        let cb_width = [4, 8, 1, 2][(header >> 25 & 0x3) as usize];
+        node.size - stp_width - cb_width
+    }
+}
+
+fn round_trip(original: &[u8]) -> Result<(), String> {
+    let store = OneStore::parse(&mut Cursor::new(original)).map_err(|e| format!("doesn't parse: {}", e))?;
+    let mut rewritten = Vec::new();
+    write_store(&store, &mut Cursor::new(original), "rewritten.one", &mut rewritten).map_err(|e| format!("can't be written: {}", e))?;
+    compare(original, &rewritten)
+}
+
+// The first divergence between two files, with the byte offsets it was found at
+fn compare(original: &[u8], rewritten: &[u8]) -> Result<(), String> {
+    let original = Side { store: OneStore::parse(&mut Cursor::new(original)).map_err(|e| format!("doesn't parse: {}", e))?, bytes: original };
+    let rewritten = Side { store: OneStore::parse(&mut Cursor::new(rewritten)).map_err(|e| format!("rewritten file doesn't parse: {}", e))?, bytes: rewritten };
+
+    compare_headers(&original.store.header, &rewritten.store.header)?;
+
+    let mut visited = HashSet::new();
+    compare_lists(&original, &rewritten, &original.store.header.file_node_list_root, &rewritten.store.header.file_node_list_root, &mut visited)?;
+    let (hashed, rewritten_hashed) = (original.store.header.hashed_chunk_list, rewritten.store.header.hashed_chunk_list);
+    if is_empty(&hashed) != is_empty(&rewritten_hashed) {
+        return Err(format!("hashed chunk list at {:#x} is only in one file", hashed.start));
+    }
+    if !is_empty(&hashed) {
+        compare_lists(&original, &rewritten, &hashed, &rewritten_hashed, &mut visited)?;
+    }
+    Ok(())
+}
+
+// Every header field except the ones that locate structures, which the writer lays out anew
+fn compare_headers(original: &OneNoteFileHeader, rewritten: &OneNoteFileHeader) -> Result<(), String> {
+    let layout_removed = |header: &OneNoteFileHeader| {
+        let header = OneNoteFileHeader {
+            transactions_in_log: 0,
+            hashed_chunk_list: FileChunkReference::zero(),
+            transaction_log: FileChunkReference::zero(),
+            file_node_list_root: FileChunkReference::zero(),
+            free_chunk_list: FileChunkReference::zero(),
+            expected_file_length: 0,
+            free_space_in_free_chunk_list: 0,
+            debug_log: FileChunkReference::zero(),
+            alloc_verification_free_chunk_list: FileChunkReference::zero(),
+            // Follows the name the file is written under
+            crc_name: 0,
+            ..header.clone()
+        };
+        let mut bytes = Vec::new();
+        header.to_writer(&mut bytes).unwrap();
+        bytes
+    };
+
+    match first_difference(&layout_removed(original), &layout_removed(rewritten)) {
+        Some(offset) => Err(format!("header differs at offset {:#x}", offset)),
+        None => Ok(())
+    }
+}
+
+fn compare_lists(original: &Side, rewritten: &Side, fcr: &FileChunkReference, rewritten_fcr: &FileChunkReference, visited: &mut HashSet<u32>) -> Result<(), String> {
+    let list = original.list(fcr)?;
+    let rewritten_list = rewritten.list(rewritten_fcr)?;
+    if list.id != rewritten_list.id {
+        return Err(format!("file node list at {:#x} has id {:#x} but its copy at {:#x} has {:#x}", fcr.start, list.id, rewritten_fcr.start, rewritten_list.id));
+    }
+    if !visited.insert(list.id) {
+        return Ok(());
+    }
+    if list.file_nodes.len() != rewritten_list.file_nodes.len() {
+        return Err(format!("file node list at {:#x} has {} nodes but its copy at {:#x} has {}", fcr.start, list.file_nodes.len(), rewritten_fcr.start, rewritten_list.file_nodes.len()));
+    }
+
+    for (node, rewritten_node) in list.file_nodes.iter().zip(&rewritten_list.file_nodes) {
+        compare_nodes(original, rewritten, node, rewritten_node, visited)
+            .map_err(|e| format!("file node at {:#x} (rewritten at {:#x}): {}", node.offset, rewritten_node.offset, e))?;
+    }
+    Ok(())
+}
+
+fn compare_nodes(original: &Side, rewritten: &Side, node: &FileNode, rewritten_node: &FileNode, visited: &mut HashSet<u32>) -> Result<(), String> {
+    if node.file_type != rewritten_node.file_type || node.base_type != rewritten_node.base_type {
+        return Err(format!("{:?} was written as {:?}", node.file_type, rewritten_node.file_type));
+    }
+
+    if let Some(offset) = first_difference(&payload(node), &payload(rewritten_node)) {
+        return Err(format!("{:?} payload differs at offset {:#x}", node.file_type, offset));
+    }
+    let (size, rewritten_size) = (original.size_without_reference(node), rewritten.size_without_reference(rewritten_node));
+    if size != rewritten_size {
+        return Err(format!("{:?} is {} bytes without its reference but was written as {}", node.file_type, size, rewritten_size));
+    }
+
+    let (fcr, rewritten_fcr) = (&node.file_chunk_ref, &rewritten_node.file_chunk_ref);
+    if node.base_type == BaseType::NoReference || is_empty(fcr) && is_empty(rewritten_fcr) {
+        return Ok(());
+    }
+    if is_empty(fcr) != is_empty(rewritten_fcr) {
+        return Err("reference is only nil or zero in one file".to_string());
+    }
+
+    match (&node.data, &rewritten_node.data) {
+        _ if node.base_type == BaseType::FileNodeReference => compare_lists(original, rewritten, fcr, rewritten_fcr, visited),
+        (FileNodeData::FileDataStoreObjectReference(reference), _) => {
+            let guid = reference.guid_reference;
+            let read = |side: &Side, fcr| -> Result<Vec<u8>, String> {
+                let mut reader = side.reader();
+                let object = FileDataStoreObject::from_reader(guid, fcr, &mut reader).map_err(|e| e.to_string())?;
+                let mut data = Vec::new();
+                object.open(&mut reader).map_err(|e| e.to_string())?.read_to_end(&mut data).map_err(|e| e.to_string())?;
+                Ok(data)
+            };
+            let (data, rewritten_data) = (read(original, fcr)?, read(rewritten, rewritten_fcr)?);
+            match first_difference(&data, &rewritten_data) {
+                Some(offset) => Err(format!("file data {} at {:#x} (rewritten at {:#x}) differs at offset {:#x}", guid, fcr.start, rewritten_fcr.start, offset)),
+                None => Ok(())
+            }
+        },
+        _ if holds_property_set(node.file_type) => {
+            let read = |side: &Side, fcr| ObjectSpaceObjectPropSet::from_reader(fcr, &mut side.reader()).map_err(|e| e.to_string());
+            let (property_set, rewritten_property_set) = (read(original, fcr)?, read(rewritten, rewritten_fcr)?);
+            if property_set == rewritten_property_set {
+                return Ok(());
+            }
+            let offset = first_difference(original.chunk(fcr), rewritten.chunk(rewritten_fcr)).unwrap_or(0);
+            Err(format!("property set at {:#x} (rewritten at {:#x}) differs at offset {:#x}", fcr.start, rewritten_fcr.start, offset))
+        },
+        _ => match first_difference(original.chunk(fcr), rewritten.chunk(rewritten_fcr)) {
+            Some(offset) => Err(format!("chunk at {:#x} (rewritten at {:#x}) differs at offset {:#x}", fcr.start, rewritten_fcr.start, offset)),
+            None => Ok(())
+        }
+    }
+}
+
+// The node's body as it would be written, without its header or reference
+fn payload(node: &FileNode) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    node.data.to_writer(&node.file_chunk_ref, &mut bytes).unwrap();
+    bytes
+}
+
+fn holds_property_set(file_type: FileType) -> bool {
+    matches!(file_type,
+        FileType::ObjectDeclarationWithRefCount
+        | FileType::ObjectDeclarationWithRefCount2
+        | FileType::ObjectRevisionWithRefCount
+        | FileType::ObjectRevisionWithRefCount2
+        | FileType::ObjectDeclaration2RefCount
+        | FileType::ObjectDeclaration2LargeRefCount
+        | FileType::ReadOnlyObjectDeclaration2RefCount
+        | FileType::ReadOnlyObjectDeclaration2LargeRefCount
+        | FileType::HashedChunkDescriptor2)
+}
+
+fn is_empty(fcr: &FileChunkReference) -> bool {
+    fcr.is_nil() || fcr.is_zero()
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b).position(|(x, y)| x != y)
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())))
+}
+
+fn samples() -> Vec<PathBuf> {
+    let mut directories = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("samples")];
+    directories.extend(std::env::var_os("ONERNOTE_SAMPLES").map(PathBuf::from));
+
+    let mut paths = Vec::new();
+    for directory in directories {
+        for entry in fs::read_dir(&directory).unwrap_or_else(|e| panic!("{}: {}", directory.display(), e)) {
+            let path = entry.unwrap().path();
+            if matches!(path.extension().and_then(|extension| extension.to_str()), Some("one" | "onetoc2")) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths
+}
+
+// Every file in tests/samples and how it is built. Set ONERNOTE_REGENERATE_SAMPLES to write them
+// out again after changing one
+fn generated_samples() -> Vec<(&'static str, Vec<u8>)> {
+    use common::*;
+
+    const PAGE_GOSID: [u8; 16] = guid_bytes(0x9A6E0001, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+    const FILE_DATA_GUID: [u8; 16] = guid_bytes(0xF11EDA7A, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+
+    let page = page_space(PAGE_GOSID, "Sample page", vec![
+        Object { id: compact_id(1, 0), jcid: JCID_PAGE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(2, 0)]).f32(PAGE_WIDTH, 17.0) },
+        Object { id: compact_id(2, 0), jcid: JCID_OUTLINE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(3, 0)]).f32(OFFSET_FROM_PARENT_HORIZ, 1.0) },
+        Object { id: compact_id(3, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(4, 0)]).object_ids(ELEMENT_CHILD_NODES, &[compact_id(5, 0)]) },
+        Object { id: compact_id(4, 0), jcid: JCID_RICH_TEXT_OE_NODE, props: Props::new().bytes(TEXT_EXTENDED_ASCII, b"Sample page") },
+        Object { id: compact_id(5, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(6, 0)]).u8(OUTLINE_ELEMENT_CHILD_LEVEL, 2) },
+        Object { id: compact_id(6, 0), jcid: JCID_RICH_TEXT_OE_NODE, props: Props::new().string(RICH_EDIT_TEXT_UNICODE, "Nested paragraph") }
+    ]);
+    let attachments = Space {
+        gosid: SECTION_GOSID,
+        guids: vec![SECTION_GOSID],
+        objects: vec![Object { id: compact_id(1, 0), jcid: JCID_SECTION_NODE, props: Props::new() }],
+        roots: vec![(1, compact_id(1, 0))]
+    };
+
+    vec![
+        ("file_data.one", StoreBuilder::one().space(&attachments).file_data(&[(FILE_DATA_GUID, b"attached file contents")]).build()),
+        ("minimal.one", minimal_store()),
+        ("notebook.onetoc2", toc_store(&[("Second.one", Some(2)), ("Sample section.one", Some(1)), ("Unordered.one", None)])),
+        ("section.one", section_store("Sample section", &[page]))
+    ]
+}
+
+#[test]
+fn samples_match_their_generator() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("samples");
+    let regenerate = std::env::var_os("ONERNOTE_REGENERATE_SAMPLES").is_some();
+
+    for (name, bytes) in generated_samples() {
+        let path = directory.join(name);
+        if regenerate {
+            fs::write(&path, &bytes).unwrap();
+        }
+        assert!(fs::read(&path).unwrap() == bytes, "{} doesn't match its generator; set ONERNOTE_REGENERATE_SAMPLES to rewrite it", path.display());
+    }
+}
+
+#[test]
+fn samples_round_trip() {
+    let samples = samples();
+    assert!(!samples.is_empty());
+
+    let failures: Vec<String> = samples.iter()
+        .filter_map(|path| round_trip(&fs::read(path).unwrap()).err().map(|e| format!("{}: {}", path.display(), e)))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn divergence_is_reported_with_its_offset() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("samples").join("section.one");
+    let original = fs::read(path).unwrap();
+    let store = OneStore::parse(&mut Cursor::new(&original)).unwrap();
+    let mut rewritten = Vec::new();
+    write_store(&store, &mut Cursor::new(&original), "section.one", &mut rewritten).unwrap();
+
+    let at = rewritten.windows(11).position(|window| window == b"Sample page").unwrap();
+    rewritten[at] = b's';
+    let divergence = compare(&original, &rewritten).unwrap_err();
+
+    // The property set holding the text is named, and its start plus the offset is the changed byte
+    assert!(divergence.contains("property set at"), "{}", divergence);
+    let hex_after = |prefix: &str| {
+        let rest = &divergence[divergence.rfind(prefix).unwrap() + prefix.len()..];
+        usize::from_str_radix(rest.split(|c: char| !c.is_ascii_hexdigit()).next().unwrap(), 16).unwrap()
+    };
+    assert_eq!(hex_after("(rewritten at 0x") + hex_after("differs at offset 0x"), at, "{}", divergence);
+}
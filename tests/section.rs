@@ -0,0 +1,119 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use onernote::onenote::*;
+use onernote::structs::exguid::ExGuid;
+use onernote::structs::guid::Guid;
+use onernote::{Error, OneStore};
+
+const FIRST_PAGE: [u8; 16] = guid_bytes(0x9A6E0001, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+const SECOND_PAGE: [u8; 16] = guid_bytes(0x9A6E0002, 0x0001, 0x0002, [0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A]);
+
+fn read_section(file: &[u8]) -> onernote::Result<Section> {
+    let store = OneStore::parse(&mut Cursor::new(file)).unwrap();
+    Section::from_store(&store, &mut Cursor::new(file))
+}
+
+fn text_object(id: u32, text: &str) -> Object {
+    Object { id, jcid: JCID_RICH_TEXT_OE_NODE, props: Props::new().string(RICH_EDIT_TEXT_UNICODE, text) }
+}
+
+// A paragraph of plain text as it is read back
+fn paragraph(text: &str, child_level: u8, children: Vec<OutlineItem>) -> OutlineItem {
+    OutlineItem::Element(OutlineElement {
+        child_level,
+        list: None,
+        contents: vec![Content::RichText(RichText { text: text.to_string(), runs: vec![TextRun { range: 0..text.len(), ..Default::default() }], ..Default::default() })],
+        children
+    })
+}
+
+fn outline(offset_horizontal: Option<f32>, items: Vec<OutlineItem>) -> Outline {
+    Outline { offset_horizontal, offset_vertical: None, layout_max_width: None, layout_max_height: None, items }
+}
+
+#[test]
+fn section_tree_is_read() {
+    let first = page_space(FIRST_PAGE, "Cached title", vec![
+        Object { id: compact_id(1, 0), jcid: JCID_PAGE_NODE, props: Props::new()
+            .object_ids(STRUCTURE_ELEMENT_CHILD_NODES, &[compact_id(2, 0)])
+            .object_ids(ELEMENT_CHILD_NODES, &[compact_id(5, 0)])
+            .f32(PAGE_WIDTH, 17.5) },
+        Object { id: compact_id(2, 0), jcid: JCID_TITLE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(3, 0)]).f32(OFFSET_FROM_PARENT_VERT, 0.5) },
+        Object { id: compact_id(3, 0), jcid: JCID_OUTLINE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(4, 0)]) },
+        Object { id: compact_id(4, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(20, 0)]) },
+        text_object(compact_id(20, 0), "Title"),
+        Object { id: compact_id(5, 0), jcid: JCID_OUTLINE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(6, 0), compact_id(8, 0)]).f32(OFFSET_FROM_PARENT_HORIZ, 1.25) },
+        Object { id: compact_id(6, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new()
+            .object_ids(CONTENT_CHILD_NODES, &[compact_id(21, 0)])
+            .object_ids(ELEMENT_CHILD_NODES, &[compact_id(7, 0)]) },
+        text_object(compact_id(21, 0), "Parent"),
+        Object { id: compact_id(7, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(22, 0)]).u8(OUTLINE_ELEMENT_CHILD_LEVEL, 2) },
+        text_object(compact_id(22, 0), "Child"),
+        Object { id: compact_id(8, 0), jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(CONTENT_CHILD_NODES, &[compact_id(23, 0)]) },
+        text_object(compact_id(23, 0), "Sibling")
+    ]);
+    let second = page_space(SECOND_PAGE, "Empty page", vec![
+        Object { id: compact_id(1, 0), jcid: JCID_PAGE_NODE, props: Props::new() }
+    ]);
+
+    let section = read_section(&section_store("Sample section", &[first, second])).unwrap();
+    let page_id = |guid| ExGuid { guid: Guid::from_bytes_le(guid), n: 1 };
+    assert_eq!(section, Section {
+        display_name: Some("Sample section".to_string()),
+        page_series: vec![PageSeries { pages: vec![
+            Page {
+                id: page_id(FIRST_PAGE),
+                title: Some(Title { offset_horizontal: None, offset_vertical: Some(0.5), contents: vec![outline(None, vec![paragraph("Title", 1, vec![])])] }),
+                cached_title: Some("Cached title".to_string()),
+                level: 1,
+                width: Some(17.5),
+                height: None,
+                contents: vec![PageContent::Outline(outline(Some(1.25), vec![
+                    paragraph("Parent", 1, vec![paragraph("Child", 2, vec![])]),
+                    paragraph("Sibling", 1, vec![])
+                ]))]
+            },
+            Page { id: page_id(SECOND_PAGE), title: None, cached_title: Some("Empty page".to_string()), level: 1, width: None, height: None, contents: vec![] }
+        ] }]
+    });
+    assert_eq!(section.pages().map(|page| page.title_text().unwrap()).collect::<Vec<_>>(), vec!["Title", "Empty page"]);
+}
+
+// A page whose outline holds elements nested levels deep, each the only child of the one before
+fn nested_page(levels: u8) -> Vec<u8> {
+    let mut objects = vec![
+        Object { id: compact_id(1, 0), jcid: JCID_PAGE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(2, 0)]) },
+        Object { id: compact_id(2, 0), jcid: JCID_OUTLINE_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, &[compact_id(3, 0)]) }
+    ];
+    for level in 0..levels {
+        let id = compact_id(3 + level, 0);
+        let children: &[u32] = if level + 1 < levels { &[compact_id(4 + level, 0)] } else { &[] };
+        objects.push(Object { id, jcid: JCID_OUTLINE_ELEMENT_NODE, props: Props::new().object_ids(ELEMENT_CHILD_NODES, children) });
+    }
+    section_store("Nested", &[page_space(FIRST_PAGE, "Nested", objects)])
+}
+
+fn depth(items: &[OutlineItem]) -> usize {
+    match items.first() {
+        Some(OutlineItem::Element(element)) => 1 + depth(&element.children),
+        _ => 0
+    }
+}
+
+#[test]
+fn outline_nesting_is_limited() {
+    // Top level elements are at depth 0, so 65 levels reach the limit of 64 without passing it
+    let section = read_section(&nested_page(65)).unwrap();
+    match &section.pages().next().unwrap().contents[..] {
+        [PageContent::Outline(outline)] => assert_eq!(depth(&outline.items), 65),
+        other => panic!("expected one outline, got {:?}", other)
+    }
+
+    match read_section(&nested_page(66)) {
+        Err(Error::InvalidData { structure: "OutlineElementNode", reason, .. }) => assert!(reason.contains("nested too deeply"), "{}", reason),
+        other => panic!("expected the nesting to be rejected, got {:?}", other)
+    }
+}
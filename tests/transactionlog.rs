@@ -0,0 +1,95 @@
+use std::io::Cursor;
+
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::transactionlog::{read_with_tail, write_transaction};
+use onernote::Error;
+
+// Transactions as written, then nextFragment
+fn log_fragment(transactions: &[&[(u32, u32)]], next: Option<(u64, u32)>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for entries in transactions {
+        write_transaction(&mut bytes, entries).unwrap();
+    }
+    let (next_start, next_len) = next.unwrap_or((u64::MAX, 0));
+    bytes.extend_from_slice(&next_start.to_le_bytes());
+    bytes.extend_from_slice(&next_len.to_le_bytes());
+    bytes
+}
+
+fn fcr(start: usize, bytes: &[u8]) -> FileChunkReference {
+    FileChunkReference { start: start as u64, len: bytes.len() as u64 }
+}
+
+const FIRST: &[&[(u32, u32)]] = &[&[(0x10, 2), (0x11, 1)], &[(0x11, 3)]];
+const SECOND: &[&[(u32, u32)]] = &[&[(0x10, 5)]];
+
+// Three transactions, the first two in one fragment and the last in the next
+fn split_log() -> (Vec<u8>, FileChunkReference, FileChunkReference) {
+    let second = log_fragment(SECOND, None);
+    let first_len = log_fragment(FIRST, None).len();
+    let first = log_fragment(FIRST, Some((first_len as u64, second.len() as u32)));
+    let (first_fcr, second_fcr) = (fcr(0, &first), fcr(first_len, &second));
+    ([first, second].concat(), first_fcr, second_fcr)
+}
+
+// Flips a bit in the CRC held by the sentinel entry at offset
+fn corrupt_sentinel(file: &mut [u8], offset: u64) {
+    assert_eq!(file[offset as usize..][..4], 1u32.to_le_bytes());
+    file[offset as usize + 4] ^= 1;
+}
+
+#[test]
+fn log_is_read_across_fragments() {
+    let (file, first, second) = split_log();
+    let (log, tail) = read_with_tail(&first, &mut Cursor::new(&file), 3).unwrap();
+
+    assert_eq!(log[&0x10], 5);
+    assert_eq!(log[&0x11], 3);
+    assert_eq!(tail.transactions, 3);
+    assert_eq!(tail.fragment, second);
+    assert_eq!(tail.end_of_transactions, second.start + 16);
+}
+
+#[test]
+fn torn_last_transaction_is_discarded() {
+    let (mut file, first, second) = split_log();
+    corrupt_sentinel(&mut file, second.start + 8);
+    let (log, tail) = read_with_tail(&first, &mut Cursor::new(&file), 3).unwrap();
+
+    assert_eq!(log[&0x10], 2);
+    assert_eq!(tail.transactions, 2);
+    assert_eq!(tail.fragment, first);
+    assert_eq!(tail.end_of_transactions, first.start + 40);
+}
+
+#[test]
+fn last_transaction_without_sentinel_is_discarded() {
+    // The sentinel never reached the file, leaving the zeros it would have overwritten
+    let (mut file, first, second) = split_log();
+    let sentinel = second.start as usize + 8;
+    file[sentinel..sentinel + 8].fill(0);
+    let (log, tail) = read_with_tail(&first, &mut Cursor::new(&file), 3).unwrap();
+
+    assert_eq!(log[&0x10], 2);
+    assert_eq!(tail.transactions, 2);
+    assert_eq!(tail.fragment, first);
+    assert_eq!(tail.end_of_transactions, first.start + 40);
+
+    // The same for a chain that points past the end of the file
+    let (mut file, first, _) = split_log();
+    file.truncate(first.len as usize);
+    let (_, tail) = read_with_tail(&first, &mut Cursor::new(&file), 3).unwrap();
+    assert_eq!(tail.transactions, 2);
+}
+
+#[test]
+fn corrupt_earlier_transaction_is_an_error() {
+    let (mut file, first, _) = split_log();
+    let sentinel = first.start + 16;
+    corrupt_sentinel(&mut file, sentinel);
+
+    match read_with_tail(&first, &mut Cursor::new(&file), 3) {
+        Err(Error::CrcMismatch { structure: "TransactionLogFragment", offset, .. }) => assert_eq!(offset, sentinel),
+        other => panic!("expected a CRC mismatch, got {:?}", other.map(|(_, tail)| tail))
+    }
+}
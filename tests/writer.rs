@@ -0,0 +1,104 @@
+mod common;
+
+use std::io::Cursor;
+
+use onernote::structs::compactid::CompactId;
+use onernote::structs::filechunkreference::FileChunkReference;
+use onernote::structs::filenode::FileType;
+use onernote::structs::filenodedata::*;
+use onernote::structs::objectpropset::ObjectSpaceObjectPropSet;
+use onernote::structs::propertyset::{PropertyId, PropertySet, PropertyValue};
+use onernote::writer::write_store;
+use onernote::OneStore;
+
+fn rewrite(file: &[u8]) -> Vec<u8> {
+    let mut reader = Cursor::new(file);
+    let store = OneStore::parse(&mut reader).unwrap();
+    let mut out = Vec::new();
+    write_store(&store, &mut reader, "Rewritten.one", &mut out).unwrap();
+    out
+}
+
+fn id(n: u8, guid_index: u32) -> CompactId {
+    CompactId { n, guid_index }
+}
+
+#[test]
+fn rewritten_store_parses_the_same() {
+    let file = common::minimal_store();
+    let original = OneStore::parse(&mut Cursor::new(&file)).unwrap();
+    let rewritten = rewrite(&file);
+    let store = OneStore::parse(&mut Cursor::new(&rewritten)).unwrap();
+
+    assert_eq!(store.header.file_guid, original.header.file_guid);
+    assert_eq!(store.header.file_version, original.header.file_version);
+    assert_eq!(store.header.expected_file_length, rewritten.len() as u64);
+    assert!(store.header.verify_name("Rewritten.one").is_ok());
+    assert_eq!(store.transaction_log, original.transaction_log);
+    assert_eq!(store.root_object_space_id, original.root_object_space_id);
+    let (space, original_space) = (store.root_object_space().unwrap(), original.root_object_space().unwrap());
+    assert_eq!(space.latest_revision().unwrap().rid, original_space.latest_revision().unwrap().rid);
+
+    // Writing is deterministic, so a rewritten file rewrites to itself
+    assert_eq!(rewrite(&rewritten), rewritten);
+}
+
+#[test]
+fn property_sets_round_trip() {
+    let nested = PropertySet { properties: vec![(PropertyId(0x14001C01), PropertyValue::FourBytes(7))] };
+    let property_set = ObjectSpaceObjectPropSet {
+        oids: vec![id(1, 2), id(3, 4)],
+        osids: vec![id(5, 6)],
+        context_ids: vec![id(7, 8)],
+        body: PropertySet { properties: vec![
+            (PropertyId(0x08001C02 | 0x80000000), PropertyValue::Bool(true)),
+            (PropertyId(0x1C001C03), PropertyValue::Bytes(b"text".to_vec())),
+            (PropertyId(0x20001C04), PropertyValue::ObjectId(id(1, 2))),
+            (PropertyId(0x24001C05), PropertyValue::ObjectIds(vec![id(3, 4)])),
+            (PropertyId(0x28001C06), PropertyValue::ObjectSpaceId(id(5, 6))),
+            (PropertyId(0x34001C07), PropertyValue::ContextIds(vec![id(7, 8)])),
+            (PropertyId(0x40001C08), PropertyValue::PropertyValues(PropertyId(0x44000000), vec![nested.clone(), nested])),
+            (PropertyId(0x40001C09), PropertyValue::PropertyValues(PropertyId(0), Vec::new()))
+        ] }
+    };
+
+    let mut bytes = Vec::new();
+    property_set.to_writer(&mut bytes).unwrap();
+    assert_eq!(bytes.len() % 8, 0);
+
+    let fcr = FileChunkReference { start: 0, len: bytes.len() as u64 };
+    assert_eq!(ObjectSpaceObjectPropSet::from_reader(&fcr, &mut Cursor::new(bytes)).unwrap(), property_set);
+
+    let only_oids = ObjectSpaceObjectPropSet { oids: vec![id(1, 1)], ..Default::default() };
+    let mut bytes = Vec::new();
+    only_oids.to_writer(&mut bytes).unwrap();
+    // The OSIDs stream is left out, which the OIDs stream header says
+    assert_eq!(u32::from_le_bytes(bytes[..4].try_into().unwrap()), 1 | 1 << 31);
+}
+
+#[test]
+fn values_too_large_for_their_bit_fields_are_rejected() {
+    let write = |data: &FileNodeData| data.to_writer(&FileChunkReference::zero(), &mut Vec::new());
+    let revision = |c_ref| FileNodeData::ObjectRevisionWithRefCount(ObjectRevisionWithRefCountFndx { oid: id(1, 1), has_oid_references: true, has_osid_references: false, c_ref });
+
+    // cRef takes the top 6 bits of the byte holding the reference flags
+    let mut bytes = Vec::new();
+    revision(0x3F).to_writer(&FileChunkReference::zero(), &mut bytes).unwrap();
+    assert_eq!(bytes[4], 0x3F << 2 | 1);
+    match FileNodeData::from_reader(FileType::ObjectRevisionWithRefCount, &FileChunkReference::zero(), &mut Cursor::new(bytes)).unwrap() {
+        FileNodeData::ObjectRevisionWithRefCount(revision) => assert_eq!(revision.c_ref, 0x3F),
+        other => panic!("read back as {:?}", other)
+    }
+    assert!(write(&revision(0x40)).is_err());
+
+    let declaration = |jci, odc| FileNodeData::ObjectDeclarationWithRefCount(ObjectDeclarationWithRefCountFndx {
+        body: ObjectDeclarationWithRefCountBody { oid: id(1, 1), jci, odc, has_oid_references: false, has_osid_references: false },
+        c_ref: 1
+    });
+    assert!(write(&declaration(0x3FF, 0xF)).is_ok());
+    assert!(write(&declaration(0x400, 0)).is_err());
+    assert!(write(&declaration(0, 0x10)).is_err());
+
+    assert!(id(1, 0xFFFFFF).to_writer(&mut Vec::new()).is_ok());
+    assert!(id(1, 0x1000000).to_writer(&mut Vec::new()).is_err());
+}